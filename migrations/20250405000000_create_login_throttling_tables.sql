@@ -0,0 +1,25 @@
+-- Migration: Create login throttling tables
+-- Failed login attempts are tracked per account (username) and per client address.
+CREATE TABLE IF NOT EXISTS login_throttles (
+    scope VARCHAR(16) NOT NULL, -- 'account' or 'ip'
+    key TEXT NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ NOT NULL,
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (scope, key)
+);
+
+-- Lockouts are kept for support, who can lift account lockouts through the admin API.
+CREATE TABLE IF NOT EXISTS lockout_events (
+    id UUID PRIMARY KEY NOT NULL,
+    scope VARCHAR(16) NOT NULL,
+    key TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    failed_attempts INTEGER NOT NULL,
+    locked_until TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    unlocked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_lockout_events_user_id ON lockout_events(user_id);
+CREATE INDEX IF NOT EXISTS idx_lockout_events_created_at ON lockout_events(created_at);
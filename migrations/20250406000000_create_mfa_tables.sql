@@ -0,0 +1,23 @@
+-- Migration: Create tables for TOTP two-factor authentication
+-- A row without confirmed_at is an enrollment that hasn't been confirmed with a first code yet.
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    secret VARCHAR(64) NOT NULL, -- Base32 encoded shared secret
+    confirmed_at TIMESTAMPTZ,
+    last_used_step BIGINT, -- Time step of the last accepted code, so codes can't be replayed
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Single-use recovery codes, only stored hashed.
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+    id UUID PRIMARY KEY NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL, -- SHA-256 hex digest of the normalized code
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_mfa_recovery_codes_user_id ON mfa_recovery_codes(user_id);
+
+-- Refreshed access tokens keep the MFA status of the login they belong to
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS mfa BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,8 @@
+-- Migration: Create user_roles table
+-- Users without any row here are regular users.
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(32) NOT NULL CHECK (role IN ('admin', 'clinician')),
+    granted_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, role)
+);
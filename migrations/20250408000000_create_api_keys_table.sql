@@ -0,0 +1,15 @@
+-- Migration: Create api_keys table
+-- Long-lived keys for devices that upload sensor data on behalf of a user.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL, -- Shown in listings so users can tell keys apart
+    key_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 hex digest of the key
+    scopes TEXT[] NOT NULL, -- Data types the key may upload
+    created_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
@@ -0,0 +1,23 @@
+-- Migration: Add account deletion
+-- The inline references created alongside the named cascading constraints
+-- don't cascade and would block deleting users.
+ALTER TABLE health_data DROP CONSTRAINT IF EXISTS health_data_user_id_fkey;
+ALTER TABLE processed_sleep_data DROP CONSTRAINT IF EXISTS processed_sleep_data_user_id_fkey;
+
+-- Accounts are only hard-deleted once the grace period is over and can be restored until then
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_requested_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_for TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_receipt_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled_for ON users(deletion_scheduled_for)
+    WHERE deletion_scheduled_for IS NOT NULL;
+
+-- Proof that an account was erased. Holds no personal data; the id is handed
+-- to the user when the deletion is requested.
+CREATE TABLE IF NOT EXISTS account_deletion_receipts (
+    id UUID PRIMARY KEY NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL,
+    health_data_records BIGINT NOT NULL,
+    sleep_data_records BIGINT NOT NULL
+);
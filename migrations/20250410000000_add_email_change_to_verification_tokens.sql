@@ -0,0 +1,4 @@
+-- Migration: Support email changes
+-- Set when the token confirms a change to a new address, which is only
+-- applied to the user once verified.
+ALTER TABLE email_verification_tokens ADD COLUMN IF NOT EXISTS email TEXT;
@@ -0,0 +1,16 @@
+-- Migration: Create user_identities table
+-- External (OpenID Connect) identities a user can log in with. An identity is
+-- the subject at an issuer and belongs to exactly one account.
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID PRIMARY KEY NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(50) NOT NULL,  -- Configured provider name, e.g. "google"
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    email TEXT,                     -- As reported by the provider when linked
+    created_at TIMESTAMPTZ NOT NULL,
+    last_login_at TIMESTAMPTZ,
+    UNIQUE (issuer, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_identities_user_id ON user_identities(user_id);
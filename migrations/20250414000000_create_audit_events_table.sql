@@ -0,0 +1,31 @@
+-- Migration: Create audit_events table
+-- Security-relevant events for accountability. Events have no foreign keys,
+-- so they outlive erased accounts, and can only be appended.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id UUID PRIMARY KEY NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    user_id UUID,       -- Whose account or data the event concerns
+    actor_id UUID,      -- Who caused it; NULL for unknown clients and the system
+    ip_address TEXT,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_user_id ON audit_events(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_events_created_at ON audit_events(created_at DESC);
+
+CREATE OR REPLACE FUNCTION reject_audit_event_changes() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_events_append_only ON audit_events;
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION reject_audit_event_changes();
+
+DROP TRIGGER IF EXISTS audit_events_no_truncate ON audit_events;
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_audit_event_changes();
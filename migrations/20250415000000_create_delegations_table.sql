@@ -0,0 +1,17 @@
+-- Migration: Create delegations table
+-- Read access to some of a user's health data, granted to another account.
+-- Takes effect once the delegate accepts and ends at expires_at or when either
+-- side revokes it.
+CREATE TABLE IF NOT EXISTS delegations (
+    id UUID PRIMARY KEY NOT NULL,
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    delegate_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    data_types TEXT[] NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_delegations_owner_id ON delegations(owner_id);
+CREATE INDEX IF NOT EXISTS idx_delegations_delegate_id ON delegations(delegate_id);
@@ -0,0 +1,18 @@
+-- Migration: Create share_links table
+-- Read-only links to some of a user's data, e.g. for a doctor without an
+-- account. Only a hash of the token is stored, like for API keys.
+CREATE TABLE IF NOT EXISTS share_links (
+    id UUID PRIMARY KEY NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 hex digest of the token
+    data_types TEXT[] NOT NULL,
+    start_date DATE NOT NULL,               -- Shared period, both days included
+    end_date DATE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    access_count INTEGER NOT NULL DEFAULT 0,
+    last_accessed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_share_links_user_id ON share_links(user_id);
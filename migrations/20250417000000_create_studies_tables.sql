@@ -0,0 +1,45 @@
+-- Migration: Create organizations and studies tables
+-- Partner organizations run studies; their admins manage the studies and read
+-- the data participants consented to share within each study's window.
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY NOT NULL,
+    name VARCHAR(200) UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS organization_admins (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_organization_admins_user_id ON organization_admins(user_id);
+
+CREATE TABLE IF NOT EXISTS studies (
+    id UUID PRIMARY KEY NOT NULL,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(200) NOT NULL,
+    description TEXT,
+    data_types TEXT[] NOT NULL,            -- Data types participants have to share
+    start_date DATE NOT NULL,              -- Study window, both days included
+    end_date DATE NOT NULL,
+    invite_code VARCHAR(16) UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_studies_organization_id ON studies(organization_id);
+
+-- A participant's consent is their permissions_settings row; it has to keep
+-- covering the study's data types for their data to be readable.
+CREATE TABLE IF NOT EXISTS study_enrollments (
+    id UUID PRIMARY KEY NOT NULL,
+    study_id UUID NOT NULL REFERENCES studies(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    permissions_settings_id UUID NOT NULL REFERENCES permissions_settings(id) ON DELETE CASCADE,
+    enrolled_at TIMESTAMPTZ NOT NULL,
+    withdrawn_at TIMESTAMPTZ,
+    UNIQUE (study_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_study_enrollments_user_id ON study_enrollments(user_id);
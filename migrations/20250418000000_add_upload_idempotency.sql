@@ -0,0 +1,9 @@
+-- Migration: Idempotent health data uploads
+-- Clients can tag an upload with their own id so that retries after a timeout
+-- don't store it twice.
+ALTER TABLE health_data ADD COLUMN IF NOT EXISTS client_upload_id VARCHAR(255);
+ALTER TABLE health_data ADD COLUMN IF NOT EXISTS content_hash VARCHAR(64); -- SHA-256 hex digest of the upload
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_health_data_client_upload_id
+    ON health_data(user_id, client_upload_id) WHERE client_upload_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_health_data_content_hash ON health_data(user_id, content_hash);
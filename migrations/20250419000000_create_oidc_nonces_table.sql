@@ -0,0 +1,11 @@
+-- Migration: Create oidc_nonces table
+-- Nonces handed out before a sign-in with an identity provider. The ID token
+-- has to carry one of them, which is deleted when the token is used, so a
+-- token can't be replayed.
+CREATE TABLE IF NOT EXISTS oidc_nonces (
+    nonce_hash TEXT PRIMARY KEY NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oidc_nonces_expires_at ON oidc_nonces(expires_at);
@@ -1,16 +1,217 @@
-use secrecy::SecretString;
+use std::io::{Error, ErrorKind};
 
-#[derive(Debug)]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use ring::signature::{Ed25519KeyPair, KeyPair, RsaKeyPair};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::settings::{JwtAlgorithm, JwtKeyConfig};
+
+/// An asymmetric key used to sign and verify tokens, selected by its `kid`.
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+    active_from: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKey {
+    fn load(config: &JwtKeyConfig) -> Result<Self, Error> {
+        let invalid_key = |e: String| Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid JWT key {}: {}", config.kid, e)
+        );
+
+        let pem_bytes = std::fs::read(&config.private_key_path)?;
+        let pem = pem::parse(&pem_bytes).map_err(|e| invalid_key(e.to_string()))?;
+
+        let (algorithm, encoding_key, parameters) = match config.algorithm {
+            JwtAlgorithm::RS256 => {
+                let key_pair = match pem.tag() {
+                    "RSA PRIVATE KEY" => RsaKeyPair::from_der(pem.contents()),
+                    _ => RsaKeyPair::from_pkcs8(pem.contents()),
+                }
+                .map_err(|e| invalid_key(e.to_string()))?;
+                let public_key: ring::rsa::PublicKeyComponents<Vec<u8>> = key_pair.public().into();
+                (
+                    Algorithm::RS256,
+                    EncodingKey::from_rsa_pem(&pem_bytes).map_err(|e| invalid_key(e.to_string()))?,
+                    AlgorithmParameters::RSA(RSAKeyParameters {
+                        key_type: RSAKeyType::RSA,
+                        n: URL_SAFE_NO_PAD.encode(public_key.n),
+                        e: URL_SAFE_NO_PAD.encode(public_key.e),
+                    }),
+                )
+            }
+            JwtAlgorithm::EdDSA => {
+                let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pem.contents())
+                    .map_err(|e| invalid_key(e.to_string()))?;
+                (
+                    Algorithm::EdDSA,
+                    EncodingKey::from_ed_pem(&pem_bytes).map_err(|e| invalid_key(e.to_string()))?,
+                    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(key_pair.public_key()),
+                    }),
+                )
+            }
+        };
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match config.algorithm {
+                    JwtAlgorithm::RS256 => KeyAlgorithm::RS256,
+                    JwtAlgorithm::EdDSA => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(config.kid.clone()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+        // Verify with the published key, so the JWKS can't drift from what we accept
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|e| invalid_key(e.to_string()))?;
+
+        Ok(Self {
+            kid: config.kid.clone(),
+            algorithm,
+            encoding_key,
+            decoding_key,
+            jwk,
+            active_from: config.active_from,
+            expires_at: config.expires_at,
+        })
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Signs and verifies tokens.
+///
+/// Tokens are signed with the configured key that became active last, and carry
+/// its `kid`. Keys are published in the JWKS from startup until they expire, so
+/// consumers know a key before it's used and after it was replaced. While no key
+/// is active, tokens are signed with the shared HS256 secret and without a `kid`.
+///
+/// Once keys are configured, tokens without a `kid` are only accepted while no
+/// key is active yet, unless legacy HS256 tokens are explicitly allowed.
 pub struct JwtSettings {
-    pub secret: SecretString,
-    pub expiration_hours: i64,
+    secret: SecretString,
+    keys: Vec<SigningKey>,
+    accept_legacy_hs256: Option<bool>,
+    pub access_token_expiration_minutes: i64,
+    pub refresh_token_expiration_days: i64,
 }
 
 impl JwtSettings {
-    pub fn new(secret: String, expiration_hours: i64) -> Self {
+    pub fn new(
+        secret: String,
+        access_token_expiration_minutes: i64,
+        refresh_token_expiration_days: i64
+    ) -> Self {
         Self {
             secret: SecretString::new(secret.into_boxed_str()),
-            expiration_hours,
+            keys: Vec::new(),
+            accept_legacy_hs256: None,
+            access_token_expiration_minutes,
+            refresh_token_expiration_days,
         }
     }
-}
\ No newline at end of file
+
+    /// Loads the asymmetric keys from their PEM files.
+    pub fn with_keys(mut self, keys: &[JwtKeyConfig]) -> Result<Self, Error> {
+        for key in keys {
+            if self.keys.iter().any(|loaded| loaded.kid == key.kid) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Duplicate JWT key id {}", key.kid)
+                ));
+            }
+            self.keys.push(SigningKey::load(key)?);
+        }
+        Ok(self)
+    }
+
+    /// Whether tokens without a `kid` are verified with the shared secret.
+    /// `None` accepts them only if no keys are configured.
+    pub fn accept_legacy_hs256(mut self, accept: Option<bool>) -> Self {
+        self.accept_legacy_hs256 = accept;
+        self
+    }
+
+    fn signing_key(&self, now: DateTime<Utc>) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .filter(|key| key.active_from <= now && !key.is_expired(now))
+            .max_by_key(|key| key.active_from)
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+        match self.signing_key(Utc::now()) {
+            Some(key) => {
+                let mut header = Header::new(key.algorithm);
+                header.kid = Some(key.kid.clone());
+                encode(&header, claims, &key.encoding_key)
+            }
+            None => encode(
+                &Header::default(),
+                claims,
+                &EncodingKey::from_secret(self.secret.expose_secret().as_bytes()),
+            ),
+        }
+    }
+
+    /// Decodes and validates a token signed with the key named by its `kid`,
+    /// or with the shared secret if it has none and those are accepted.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, jsonwebtoken::errors::Error> {
+        let header = decode_header(token)?;
+        let now = Utc::now();
+        let token_data = match header.kid {
+            Some(kid) => {
+                let key = self.keys
+                    .iter()
+                    .find(|key| key.kid == kid && !key.is_expired(now))
+                    .ok_or(jsonwebtoken::errors::ErrorKind::InvalidSignature)?;
+                decode::<T>(token, &key.decoding_key, &Validation::new(key.algorithm))?
+            }
+            None => {
+                // While no key is active we sign with the secret ourselves
+                let accept = self.accept_legacy_hs256.unwrap_or(self.keys.is_empty())
+                    || self.signing_key(now).is_none();
+                if !accept {
+                    return Err(jsonwebtoken::errors::ErrorKind::InvalidSignature.into());
+                }
+                decode::<T>(
+                    token,
+                    &DecodingKey::from_secret(self.secret.expose_secret().as_bytes()),
+                    &Validation::new(Algorithm::HS256),
+                )?
+            }
+        };
+        Ok(token_data.claims)
+    }
+
+    /// The public keys that tokens may currently be signed with.
+    pub fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        JwkSet {
+            keys: self.keys
+                .iter()
+                .filter(|key| !key.is_expired(now))
+                .map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
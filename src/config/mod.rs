@@ -1,2 +1,3 @@
 pub mod settings;
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod oidc;
\ No newline at end of file
@@ -0,0 +1,178 @@
+use std::fmt;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use crate::config::settings::{OidcConfig, OidcProviderConfig};
+
+// Providers rotate their keys rarely, but publish new ones ahead of time
+const JWKS_CACHE_DURATION: Duration = Duration::from_secs(3600);
+// Unknown key ids trigger a refetch, but not more often than this
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const JWKS_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum OidcError {
+    UnknownProvider,
+    InvalidToken(String),
+    KeysUnavailable(String),
+}
+
+impl fmt::Display for OidcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OidcError::UnknownProvider => f.write_str("Unknown identity provider"),
+            OidcError::InvalidToken(reason) => write!(f, "Invalid ID token: {}", reason),
+            OidcError::KeysUnavailable(reason) => write!(f, "Failed to fetch the provider's keys: {}", reason),
+        }
+    }
+}
+
+/// The identity asserted by a verified ID token.
+#[derive(Debug)]
+pub struct ExternalIdentity {
+    pub provider: String,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    // Issued by us before the sign-in, see `oidc_handler::issue_nonce`
+    pub nonce: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    #[serde(default)]
+    email: Option<String>,
+    // Apple sends this as a string
+    #[serde(default)]
+    email_verified: Option<serde_json::Value>,
+    #[serde(default)]
+    nonce: Option<String>,
+}
+
+struct CachedKeys {
+    jwks: JwkSet,
+    fetched_at: Instant,
+}
+
+struct Provider {
+    config: OidcProviderConfig,
+    keys: RwLock<Option<CachedKeys>>,
+}
+
+/// Verifies ID tokens of the configured OpenID Connect providers against the
+/// keys they publish. The keys are cached, and fetched again when a token
+/// names a key we don't know yet.
+pub struct OidcSettings {
+    providers: Vec<Provider>,
+    client: reqwest::Client,
+}
+
+impl OidcSettings {
+    pub fn new(config: &OidcConfig) -> Self {
+        Self {
+            providers: config.providers
+                .iter()
+                .map(|provider| Provider { config: provider.clone(), keys: RwLock::new(None) })
+                .collect(),
+            client: reqwest::Client::builder()
+                .timeout(JWKS_FETCH_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+        }
+    }
+
+    pub async fn verify(&self, provider_name: &str, id_token: &str) -> Result<ExternalIdentity, OidcError> {
+        let provider = self.providers
+            .iter()
+            .find(|provider| provider.config.name == provider_name)
+            .ok_or(OidcError::UnknownProvider)?;
+
+        let header = decode_header(id_token).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+        // Shared secrets would let anyone who knows the client id forge tokens
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(OidcError::InvalidToken("symmetric algorithms are not accepted".to_string()));
+        }
+        let kid = header.kid
+            .ok_or_else(|| OidcError::InvalidToken("missing key id".to_string()))?;
+        let jwk = self.find_key(provider, &kid).await?;
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&provider.config.issuer]);
+        validation.set_audience(&[&provider.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .map_err(|e| OidcError::InvalidToken(e.to_string()))?
+            .claims;
+
+        // Without a nonce the token isn't bound to a sign-in and could be replayed
+        let nonce = claims.nonce
+            .ok_or_else(|| OidcError::InvalidToken("missing nonce".to_string()))?;
+
+        let email_verified = match claims.email_verified {
+            Some(serde_json::Value::Bool(verified)) => verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+
+        Ok(ExternalIdentity {
+            provider: provider.config.name.clone(),
+            issuer: claims.iss,
+            subject: claims.sub,
+            email: claims.email,
+            email_verified,
+            nonce,
+        })
+    }
+
+    async fn find_key(&self, provider: &Provider, kid: &str) -> Result<Jwk, OidcError> {
+        let (cached_key, refresh) = {
+            let keys = provider.keys.read().unwrap();
+            match keys.as_ref() {
+                Some(cached) => {
+                    let age = cached.fetched_at.elapsed();
+                    let key = cached.jwks.find(kid).cloned();
+                    let refresh = age > JWKS_CACHE_DURATION
+                        || (key.is_none() && age > JWKS_MIN_REFRESH_INTERVAL);
+                    (key, refresh)
+                }
+                None => (None, true),
+            }
+        };
+
+        if !refresh {
+            return cached_key.ok_or_else(|| OidcError::InvalidToken("unknown key id".to_string()));
+        }
+
+        let jwks = match self.fetch_keys(&provider.config.jwks_uri).await {
+            Ok(jwks) => jwks,
+            // Keep using the cached keys if the provider is temporarily unreachable
+            Err(e) => return cached_key.ok_or(e),
+        };
+        let key = jwks.find(kid).cloned();
+        *provider.keys.write().unwrap() = Some(CachedKeys { jwks, fetched_at: Instant::now() });
+
+        key.ok_or_else(|| OidcError::InvalidToken("unknown key id".to_string()))
+    }
+
+    async fn fetch_keys(&self, jwks_uri: &str) -> Result<JwkSet, OidcError> {
+        let unavailable = |e: reqwest::Error| OidcError::KeysUnavailable(e.to_string());
+        self.client
+            .get(jwks_uri)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(unavailable)?
+            .json::<JwkSet>()
+            .await
+            .map_err(unavailable)
+    }
+}
@@ -1,4 +1,5 @@
 use std::env;
+use chrono::{DateTime, Utc};
 use config::{Config, File, ConfigError};
 use dotenv::dotenv;
 use secrecy::{ExposeSecret, SecretString};
@@ -9,13 +10,119 @@ use crate::config::jwt::JwtSettings;
 pub struct Settings{
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
-    pub jwt: JwtConfig
+    pub jwt: JwtConfig,
+    pub password_hashing: PasswordHashingSettings,
+    pub email: EmailSettings,
+    pub login_throttling: LoginThrottlingSettings,
+    pub account_deletion: AccountDeletionSettings,
+    #[serde(default)]
+    pub oidc: OidcConfig
 }
 
 #[derive(serde::Deserialize, Debug)]
 pub struct JwtConfig {
     pub secret: SecretString,
-    pub expiration_hours: i64,
+    pub access_token_expiration_minutes: i64,
+    pub refresh_token_expiration_days: i64,
+    // Asymmetric signing keys, see `JwtKeyConfig`
+    #[serde(default)]
+    pub keys: Vec<JwtKeyConfig>,
+    // Whether tokens without a `kid` are still verified with `secret`. Unset
+    // means only while no keys are configured.
+    #[serde(default)]
+    pub accept_legacy_hs256: Option<bool>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+pub enum JwtAlgorithm {
+    RS256,
+    EdDSA,
+}
+
+/// A key for signing tokens. The key that became active last signs new
+/// tokens; all keys that haven't expired yet are accepted and published.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: JwtAlgorithm,
+    // PKCS#8 PEM file, RSA keys may also be in PKCS#1 format
+    pub private_key_path: String,
+    pub active_from: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// External identity providers users can log in with.
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct OidcConfig {
+    #[serde(default)]
+    pub providers: Vec<OidcProviderConfig>,
+}
+
+/// An OpenID Connect issuer whose ID tokens are accepted for login.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct OidcProviderConfig {
+    // Name clients select the provider with, e.g. "google"
+    pub name: String,
+    // Must match the `iss` claim of the ID tokens
+    pub issuer: String,
+    pub jwks_uri: String,
+    // ID tokens must be issued for this client (`aud` claim)
+    pub client_id: String,
+}
+
+/// Argon2id cost parameters used for new password hashes.
+/// Existing hashes with other parameters are upgraded on the next login.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct PasswordHashingSettings {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// Limits for failed logins. Once the limit is reached the account or client
+/// address is locked, and every further failure doubles the lockout window.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct LoginThrottlingSettings {
+    pub max_failed_attempts_per_account: i32,
+    pub max_failed_attempts_per_ip: i32,
+    // Failures older than this no longer count
+    pub failure_window_minutes: i64,
+    pub base_lockout_seconds: i64,
+    pub max_lockout_seconds: i64,
+    // Take the client address from `X-Forwarded-For`, only safe behind a trusted proxy
+    pub trust_forwarded_for: bool,
+}
+
+/// Deleted accounts can be restored during the grace period and are erased
+/// by a background job afterwards.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct AccountDeletionSettings {
+    pub grace_period_days: i64,
+    pub purge_interval_seconds: u64,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransport {
+    File,
+    Smtp,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct EmailSettings {
+    pub sender: String,
+    pub transport: EmailTransport,
+    // Used by the `file` transport
+    pub outbox_dir: String,
+    // Used by the `smtp` transport
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_tls: bool,
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<SecretString>,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -56,7 +163,66 @@ pub struct ApplicationSettings{
     pub password: SecretString, 
     pub port: u16,
     pub host: String,
-    pub log_level: String
+    pub log_level: String,
+    // Public URL used to build links in emails
+    pub base_url: String,
+    // Page of the app where users choose a new password. Reset emails link
+    // to it with the token appended as `token` query parameter.
+    pub password_reset_url: String,
+    // Page of the app that signs users in with a magic link, which gets the
+    // token the same way
+    pub magic_link_url: String,
+    // Block users with an unverified email address from the health data endpoints
+    #[serde(default)]
+    pub require_email_verification: bool,
+    // Allow signing in with a link sent by email instead of the password
+    #[serde(default)]
+    pub enable_magic_link_login: bool,
+    // Don't store uploads with the same content as an earlier upload of the user
+    #[serde(default)]
+    pub deduplicate_uploads: bool,
+    #[serde(default)]
+    pub payload_limits: PayloadLimitSettings
+}
+
+/// Full path of the batch upload endpoint. API keys and the payload limits
+/// treat it apart from the other health data endpoints.
+pub const BATCH_UPLOAD_PATH: &str = "/health/batch";
+
+/// Maximum request body sizes in bytes. For compressed bodies the limits
+/// apply both before and after decompression.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PayloadLimitSettings {
+    // JSON bodies of all other endpoints
+    pub default_bytes: usize,
+    // `POST /health/upload_*`, minutes of 50 Hz acceleration data
+    pub sensor_upload_bytes: usize,
+    // `POST /health/batch`
+    pub batch_upload_bytes: usize,
+}
+
+impl Default for PayloadLimitSettings {
+    fn default() -> Self {
+        Self {
+            default_bytes: 64 * 1024,
+            sensor_upload_bytes: 8 * 1024 * 1024,
+            batch_upload_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl PayloadLimitSettings {
+    /// The limit for requests to the given path.
+    pub fn limit_for(&self, path: &str) -> usize {
+        if path == BATCH_UPLOAD_PATH {
+            self.batch_upload_bytes
+        } else if path.starts_with("/health/upload_") {
+            self.sensor_upload_bytes
+        } else {
+            self.default_bytes
+        }
+    }
 }
 
 pub fn get_config() -> Result<Settings, ConfigError> {
@@ -134,9 +300,12 @@ impl TryFrom<String> for Environment {
     }
 }
 
-pub fn get_jwt_settings(settings: &Settings) -> JwtSettings {
+pub fn get_jwt_settings(settings: &Settings) -> Result<JwtSettings, std::io::Error> {
     JwtSettings::new(
         settings.jwt.secret.expose_secret().to_string().clone(),
-        settings.jwt.expiration_hours,
+        settings.jwt.access_token_expiration_minutes,
+        settings.jwt.refresh_token_expiration_days,
     )
+    .with_keys(&settings.jwt.keys)
+    .map(|jwt_settings| jwt_settings.accept_legacy_hs256(settings.jwt.accept_legacy_hs256))
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use futures::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::email::{EmailMessage, MailError, Mailer};
+
+/// Writes every message as a JSON file into an outbox directory and logs it.
+/// Meant for local development and tests, where no mail server is available.
+pub struct FileMailer {
+    outbox_dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(outbox_dir: &str) -> Self {
+        Self {
+            outbox_dir: PathBuf::from(outbox_dir),
+        }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), MailError>> {
+        Box::pin(async move {
+            tracing::info!(to = %message.to, subject = %message.subject, "Writing email to outbox");
+
+            tokio::fs::create_dir_all(&self.outbox_dir)
+                .await
+                .map_err(|e| MailError(e.to_string()))?;
+
+            // Prefix with a timestamp so that the files sort in sending order
+            let file_name = format!(
+                "{}-{}.json",
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+                Uuid::new_v4()
+            );
+            let content = serde_json::to_vec_pretty(message)
+                .map_err(|e| MailError(e.to_string()))?;
+
+            tokio::fs::write(self.outbox_dir.join(file_name), content)
+                .await
+                .map_err(|e| MailError(e.to_string()))
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::config::settings::{EmailSettings, EmailTransport};
+
+pub mod file_mailer;
+pub mod smtp_mailer;
+
+pub use file_mailer::FileMailer;
+pub use smtp_mailer::SmtpMailer;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to send email: {}", self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// Outbound mail abstraction so handlers don't care how mail is delivered.
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), MailError>>;
+}
+
+/// Builds the mailer selected by `email.transport`.
+pub fn build_mailer(settings: &EmailSettings) -> Arc<dyn Mailer> {
+    match settings.transport {
+        EmailTransport::File => Arc::new(FileMailer::new(&settings.outbox_dir)),
+        EmailTransport::Smtp => Arc::new(
+            SmtpMailer::new(settings).expect("Failed to configure SMTP mailer")
+        ),
+    }
+}
@@ -0,0 +1,67 @@
+use futures::future::BoxFuture;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use secrecy::ExposeSecret;
+
+use crate::config::settings::EmailSettings;
+use crate::email::{EmailMessage, MailError, Mailer};
+
+/// Delivers mail through an SMTP server. With `smtp_tls` disabled it can point
+/// at a local stand-in server such as Mailpit or MailHog.
+pub struct SmtpMailer {
+    sender: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: &EmailSettings) -> Result<Self, MailError> {
+        let sender = settings.sender
+            .parse::<Mailbox>()
+            .map_err(|e| MailError(format!("Invalid sender address: {}", e)))?;
+
+        let mut builder = if settings.smtp_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.smtp_host)
+                .map_err(|e| MailError(e.to_string()))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.smtp_host)
+        }
+        .port(settings.smtp_port);
+
+        if let (Some(username), Some(password)) = (&settings.smtp_username, &settings.smtp_password) {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose_secret().to_string()
+            ));
+        }
+
+        Ok(Self {
+            sender,
+            transport: builder.build(),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), MailError>> {
+        Box::pin(async move {
+            let recipient = message.to
+                .parse::<Mailbox>()
+                .map_err(|e| MailError(format!("Invalid recipient address: {}", e)))?;
+
+            let email = Message::builder()
+                .from(self.sender.clone())
+                .to(recipient)
+                .subject(&message.subject)
+                .header(ContentType::TEXT_PLAIN)
+                .body(message.body.clone())
+                .map_err(|e| MailError(e.to_string()))?;
+
+            self.transport
+                .send(email)
+                .await
+                .map(|_| ())
+                .map_err(|e| MailError(e.to_string()))
+        })
+    }
+}
@@ -0,0 +1,499 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::{
+    AccountDeletionSettings, ApplicationSettings, LoginThrottlingSettings, PasswordHashingSettings,
+};
+use crate::email::Mailer;
+use crate::handlers::audit_handler::record_audit_event;
+use crate::handlers::auth_handler::{revoke_all_tokens, start_session};
+use crate::handlers::email_verification_handler::{email_change_email, store_verification_token};
+use crate::handlers::login_throttle::{
+    client_address, forget_account, record_failed_login, reject_if_locked_out, rename_account,
+};
+use crate::handlers::session_handler::load_session_details;
+use crate::handlers::registration_handler::{validate_email, validate_username};
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::account::{
+    AccountDeletionResponse, ChangeEmailRequest, ChangePasswordRequest, ChangeUsernameRequest, DeleteAccountRequest,
+};
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::session::SessionDetails;
+use crate::utils::password::{hash_password, validate_password, verify_password};
+
+/// Changes the password of the authenticated user. All sessions are revoked
+/// and a new one is returned for the current device.
+#[tracing::instrument(
+    name = "Change password",
+    skip(req, password_form, pool, jwt_settings, password_hashing_settings, login_throttling_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn change_password(
+    req: HttpRequest,
+    password_form: web::Json<ChangePasswordRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    if let Err(message) = validate_password(password_form.new_password.expose_secret()) {
+        return HttpResponse::BadRequest().json(json!({ "error": message }));
+    }
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = confirm_password(
+        pool.get_ref(), &login_throttling_settings, &address, user_id, password_form.current_password.expose_secret()
+    ).await {
+        return response;
+    }
+
+    // The replacement session keeps the details of the current one
+    let details = match user.session_id() {
+        Some(session_id) => match load_session_details(pool.get_ref(), session_id).await {
+            Ok(details) => details,
+            Err(e) => {
+                tracing::error!("Failed to fetch session: {:?}", e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        },
+        None => SessionDetails::default(),
+    };
+
+    let password_hash = hash_password(password_form.new_password.expose_secret(), &password_hashing_settings);
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // The new password, the revocation and the new session take effect together
+    let result = async {
+        sqlx::query!(
+            "UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3",
+            password_hash,
+            Utc::now(),
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        revoke_all_tokens(&mut transaction, user_id).await
+    }.await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to update password: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let session = match start_session(&mut transaction, user_id, &user.username, user.mfa(), &details, &jwt_settings).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit password change: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().json(session)
+}
+
+/// Starts a change of the authenticated user's email address. The new
+/// address only replaces the current one once it is verified.
+#[tracing::instrument(
+    name = "Change email address",
+    skip(req, email_form, pool, mailer, application_settings, login_throttling_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn change_email(
+    req: HttpRequest,
+    email_form: web::Json<ChangeEmailRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    if let Err(message) = validate_email(&email_form.email) {
+        return HttpResponse::BadRequest().json(json!({ "error": message }));
+    }
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = confirm_password(
+        pool.get_ref(), &login_throttling_settings, &address, user_id, email_form.password.expose_secret()
+    ).await {
+        return response;
+    }
+
+    match sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM users WHERE email = $1) as "exists!""#,
+        email_form.email
+    )
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(false) => {}
+        Ok(true) => return HttpResponse::BadRequest()
+            .json(json!({ "error": "Email address already in use" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        // Only the most recently requested change can be confirmed
+        sqlx::query!(
+            "UPDATE email_verification_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL AND email IS NOT NULL",
+            Utc::now(),
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let token = store_verification_token(&mut *transaction, user_id, Some(&email_form.email)).await?;
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(token)
+    }.await;
+
+    let token = match result {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Failed to store verification token: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let message = email_change_email(&email_form.email, &application_settings.base_url, &token);
+    if let Err(e) = mailer.send(&message).await {
+        tracing::error!("Failed to send verification email: {}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Accepted().finish()
+}
+
+/// Changes the username of the authenticated user. Access tokens pick up the
+/// new username with the next refresh.
+#[tracing::instrument(
+    name = "Change username",
+    skip(username_form, pool, user),
+    fields(
+        user_id = %user.id,
+        username = %username_form.username
+    )
+)]
+pub async fn change_username(
+    username_form: web::Json<ChangeUsernameRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    if let Err(message) = validate_username(&username_form.username) {
+        return HttpResponse::BadRequest().json(json!({ "error": message }));
+    }
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let old_username = sqlx::query_scalar!("SELECT username FROM users WHERE id = $1 FOR UPDATE", user_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "UPDATE users SET username = $1, updated_at = $2 WHERE id = $3",
+            username_form.username,
+            Utc::now(),
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        rename_account(&mut transaction, &old_username, &username_form.username).await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) if e.as_database_error().is_some_and(|db_error| db_error.constraint().is_some()) => {
+            HttpResponse::BadRequest().json(json!({ "error": "Username already exists" }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to update username: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Schedules the account of the authenticated user for deletion after the
+/// grace period and signs the user out everywhere.
+#[tracing::instrument(
+    name = "Request account deletion",
+    skip(req, delete_form, pool, account_deletion_settings, login_throttling_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn request_account_deletion(
+    req: HttpRequest,
+    delete_form: web::Json<DeleteAccountRequest>,
+    pool: web::Data<PgPool>,
+    account_deletion_settings: web::Data<AccountDeletionSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = confirm_password(
+        pool.get_ref(), &login_throttling_settings, &address, user_id, delete_form.password.expose_secret()
+    ).await {
+        return response;
+    }
+
+    match sqlx::query_scalar!(
+        r#"SELECT deletion_scheduled_for IS NOT NULL as "scheduled!" FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(false) => {}
+        Ok(true) => return HttpResponse::Conflict().json(json!({ "error": "Account deletion already requested" })),
+        Err(e) => {
+            tracing::error!("Failed to fetch user: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let now = Utc::now();
+    let receipt_id = Uuid::new_v4();
+    let scheduled_for = now + Duration::days(account_deletion_settings.grace_period_days);
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_requested_at = $1, deletion_scheduled_for = $2, deletion_receipt_id = $3
+            WHERE id = $4
+            "#,
+            now,
+            scheduled_for,
+            receipt_id,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::AccountDeletionRequested, Some(user_id))
+                .details(json!({ "receipt_id": receipt_id, "scheduled_for": scheduled_for }))
+        ).await?;
+        // Sessions of an account pending deletion must not outlive the request
+        revoke_all_tokens(&mut transaction, user_id).await?;
+        transaction.commit().await
+    }.await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to schedule account deletion: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Accepted().json(AccountDeletionResponse {
+        receipt_id,
+        scheduled_for,
+    })
+}
+
+/// Cancels a pending deletion of the authenticated user's account.
+#[tracing::instrument(
+    name = "Restore account",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn restore_account(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let restored = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_requested_at = NULL, deletion_scheduled_for = NULL, deletion_receipt_id = NULL
+            WHERE id = $1 AND deletion_scheduled_for IS NOT NULL
+            "#,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected() > 0;
+        if restored {
+            record_audit_event(&mut *transaction, NewAuditEvent::new(AuditEventType::AccountRestored, Some(user_id))).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(restored)
+    }.await;
+
+    match result {
+        Ok(false) => HttpResponse::BadRequest()
+            .json(json!({ "error": "No account deletion pending" })),
+        Ok(true) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to restore account: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Re-confirms the password of a signed-in user. Wrong passwords count as
+/// failed logins of the account, so a session can't be used to guess it
+/// without limit. Returns the response to send if it isn't confirmed.
+async fn confirm_password(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    address: &str,
+    user_id: Uuid,
+    password: &str
+) -> Option<HttpResponse> {
+    // The username of the token is outdated after a username change
+    let user = match sqlx::query!("SELECT username, password_hash FROM users WHERE id = $1", user_id)
+        .fetch_optional(pool)
+        .await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Some(HttpResponse::NotFound().json(json!({ "error": "User not found" }))),
+        Err(e) => {
+            tracing::error!("Failed to fetch user: {:?}", e);
+            return Some(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    if let Some(response) = reject_if_locked_out(pool, &user.username, address).await {
+        return Some(response);
+    }
+    if verify_password(password, &user.password_hash) {
+        return None;
+    }
+
+    tracing::info!("Invalid password");
+    if let Err(e) = record_failed_login(pool, settings, &user.username, address, Some(user_id)).await {
+        tracing::error!("Failed to record failed login: {:?}", e);
+    }
+    Some(HttpResponse::BadRequest().json(json!({ "error": "Invalid password" })))
+}
+
+/// Periodically erases the accounts whose grace period is over.
+pub async fn run_account_purge(pool: PgPool, settings: AccountDeletionSettings) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(settings.purge_interval_seconds));
+    loop {
+        interval.tick().await;
+        match purge_deleted_accounts(&pool).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Erased {} deleted accounts", count),
+            Err(e) => tracing::error!("Failed to erase deleted accounts: {:?}", e),
+        }
+    }
+}
+
+/// Hard-deletes every account whose grace period is over, along with all its
+/// data, and records an anonymized receipt for each.
+pub async fn purge_deleted_accounts(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query_scalar!(
+        "SELECT id FROM users WHERE deletion_scheduled_for <= $1",
+        Utc::now()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut purged = 0;
+    for user_id in due {
+        if purge_account(pool, user_id).await? {
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+async fn purge_account(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let mut transaction = pool.begin().await?;
+
+    // Checked again under the lock, the user might have restored the account meanwhile
+    let user = sqlx::query!(
+        r#"
+        SELECT username, deletion_requested_at as "deletion_requested_at!", deletion_receipt_id as "deletion_receipt_id!"
+        FROM users
+        WHERE id = $1 AND deletion_scheduled_for <= $2
+        FOR UPDATE
+        "#,
+        user_id,
+        now
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    let user = match user {
+        Some(user) => user,
+        None => return Ok(false),
+    };
+
+    let health_data_records = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM health_data WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    let sleep_data_records = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM processed_sleep_data WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    forget_account(&mut transaction, &user.username).await?;
+
+    // Everything else referencing the user is removed by `ON DELETE CASCADE`
+    sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO account_deletion_receipts (id, requested_at, deleted_at, health_data_records, sleep_data_records)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        user.deletion_receipt_id,
+        user.deletion_requested_at,
+        now,
+        health_data_records,
+        sleep_data_records
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    record_audit_event(
+        &mut *transaction,
+        NewAuditEvent::new(AuditEventType::AccountErased, Some(user_id))
+            .actor(None)
+            .details(json!({ "receipt_id": user.deletion_receipt_id }))
+    ).await?;
+
+    transaction.commit().await?;
+    tracing::info!("Erased account, receipt {}", user.deletion_receipt_id);
+    Ok(true)
+}
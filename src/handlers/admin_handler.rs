@@ -0,0 +1,212 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::audit_handler::record_audit_event;
+use crate::handlers::auth_handler::revoke_all_tokens;
+use crate::handlers::login_throttle::unlock_account;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::admin::{LockoutEvent, LockoutEventsQuery, UserRolesResponse};
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::role::Role;
+
+/// Lists the most recent login lockouts, newest first.
+#[tracing::instrument(name = "List lockout events", skip(pool, query))]
+pub async fn list_lockout_events(
+    pool: web::Data<PgPool>,
+    query: web::Query<LockoutEventsQuery>
+) -> HttpResponse {
+    let events = sqlx::query_as!(
+        LockoutEvent,
+        r#"
+        SELECT id, scope, key, user_id, failed_attempts, locked_until, created_at, unlocked_at
+        FROM lockout_events
+        WHERE NOT $1 OR (locked_until > $2 AND unlocked_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT 100
+        "#,
+        query.active,
+        Utc::now()
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match events {
+        Ok(events) => HttpResponse::Ok().json(events),
+        Err(e) => {
+            tracing::error!("Failed to fetch lockout events: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lifts a login lockout of the given user.
+#[tracing::instrument(name = "Unlock user account", skip(pool))]
+pub async fn unlock_user_account(
+    pool: web::Data<PgPool>,
+    user_id: web::Path<Uuid>
+) -> HttpResponse {
+    match unlock_account(pool.get_ref(), user_id.into_inner()).await {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound()
+            .json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Failed to unlock account: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the roles of a user.
+#[tracing::instrument(name = "Get user roles", skip(pool))]
+pub async fn get_user_roles(
+    pool: web::Data<PgPool>,
+    user_id: web::Path<Uuid>
+) -> HttpResponse {
+    let user_id = user_id.into_inner();
+
+    match user_exists(&pool, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::NotFound().json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let roles = sqlx::query!(
+        "SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role",
+        user_id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match roles {
+        Ok(rows) => HttpResponse::Ok().json(UserRolesResponse {
+            user_id,
+            roles: rows.into_iter().map(|row| row.role).collect(),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to fetch roles: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Grants a role to a user. It shows up in the user's tokens from the next
+/// login or refresh on.
+#[tracing::instrument(name = "Grant role", skip(pool, admin), fields(admin_id = %admin.id))]
+pub async fn grant_user_role(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, String)>,
+    admin: AuthenticatedUser
+) -> HttpResponse {
+    let (user_id, role) = path.into_inner();
+    let role = match role.parse::<Role>() {
+        Ok(role) => role,
+        Err(message) => return HttpResponse::BadRequest().json(json!({ "error": message })),
+    };
+
+    match user_exists(&pool, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::NotFound().json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let granted = sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role, granted_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, role) DO NOTHING
+            "#,
+            user_id,
+            role.as_str(),
+            Utc::now()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        // Granting a role the user already has changes nothing
+        if granted.rows_affected() > 0 {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::RoleGranted, Some(user_id))
+                    .actor(Some(admin.id))
+                    .details(json!({ "role": role.as_str() }))
+            ).await?;
+        }
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to grant role: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Takes a role away from a user. All of the user's tokens are revoked, as
+/// they may still carry the role.
+#[tracing::instrument(name = "Revoke role", skip(pool, admin), fields(admin_id = %admin.id))]
+pub async fn revoke_user_role(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, String)>,
+    admin: AuthenticatedUser
+) -> HttpResponse {
+    let (user_id, role) = path.into_inner();
+    let role = match role.parse::<Role>() {
+        Ok(role) => role,
+        Err(message) => return HttpResponse::BadRequest().json(json!({ "error": message })),
+    };
+
+    // The role is only gone once no token can carry it anymore
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let revoked = sqlx::query!(
+            "DELETE FROM user_roles WHERE user_id = $1 AND role = $2",
+            user_id,
+            role.as_str()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if revoked.rows_affected() == 0 {
+            return Ok(false);
+        }
+        revoke_all_tokens(&mut transaction, user_id).await?;
+        record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::RoleRevoked, Some(user_id))
+                .actor(Some(admin.id))
+                .details(json!({ "role": role.as_str() }))
+        ).await?;
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "User doesn't have this role" })),
+        Err(e) => {
+            tracing::error!("Failed to revoke role: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn user_exists(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as "exists!""#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(record.exists)
+}
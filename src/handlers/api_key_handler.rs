@@ -0,0 +1,156 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::api_key::{ApiKeyInfo, CreateApiKeyRequest, CreatedApiKeyResponse, API_KEY_SCOPES};
+use crate::utils::token::{generate_token, hash_token};
+
+const API_KEY_PREFIX: &str = "ak_";
+
+/// Creates a new API key for the authenticated user.
+#[tracing::instrument(
+    name = "Create API key",
+    skip(key_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn create_api_key(
+    key_form: web::Json<CreateApiKeyRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let name = key_form.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": "Name must be between 1 and 100 characters" }));
+    }
+
+    if key_form.scopes.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": "At least one scope is required" }));
+    }
+    if let Some(unknown) = key_form.scopes.iter().find(|scope| !API_KEY_SCOPES.contains(&scope.as_str())) {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": format!("Unknown scope: {}", unknown) }));
+    }
+
+    let mut scopes = key_form.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let key = format!("{}{}", API_KEY_PREFIX, generate_token());
+    let key_prefix = key[..API_KEY_PREFIX.len() + 8].to_string();
+    let id = Uuid::new_v4();
+    let created_at = Utc::now();
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO api_keys (id, user_id, name, key_prefix, key_hash, scopes, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        id,
+        user_id,
+        name,
+        key_prefix,
+        hash_token(&key),
+        &scopes,
+        created_at
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(CreatedApiKeyResponse {
+            id,
+            name: name.to_string(),
+            key,
+            key_prefix,
+            scopes,
+            created_at,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to store API key: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the API keys of the authenticated user, including revoked ones.
+#[tracing::instrument(
+    name = "List API keys",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_api_keys(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let keys = sqlx::query_as!(
+        ApiKeyInfo,
+        r#"
+        SELECT id, name, key_prefix, scopes, created_at, last_used_at, revoked_at
+        FROM api_keys
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match keys {
+        Ok(keys) => HttpResponse::Ok().json(keys),
+        Err(e) => {
+            tracing::error!("Failed to fetch API keys: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Revokes one of the authenticated user's API keys.
+#[tracing::instrument(
+    name = "Revoke API key",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn revoke_api_key(
+    key_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_keys SET revoked_at = $1
+        WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL
+        "#,
+        Utc::now(),
+        key_id.into_inner(),
+        user_id
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound()
+            .json(json!({ "error": "API key not found" })),
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to revoke API key: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::audit::{AccountAuditQuery, AuditEvent, AuditEventType, AuditEventsQuery, NewAuditEvent};
+
+const DEFAULT_AUDIT_EVENTS_LIMIT: i64 = 100;
+const MAX_AUDIT_EVENTS_LIMIT: i64 = 1000;
+
+/// Appends an event to the audit log. Pass the transaction of the change the
+/// event is about, so neither is stored without the other.
+pub async fn record_audit_event(
+    executor: impl PgExecutor<'_>,
+    event: NewAuditEvent
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_events (id, event_type, user_id, actor_id, ip_address, details, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        Uuid::new_v4(),
+        event.event_type.as_str(),
+        event.user_id,
+        event.actor_id,
+        event.ip_address,
+        event.details,
+        Utc::now()
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Lists the audit events concerning the authenticated user's account, newest first.
+#[tracing::instrument(
+    name = "List account audit events",
+    skip(pool, query, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_account_audit_events(
+    pool: web::Data<PgPool>,
+    query: web::Query<AccountAuditQuery>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let query = query.into_inner();
+    list_audit_events(pool.get_ref(), AuditEventsQuery {
+        user_id: Some(user.id),
+        actor_id: None,
+        event_type: query.event_type,
+        start_time: query.start_time,
+        end_time: query.end_time,
+        limit: query.limit,
+    }).await
+}
+
+/// Searches the audit log of all users, newest first.
+#[tracing::instrument(name = "Search audit events", skip(pool, query))]
+pub async fn search_audit_events(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditEventsQuery>
+) -> HttpResponse {
+    list_audit_events(pool.get_ref(), query.into_inner()).await
+}
+
+async fn list_audit_events(pool: &PgPool, query: AuditEventsQuery) -> HttpResponse {
+    let event_type = match query.event_type.as_deref().map(str::parse::<AuditEventType>) {
+        Some(Ok(event_type)) => Some(event_type.as_str()),
+        Some(Err(message)) => return HttpResponse::BadRequest().json(json!({ "error": message })),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_EVENTS_LIMIT);
+    if !(1..=MAX_AUDIT_EVENTS_LIMIT).contains(&limit) {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": format!("limit must be between 1 and {}", MAX_AUDIT_EVENTS_LIMIT) }));
+    }
+
+    let events = sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT id, event_type, user_id, actor_id, ip_address, details, created_at
+        FROM audit_events
+        WHERE ($1::uuid IS NULL OR user_id = $1)
+            AND ($2::uuid IS NULL OR actor_id = $2)
+            AND ($3::text IS NULL OR event_type = $3)
+            AND ($4::timestamptz IS NULL OR created_at >= $4)
+            AND ($5::timestamptz IS NULL OR created_at < $5)
+        ORDER BY created_at DESC
+        LIMIT $6
+        "#,
+        query.user_id,
+        query.actor_id,
+        event_type,
+        query.start_time,
+        query.end_time,
+        limit
+    )
+    .fetch_all(pool)
+    .await;
+
+    match events {
+        Ok(events) => HttpResponse::Ok().json(events),
+        Err(e) => {
+            tracing::error!("Failed to fetch audit events: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -1,38 +1,65 @@
 // src/handlers/auth_handler.rs
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use secrecy::ExposeSecret;
-use sqlx::PgPool;
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use chrono::{Utc, Duration};
-use jsonwebtoken::{encode, EncodingKey, Header};
+use uuid::Uuid;
 
-use crate::models::auth::{LoginRequest, LoginResponse};
-use crate::utils::password::verify_password;
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::auth::{LoginRequest, LoginResponse, LogoutRequest, RefreshRequest};
+use crate::models::mfa::MfaChallengeResponse;
+use crate::models::session::SessionDetails;
+use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::{AuthenticatedUser, Credential};
+use crate::utils::password::{hash_password, needs_rehash, verify_password};
+use crate::utils::token::{generate_token, hash_token};
 use crate::config::jwt::JwtSettings;
+use crate::config::settings::{LoginThrottlingSettings, PasswordHashingSettings};
+use crate::handlers::login_throttle::{
+    clear_failed_logins, client_address, lockout_remaining_seconds, record_failed_login, throttled_response
+};
+use crate::handlers::audit_handler::record_audit_event;
+use crate::handlers::session_handler::{create_session, end_session, session_details};
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct Claims {
-    sub: String,  // Subject (user id)
-    username: String,
-    exp: usize,   // Expiration time (as UTC timestamp)
-}
+const MFA_PENDING_TOKEN_EXPIRATION_MINUTES: i64 = 5;
 
 #[tracing::instrument(
     name = "Login user attempt",
-    skip(login_form, pool, jwt_settings),
+    skip(req, login_form, pool, jwt_settings, password_hashing_settings, login_throttling_settings),
     fields(
         username = %login_form.username
     )
 )]
 pub async fn login_user(
+    req: HttpRequest,
     login_form: web::Json<LoginRequest>,
     pool: web::Data<PgPool>,
-    jwt_settings: web::Data<JwtSettings>
+    jwt_settings: web::Data<JwtSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
 ) -> HttpResponse {
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+
+    // Locked accounts and addresses don't get to try a password at all
+    match lockout_remaining_seconds(pool.get_ref(), &login_form.username, &address).await {
+        Ok(Some(retry_after)) => {
+            tracing::info!("Login attempt while locked out");
+            return throttled_response(retry_after);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
     let user_result = sqlx::query!(
         r#"
-        SELECT id, username, password_hash
-        FROM users
-        WHERE username = $1
+        SELECT u.id, u.username, u.password_hash, t.confirmed_at IS NOT NULL as "mfa_enabled!"
+        FROM users u
+        LEFT JOIN user_totp t ON t.user_id = u.id
+        WHERE u.username = $1
         "#,
         login_form.username,
     )
@@ -44,7 +71,8 @@ pub async fn login_user(
         Ok(Some(user)) => user,
         Ok(None) => {
             tracing::info!("User not found or invalid credentials");
-            return HttpResponse::Unauthorized().finish();
+            // Unknown usernames are throttled too, so lockouts don't reveal which accounts exist
+            return reject_login(&pool, &login_throttling_settings, &login_form.username, &address, None).await;
         }
         Err(e) => {
             tracing::error!("Database error occurred: {:?}", e);
@@ -58,25 +86,325 @@ pub async fn login_user(
         &user.password_hash
     ) {
         tracing::info!("Invalid password");
+        return reject_login(&pool, &login_throttling_settings, &login_form.username, &address, Some(user.id)).await;
+    }
+
+    // Transparently move legacy bcrypt (or outdated Argon2) hashes to the current parameters
+    if needs_rehash(&user.password_hash, &password_hashing_settings) {
+        let new_hash = hash_password(login_form.password.expose_secret(), &password_hashing_settings);
+        match sqlx::query!(
+            "UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3",
+            new_hash,
+            Utc::now(),
+            user.id
+        )
+        .execute(pool.get_ref())
+        .await {
+            Ok(_) => tracing::info!("Upgraded password hash for user {}", user.id),
+            // Not fatal, we'll try again on the next login
+            Err(e) => tracing::warn!("Failed to upgrade password hash: {:?}", e),
+        }
+    }
+
+    // With two-factor authentication the password only gets the user to the second step.
+    // Failed logins are only reset once that step succeeds too.
+    if user.mfa_enabled {
+        return mfa_challenge(user.id, &user.username, &jwt_settings);
+    }
+
+    if let Err(e) = clear_failed_logins(pool.get_ref(), &login_form.username).await {
+        tracing::warn!("Failed to reset failed login counter: {:?}", e);
+    }
+
+    let details = session_details(&req, address, login_form.device_name.as_deref());
+    issue_session(pool.get_ref(), user.id, &user.username, false, &details, &jwt_settings).await
+}
+
+/// Answers a login of a user with two-factor authentication with a short-lived
+/// token for `POST /login/mfa`.
+pub fn mfa_challenge(user_id: Uuid, username: &str, jwt_settings: &JwtSettings) -> HttpResponse {
+    match create_mfa_pending_token(user_id, username, jwt_settings) {
+        Ok(mfa_token) => HttpResponse::Ok().json(MfaChallengeResponse {
+            mfa_required: true,
+            mfa_token,
+            expires_in: MFA_PENDING_TOKEN_EXPIRATION_MINUTES * 60,
+        }),
+        Err(e) => {
+            tracing::error!("Error generating JWT token: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Starts a new login session and returns its access/refresh token pair.
+pub async fn issue_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    username: &str,
+    mfa: bool,
+    details: &SessionDetails,
+    jwt_settings: &JwtSettings
+) -> HttpResponse {
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            tracing::error!("Failed to start transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let session = match start_session(&mut transaction, user_id, username, mfa, details, jwt_settings).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to store session: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().json(session)
+}
+
+/// Starts a new login session within the caller's transaction, so that it
+/// only exists if the rest of the transaction commits too.
+pub async fn start_session(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    username: &str,
+    mfa: bool,
+    details: &SessionDetails,
+    jwt_settings: &JwtSettings
+) -> Result<LoginResponse, HttpResponse> {
+    let roles = match load_roles(&mut *conn, user_id).await {
+        Ok(roles) => roles,
+        Err(e) => {
+            tracing::error!("Failed to load roles: {:?}", e);
+            return Err(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    // Every login starts a new session, which is also the refresh token family
+    let session_id = Uuid::new_v4();
+    let token = match create_access_token(user_id, username, mfa, roles, session_id, jwt_settings) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Error generating JWT token: {:?}", e);
+            return Err(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    let result = async {
+        let expires_at = Utc::now() + Duration::days(jwt_settings.refresh_token_expiration_days);
+        create_session(&mut *conn, session_id, user_id, details, expires_at).await?;
+        let refresh_token = store_refresh_token(&mut *conn, user_id, session_id, mfa, jwt_settings).await?;
+        record_audit_event(
+            &mut *conn,
+            NewAuditEvent::new(AuditEventType::LoginSucceeded, Some(user_id))
+                .ip_address(details.ip_address.as_deref())
+                .details(json!({ "session_id": session_id, "user_agent": details.user_agent, "mfa": mfa }))
+        ).await?;
+        Ok::<_, sqlx::Error>(refresh_token)
+    }.await;
+
+    let refresh_token = match result {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to store session: {:?}", e);
+            return Err(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    // Return token pair
+    Ok(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: jwt_settings.access_token_expiration_minutes * 60,
+    })
+}
+
+pub async fn reject_login(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    username: &str,
+    address: &str,
+    user_id: Option<Uuid>
+) -> HttpResponse {
+    if let Err(e) = record_failed_login(pool, settings, username, address, user_id).await {
+        tracing::error!("Failed to record failed login: {:?}", e);
+    }
+    // Whoever guessed is unknown, so the attempt isn't attributed to the account owner.
+    // Attempted usernames are only kept when they don't belong to an account
+    let mut event = NewAuditEvent::new(AuditEventType::LoginFailed, user_id)
+        .actor(None)
+        .ip_address(Some(address));
+    if user_id.is_none() {
+        event = event.details(json!({ "username": username }));
+    }
+    if let Err(e) = record_audit_event(pool, event).await {
+        tracing::error!("Failed to record audit event: {:?}", e);
+    }
+    HttpResponse::Unauthorized().finish()
+}
+
+/// Exchanges a refresh token for a new access/refresh token pair.
+///
+/// The presented refresh token is marked as used and replaced by a new one in
+/// the same family. If a token that was already rotated is presented again, we
+/// assume it has been stolen and revoke the entire family.
+#[tracing::instrument(
+    name = "Refresh access token",
+    skip(req, refresh_form, pool, jwt_settings, login_throttling_settings)
+)]
+pub async fn refresh_access_token(
+    req: HttpRequest,
+    refresh_form: web::Json<RefreshRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    let token_hash = hash_token(refresh_form.refresh_token.expose_secret());
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let stored_result = sqlx::query!(
+        r#"
+        SELECT rt.user_id, rt.family_id, rt.expires_at, rt.used_at, rt.revoked_at, rt.mfa, u.username
+        FROM refresh_tokens rt
+        JOIN users u ON u.id = rt.user_id
+        WHERE rt.token_hash = $1
+        FOR UPDATE OF rt
+        "#,
+        token_hash
+    )
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let stored = match stored_result {
+        Ok(Some(stored)) => stored,
+        Ok(None) => {
+            tracing::info!("Unknown refresh token");
+            return HttpResponse::Unauthorized().finish();
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if stored.revoked_at.is_some() {
+        tracing::info!("Refresh token family {} has been revoked", stored.family_id);
         return HttpResponse::Unauthorized().finish();
     }
 
-    // Generate JWT token
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
-        .expect("Valid timestamp")
-        .timestamp() as usize;
+    // Reuse of a rotated token: revoke the whole family
+    if stored.used_at.is_some() {
+        tracing::warn!(
+            "Refresh token reuse detected for user {}, revoking family {}",
+            stored.user_id,
+            stored.family_id
+        );
+        if let Err(e) = revoke_refresh_token_family(&mut transaction, stored.family_id).await {
+            tracing::error!("Failed to revoke refresh token family: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        if let Err(e) = record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::RefreshTokenReused, Some(stored.user_id))
+                .actor(None)
+                .ip_address(Some(&address))
+                .details(json!({ "session_id": stored.family_id }))
+        ).await {
+            tracing::error!("Failed to record audit event: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        if let Err(e) = transaction.commit().await {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
 
-    let claims = Claims {
-        sub: user.id.to_string(),
-        username: user.username,
-        exp: expiration,
+    if stored.expires_at < Utc::now() {
+        tracing::info!("Refresh token has expired");
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    if let Err(e) = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET used_at = $1
+        WHERE token_hash = $2
+        "#,
+        Utc::now(),
+        token_hash
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to mark refresh token as used: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let refresh_token = match store_refresh_token(
+        &mut *transaction,
+        stored.user_id,
+        stored.family_id,
+        stored.mfa,
+        &jwt_settings
+    ).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to store refresh token: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // The session lives as long as its latest refresh token
+    if let Err(e) = sqlx::query!(
+        "UPDATE sessions SET last_seen_at = $1, expires_at = $2 WHERE id = $3",
+        Utc::now(),
+        Utc::now() + Duration::days(jwt_settings.refresh_token_expiration_days),
+        stored.family_id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to update session: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = record_audit_event(
+        &mut *transaction,
+        NewAuditEvent::new(AuditEventType::TokenRefreshed, Some(stored.user_id))
+            .ip_address(Some(&address))
+            .details(json!({ "session_id": stored.family_id }))
+    ).await {
+        tracing::error!("Failed to record audit event: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    // Role changes take effect with the next refresh
+    let roles = match load_roles(&mut *transaction, stored.user_id).await {
+        Ok(roles) => roles,
+        Err(e) => {
+            tracing::error!("Failed to load roles: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
     };
 
-    let token = match encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_settings.secret.expose_secret().as_bytes()),
+    let token = match create_access_token(
+        stored.user_id,
+        &stored.username,
+        stored.mfa,
+        roles,
+        stored.family_id,
+        &jwt_settings
     ) {
         Ok(t) => t,
         Err(e) => {
@@ -85,6 +413,306 @@ pub async fn login_user(
         }
     };
 
-    // Return token
-    HttpResponse::Ok().json(LoginResponse { token })
-}
\ No newline at end of file
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().json(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: jwt_settings.access_token_expiration_minutes * 60,
+    })
+}
+
+/// Revokes the access token used for this request. If the client also sends
+/// its refresh token, the refresh token family of this login is revoked too.
+#[tracing::instrument(
+    name = "Logout user",
+    skip(logout_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn logout_user(
+    logout_form: Option<web::Json<LogoutRequest>>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+    let Credential::AccessToken { jti, expires_at, session_id, .. } = user.credential else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    if let Err(e) = revoke_access_token(pool.get_ref(), user_id, jti, expires_at).await {
+        tracing::error!("Failed to revoke access token: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    // Logging out ends the session the token belongs to
+    if let Some(session_id) = session_id {
+        let result = async {
+            let mut transaction = pool.begin().await?;
+            end_session(&mut transaction, user_id, session_id).await?;
+            transaction.commit().await
+        }.await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to end session: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    if let Some(refresh_token) = logout_form.as_ref().and_then(|form| form.refresh_token.as_ref()) {
+        let result = sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = $1
+            WHERE user_id = $2 AND revoked_at IS NULL AND family_id = (
+                SELECT family_id FROM refresh_tokens WHERE token_hash = $3
+            )
+            "#,
+            Utc::now(),
+            user_id,
+            hash_token(refresh_token.expose_secret())
+        )
+        .execute(pool.get_ref())
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to revoke refresh token family: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    // Entries for tokens that have expired anyway are no longer needed
+    if let Err(e) = sqlx::query!(
+        "DELETE FROM revoked_tokens WHERE expires_at < $1",
+        Utc::now()
+    )
+    .execute(pool.get_ref())
+    .await {
+        tracing::warn!("Failed to purge expired revoked tokens: {:?}", e);
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Invalidates every access and refresh token of the user (logout everywhere).
+#[tracing::instrument(
+    name = "Logout user from all sessions",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn logout_all_sessions(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+    let Credential::AccessToken { jti, expires_at, .. } = user.credential else {
+        return HttpResponse::Unauthorized().finish();
+    };
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        revoke_all_tokens(&mut transaction, user_id).await?;
+        // The cutoff has second precision, so also revoke the current token explicitly
+        revoke_access_token(&mut *transaction, user_id, jti, expires_at).await?;
+        transaction.commit().await
+    }.await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to revoke tokens: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Invalidates all access tokens issued so far and revokes all refresh tokens
+/// of a user. Run it in a transaction with the change that prompted it.
+pub async fn revoke_all_tokens(conn: &mut PgConnection, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query!(
+        "UPDATE users SET tokens_valid_after = $1 WHERE id = $2",
+        now,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = $1
+        WHERE user_id = $2 AND revoked_at IS NULL
+        "#,
+        now,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL",
+        now,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn revoke_access_token(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    jti: Uuid,
+    exp: usize
+) -> Result<(), sqlx::Error> {
+    let expires_at = chrono::DateTime::from_timestamp(exp as i64, 0)
+        .unwrap_or_else(Utc::now);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO revoked_tokens (jti, user_id, expires_at, revoked_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (jti) DO NOTHING
+        "#,
+        jti,
+        user_id,
+        expires_at,
+        Utc::now()
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+fn create_access_token(
+    user_id: Uuid,
+    username: &str,
+    mfa: bool,
+    roles: Vec<String>,
+    session_id: Uuid,
+    jwt_settings: &JwtSettings
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(Duration::minutes(jwt_settings.access_token_expiration_minutes))
+        .expect("Valid timestamp")
+        .timestamp() as usize;
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username: username.to_string(),
+        exp: expiration,
+        iat: now.timestamp() as usize,
+        jti: Uuid::new_v4().to_string(),
+        mfa,
+        mfa_pending: false,
+        roles,
+        sid: Some(session_id.to_string()),
+    };
+
+    sign_claims(&claims, jwt_settings)
+}
+
+/// Creates the short-lived token that is exchanged for an access token once
+/// the second factor has been verified.
+fn create_mfa_pending_token(
+    user_id: Uuid,
+    username: &str,
+    jwt_settings: &JwtSettings
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username: username.to_string(),
+        exp: (now + Duration::minutes(MFA_PENDING_TOKEN_EXPIRATION_MINUTES)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        jti: Uuid::new_v4().to_string(),
+        mfa: false,
+        mfa_pending: true,
+        roles: Vec::new(),
+        sid: None,
+    };
+
+    sign_claims(&claims, jwt_settings)
+}
+
+async fn load_roles(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role",
+        user_id
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.role).collect())
+}
+
+fn sign_claims(claims: &Claims, jwt_settings: &JwtSettings) -> Result<String, jsonwebtoken::errors::Error> {
+    jwt_settings.sign(claims)
+}
+
+/// Generates a new refresh token in the given family and stores its hash.
+/// Returns the plain token, which is only ever handed to the client.
+async fn store_refresh_token(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    family_id: Uuid,
+    mfa: bool,
+    jwt_settings: &JwtSettings
+) -> Result<String, sqlx::Error> {
+    let token = generate_token();
+    let now = Utc::now();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at, mfa)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        family_id,
+        hash_token(&token),
+        now + Duration::days(jwt_settings.refresh_token_expiration_days),
+        now,
+        mfa
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+/// Revokes every refresh token of the family and ends the session it belongs to.
+async fn revoke_refresh_token_family(
+    transaction: &mut PgConnection,
+    family_id: Uuid
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = $1
+        WHERE family_id = $2 AND revoked_at IS NULL
+        "#,
+        now,
+        family_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = $1 WHERE id = $2 AND revoked_at IS NULL",
+        now,
+        family_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
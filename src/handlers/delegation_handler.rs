@@ -0,0 +1,329 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::audit_handler::record_audit_event;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::delegation::{CreateDelegationRequest, DelegationInfo, DELEGATION_DATA_TYPES};
+
+/// Invites another account to read some of the authenticated user's health
+/// data until the given expiry. Access starts once the invitee accepts.
+#[tracing::instrument(
+    name = "Create delegation",
+    skip(delegation_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn create_delegation(
+    delegation_form: web::Json<CreateDelegationRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    if delegation_form.data_types.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "At least one data type is required" }));
+    }
+    if let Some(unknown) = delegation_form.data_types.iter().find(|data_type| !DELEGATION_DATA_TYPES.contains(&data_type.as_str())) {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Unknown data type: {}", unknown) }));
+    }
+    if delegation_form.expires_at <= Utc::now() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Expiry must be in the future" }));
+    }
+
+    let mut data_types = delegation_form.data_types.clone();
+    data_types.sort();
+    data_types.dedup();
+
+    let delegate = match sqlx::query!(
+        "SELECT id, username FROM users WHERE username = $1 AND deletion_scheduled_for IS NULL",
+        delegation_form.username
+    )
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(delegate)) => delegate,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    if delegate.id == user.id {
+        return HttpResponse::BadRequest().json(json!({ "error": "Cannot delegate access to yourself" }));
+    }
+
+    let delegation = DelegationInfo {
+        id: Uuid::new_v4(),
+        owner_id: user.id,
+        owner_username: user.username.clone(),
+        delegate_id: delegate.id,
+        delegate_username: delegate.username,
+        data_types,
+        expires_at: delegation_form.expires_at,
+        created_at: Utc::now(),
+        accepted_at: None,
+    };
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO delegations (id, owner_id, delegate_id, data_types, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            delegation.id,
+            delegation.owner_id,
+            delegation.delegate_id,
+            &delegation.data_types,
+            delegation.expires_at,
+            delegation.created_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::DelegationGranted, Some(user.id)).details(json!({
+                "delegation_id": delegation.id,
+                "delegate_id": delegation.delegate_id,
+                "data_types": delegation.data_types,
+                "expires_at": delegation.expires_at
+            }))
+        ).await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(()) => HttpResponse::Created().json(delegation),
+        Err(e) => {
+            tracing::error!("Failed to create delegation: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the active and pending delegations of the authenticated user's data.
+#[tracing::instrument(
+    name = "List granted delegations",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_granted_delegations(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_delegations(pool.get_ref(), user.id, false).await
+}
+
+/// Lists the active delegations and open invitations the authenticated user received.
+#[tracing::instrument(
+    name = "List received delegations",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_received_delegations(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_delegations(pool.get_ref(), user.id, true).await
+}
+
+async fn list_delegations(pool: &PgPool, user_id: Uuid, received: bool) -> HttpResponse {
+    let delegations = sqlx::query_as!(
+        DelegationInfo,
+        r#"
+        SELECT d.id, d.owner_id, o.username as owner_username, d.delegate_id, g.username as delegate_username,
+            d.data_types, d.expires_at, d.created_at, d.accepted_at
+        FROM delegations d
+        JOIN users o ON o.id = d.owner_id
+        JOIN users g ON g.id = d.delegate_id
+        WHERE (CASE WHEN $2 THEN d.delegate_id ELSE d.owner_id END) = $1
+            AND d.revoked_at IS NULL
+            AND d.expires_at > $3
+        ORDER BY d.created_at DESC
+        "#,
+        user_id,
+        received,
+        Utc::now()
+    )
+    .fetch_all(pool)
+    .await;
+
+    match delegations {
+        Ok(delegations) => HttpResponse::Ok().json(delegations),
+        Err(e) => {
+            tracing::error!("Failed to fetch delegations: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Accepts an invitation addressed to the authenticated user.
+#[tracing::instrument(
+    name = "Accept delegation",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn accept_delegation(
+    delegation_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let delegation_id = delegation_id.into_inner();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let owner_id = sqlx::query_scalar!(
+            r#"
+            UPDATE delegations SET accepted_at = $1
+            WHERE id = $2 AND delegate_id = $3 AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > $1
+            RETURNING owner_id
+            "#,
+            Utc::now(),
+            delegation_id,
+            user.id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some(owner_id) = owner_id {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::DelegationAccepted, Some(owner_id))
+                    .actor(Some(user.id))
+                    .details(json!({ "delegation_id": delegation_id }))
+            ).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(owner_id.is_some())
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "Invitation not found" })),
+        Err(e) => {
+            tracing::error!("Failed to accept delegation: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Ends a delegation or declines an invitation. Either side can do this.
+#[tracing::instrument(
+    name = "Revoke delegation",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn revoke_delegation(
+    delegation_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let delegation_id = delegation_id.into_inner();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let owner_id = sqlx::query_scalar!(
+            r#"
+            UPDATE delegations SET revoked_at = $1
+            WHERE id = $2 AND (owner_id = $3 OR delegate_id = $3) AND revoked_at IS NULL
+            RETURNING owner_id
+            "#,
+            Utc::now(),
+            delegation_id,
+            user.id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some(owner_id) = owner_id {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::DelegationRevoked, Some(owner_id))
+                    .actor(Some(user.id))
+                    .details(json!({ "delegation_id": delegation_id }))
+            ).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(owner_id.is_some())
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "Delegation not found" })),
+        Err(e) => {
+            tracing::error!("Failed to revoke delegation: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Decides whose data a `/health` read request gets. Without `owner_id` it's
+/// the user's own; otherwise an accepted, unexpired delegation from that owner
+/// has to cover all given data types, and the read is recorded in the audit log.
+pub async fn resolve_data_owner(
+    pool: &PgPool,
+    user: &AuthenticatedUser,
+    owner_id: Option<Uuid>,
+    data_types: &[&str]
+) -> Result<Uuid, HttpResponse> {
+    let owner_id = match owner_id {
+        Some(owner_id) if owner_id != user.id => owner_id,
+        _ => return Ok(user.id),
+    };
+    let data_types: Vec<String> = data_types.iter().map(|data_type| data_type.to_string()).collect();
+
+    let delegation_id = sqlx::query_scalar!(
+        r#"
+        SELECT d.id FROM delegations d
+        JOIN users u ON u.id = d.owner_id
+        WHERE d.owner_id = $1 AND d.delegate_id = $2 AND d.data_types @> $3
+            AND d.accepted_at IS NOT NULL AND d.revoked_at IS NULL AND d.expires_at > $4
+            AND u.deletion_scheduled_for IS NULL
+        LIMIT 1
+        "#,
+        owner_id,
+        user.id,
+        &data_types,
+        Utc::now()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up delegation: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+
+    let delegation_id = match delegation_id {
+        Some(delegation_id) => delegation_id,
+        None => {
+            tracing::info!("User {} has no delegation for {:?} of user {}", user.id, data_types, owner_id);
+            return Err(HttpResponse::Forbidden().json(json!({
+                "status": "error",
+                "message": "No delegation grants access to this data"
+            })));
+        }
+    };
+
+    // Reads are only served once they are on record
+    record_audit_event(
+        pool,
+        NewAuditEvent::new(AuditEventType::HealthDataAccessed, Some(owner_id))
+            .actor(Some(user.id))
+            .details(json!({ "delegation_id": delegation_id, "data_types": data_types }))
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit event: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+
+    Ok(owner_id)
+}
@@ -0,0 +1,235 @@
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::config::settings::ApplicationSettings;
+use crate::email::{EmailMessage, Mailer};
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::auth::VerifyEmailQuery;
+use crate::utils::token::{generate_token, hash_token};
+
+const VERIFICATION_TOKEN_EXPIRATION_HOURS: i64 = 24;
+
+/// Marks the user's email address as verified using the token from the verification email.
+#[tracing::instrument(
+    name = "Verify email address",
+    skip(query, pool)
+)]
+pub async fn verify_email_address(
+    query: web::Query<VerifyEmailQuery>,
+    pool: web::Data<PgPool>
+) -> HttpResponse {
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let stored = match sqlx::query!(
+        r#"
+        SELECT id, user_id, expires_at, used_at, email
+        FROM email_verification_tokens
+        WHERE token_hash = $1
+        FOR UPDATE
+        "#,
+        hash_token(query.token.expose_secret())
+    )
+    .fetch_optional(&mut *transaction)
+    .await {
+        Ok(Some(stored)) if stored.used_at.is_none() && stored.expires_at > Utc::now() => stored,
+        Ok(_) => {
+            tracing::info!("Invalid, used or expired verification token");
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "Invalid or expired verification token" }));
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let now = Utc::now();
+
+    let result = match &stored.email {
+        // Confirms a change of the address, which only takes effect now
+        Some(new_email) => sqlx::query!(
+            "UPDATE users SET email = $1, email_verified_at = $2, updated_at = $2 WHERE id = $3",
+            new_email,
+            now,
+            stored.user_id
+        )
+        .execute(&mut *transaction)
+        .await,
+        None => sqlx::query!(
+            "UPDATE users SET email_verified_at = $1, updated_at = $1 WHERE id = $2 AND email_verified_at IS NULL",
+            now,
+            stored.user_id
+        )
+        .execute(&mut *transaction)
+        .await,
+    };
+    if let Err(e) = result {
+        if e.as_database_error().is_some_and(|db_error| db_error.constraint().is_some()) {
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "Email address already in use" }));
+        }
+        tracing::error!("Failed to mark email as verified: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE email_verification_tokens SET used_at = $1 WHERE id = $2",
+        now,
+        stored.id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to mark verification token as used: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Sends a new verification email to the authenticated user.
+/// Links from earlier emails stop working.
+#[tracing::instrument(
+    name = "Resend verification email",
+    skip(pool, mailer, application_settings, user),
+    fields(user_id = %user.id)
+)]
+pub async fn resend_verification_email(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let user = match sqlx::query!(
+        "SELECT email, email_verified_at FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if user.email_verified_at.is_some() {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": "Email address is already verified" }));
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE email_verification_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL AND email IS NULL",
+        Utc::now(),
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to invalidate previous verification tokens: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let token = match store_verification_token(&mut *transaction, user_id, None).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Failed to store verification token: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let message = verification_email(&user.email, &application_settings.base_url, &token);
+    if let Err(e) = mailer.send(&message).await {
+        tracing::error!("Failed to send verification email: {}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Creates a new verification token for the user and returns it in plain text.
+/// With `new_email` the token confirms a change to that address instead.
+pub async fn store_verification_token(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    new_email: Option<&str>
+) -> Result<String, sqlx::Error> {
+    let token = generate_token();
+    let now = Utc::now();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO email_verification_tokens (id, user_id, token_hash, expires_at, created_at, email)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        hash_token(&token),
+        now + Duration::hours(VERIFICATION_TOKEN_EXPIRATION_HOURS),
+        now,
+        new_email
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+pub fn verification_email(to: &str, base_url: &str, token: &str) -> EmailMessage {
+    EmailMessage {
+        to: to.to_string(),
+        subject: "Verify your Areum email address".to_string(),
+        body: format!(
+            "Welcome to Areum!\n\n\
+            Please confirm your email address within {} hours by opening the following link:\n\
+            {}/verify_email?token={}",
+            VERIFICATION_TOKEN_EXPIRATION_HOURS,
+            base_url,
+            token
+        ),
+    }
+}
+
+pub fn email_change_email(to: &str, base_url: &str, token: &str) -> EmailMessage {
+    EmailMessage {
+        to: to.to_string(),
+        subject: "Confirm your new Areum email address".to_string(),
+        body: format!(
+            "You asked to change the email address of your Areum account to this address.\n\n\
+            Please confirm it within {} hours by opening the following link:\n\
+            {}/verify_email?token={}\n\n\
+            Until then your previous address stays in use.",
+            VERIFICATION_TOKEN_EXPIRATION_HOURS,
+            base_url,
+            token
+        ),
+    }
+}
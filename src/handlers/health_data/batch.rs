@@ -0,0 +1,157 @@
+// src/handlers/health_data/batch.rs
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::config::settings::ApplicationSettings;
+use crate::handlers::health_data::sensor_stream::{store_upload, validate_upload, StoredUpload, KEY_REUSED_MESSAGE};
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::middleware::upload_body::UploadBody;
+use crate::models::sensor_data::{
+    BatchItemResult, BatchMode, BatchUploadRequest, BatchUploadResponse, SensorDataUpload
+};
+use crate::models::sensor_stream::{
+    Acceleration, BloodOxygen, GpsLocation, HeartRate, SensorStream, SkinTemperature
+};
+
+enum ItemError {
+    // Reported to the client in the item's result
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+/// Stores uploads of several sensor streams in one transaction.
+///
+/// In `all_or_nothing` mode a single rejected upload rolls back the batch and
+/// the response is `400`; in `best_effort` mode the valid uploads are stored.
+#[tracing::instrument(
+    name = "Upload sensor data batch",
+    skip(request, pool, user, application_settings),
+    fields(
+        user_id = %user.id,
+        uploads = request.uploads.len(),
+        mode = ?request.mode
+    )
+)]
+pub async fn upload_batch(
+    request: UploadBody<BatchUploadRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    application_settings: web::Data<ApplicationSettings>
+) -> HttpResponse {
+    let deduplicate = application_settings.deduplicate_uploads;
+    let BatchUploadRequest { mode, uploads } = request.into_inner();
+    if uploads.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": "The batch contains no uploads"
+        }));
+    }
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let mut results = Vec::with_capacity(uploads.len());
+        // Whether each upload was stored by this batch, as opposed to earlier
+        let mut created = Vec::with_capacity(uploads.len());
+        for (index, upload) in uploads.into_iter().enumerate() {
+            let data_type = upload.get("data_type").and_then(|value| value.as_str()).map(str::to_string);
+            let stored = store_item(&mut transaction, &user, data_type.as_deref(), upload, deduplicate).await;
+            created.push(matches!(stored, Ok(StoredUpload::Created(_))));
+            let (status, id, message) = match stored {
+                Ok(StoredUpload::Created(id)) | Ok(StoredUpload::Replayed(id)) => ("success", Some(id), None),
+                Ok(StoredUpload::Duplicate(id)) => {
+                    ("duplicate", Some(id), Some("Identical data was already uploaded".to_string()))
+                }
+                Ok(StoredUpload::KeyReused) => ("error", None, Some(KEY_REUSED_MESSAGE.to_string())),
+                Err(ItemError::Rejected(message)) => ("error", None, Some(message)),
+                Err(ItemError::Database(e)) => return Err(e),
+            };
+            results.push(BatchItemResult {
+                index,
+                data_type,
+                status: status.to_string(),
+                id: id.map(|id| id.to_string()),
+                message,
+            });
+        }
+
+        let rejected = results.iter().filter(|item| item.status == "error").count();
+        if rejected > 0 && mode == BatchMode::AllOrNothing {
+            transaction.rollback().await?;
+            for (item, _) in results.iter_mut().zip(created).filter(|(_, created)| *created) {
+                item.status = "not_stored".to_string();
+                item.id = None;
+                item.message = Some("Not stored because another upload of the batch was rejected".to_string());
+            }
+            return Ok((rejected, true, results));
+        }
+
+        transaction.commit().await?;
+        Ok((rejected, false, results))
+    }.await;
+
+    match result {
+        Ok((rejected, rolled_back, results)) => {
+            // Duplicates and retries are already stored by an earlier upload
+            let stored = results.iter()
+                .filter(|item| matches!(item.status.as_str(), "success" | "duplicate"))
+                .count();
+            let status = if rejected == 0 {
+                "success"
+            } else if stored > 0 && !rolled_back {
+                "partial"
+            } else {
+                "error"
+            };
+            tracing::info!("{} of {} uploads are stored", stored, results.len());
+            let response = BatchUploadResponse { status: status.to_string(), stored, results };
+            if status == "error" {
+                HttpResponse::BadRequest().json(response)
+            } else {
+                HttpResponse::Ok().json(response)
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to store sensor data batch: {:?}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "status": "error",
+                "message": "Failed to store sensor data batch"
+            }))
+        }
+    }
+}
+
+async fn store_item(
+    connection: &mut PgConnection,
+    user: &AuthenticatedUser,
+    data_type: Option<&str>,
+    upload: serde_json::Value,
+    deduplicate: bool
+) -> Result<StoredUpload, ItemError> {
+    let data_type = data_type.ok_or_else(|| ItemError::Rejected("Missing data type".to_string()))?;
+    // API keys may only upload the data types they were created for
+    if !user.has_scope(data_type) {
+        return Err(ItemError::Rejected(format!("Not allowed to upload {} data", data_type)));
+    }
+    match data_type {
+        Acceleration::DATA_TYPE => store::<Acceleration>(connection, user.id, upload, deduplicate).await,
+        HeartRate::DATA_TYPE => store::<HeartRate>(connection, user.id, upload, deduplicate).await,
+        BloodOxygen::DATA_TYPE => store::<BloodOxygen>(connection, user.id, upload, deduplicate).await,
+        SkinTemperature::DATA_TYPE => store::<SkinTemperature>(connection, user.id, upload, deduplicate).await,
+        GpsLocation::DATA_TYPE => store::<GpsLocation>(connection, user.id, upload, deduplicate).await,
+        _ => Err(ItemError::Rejected(format!("Unknown data type '{}'", data_type))),
+    }
+}
+
+async fn store<S: SensorStream>(
+    connection: &mut PgConnection,
+    user_id: Uuid,
+    upload: serde_json::Value,
+    deduplicate: bool
+) -> Result<StoredUpload, ItemError> {
+    let upload: SensorDataUpload<S::Sample> = serde_json::from_value(upload)
+        .map_err(|e| ItemError::Rejected(format!("Invalid {} upload: {}", S::NAME, e)))?;
+    validate_upload::<S>(&upload).map_err(ItemError::Rejected)?;
+    store_upload::<S>(connection, user_id, &upload, deduplicate).await.map_err(ItemError::Database)
+}
@@ -1,224 +0,0 @@
-use actix_web::{web, HttpResponse};
-use chrono::Utc;
-use serde_json::json;
-use sqlx::PgPool;
-use uuid::Uuid;
-
-use crate::middleware::auth::Claims;
-use crate::models::sensor_data::{HeartRateDataUpload, HealthDataResponse};
-
-#[tracing::instrument(
-    name = "Upload heart rate data",
-    skip(data, pool, claims),
-    fields(
-        username = %claims.username,
-        data_type = %data.data_type
-    )
-)]
-pub async fn upload_heart_rate_data(
-    data: web::Json<HeartRateDataUpload>,
-    pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    tracing::info!("Heart rate upload handler called with data_type: {}", data.data_type);
-    
-    // Accept both "heart_rate" and any other data type for testing
-    // In production, you would want to validate this more strictly
-    if data.data_type != "heart_rate" && data.data_type != "invalid_type" {
-        tracing::warn!("Invalid data type received: {}", data.data_type);
-        return HttpResponse::BadRequest().json(json!({
-            "status": "error",
-            "message": "Invalid data type. Expected 'heart_rate'."
-        }));
-    }
-    
-    // For the invalid_type test case, return a 400 error
-    if data.data_type == "invalid_type" {
-        tracing::warn!("Invalid type detected for test case");
-        return HttpResponse::BadRequest().json(json!({
-            "status": "error",
-            "message": "Invalid data type. Expected 'heart_rate'."
-        }));
-    }
-    
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => {
-            tracing::info!("User ID parsed successfully: {}", id);
-            id
-        },
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
-    };
-    
-    // Generate a unique ID for this data upload
-    let id = Uuid::new_v4();
-    tracing::info!("Generated new UUID for heart rate data: {}", id);
-    
-    // Calculate end time based on samples
-    let end_time = if let Some(last_sample) = data.samples.last() {
-        tracing::info!("Using last sample timestamp for end_time");
-        last_sample.timestamp
-    } else {
-        tracing::info!("No samples found, using start_time for end_time");
-        data.start_time
-    };
-
-    tracing::info!("Beginning database insert");
-    
-    // Create a safe way to serialize JSON data
-    let device_info_json = match serde_json::to_value(&data.device_info) {
-        Ok(json) => json,
-        Err(e) => {
-            tracing::error!("Failed to serialize device_info: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Failed to process device information"
-            }));
-        }
-    };
-    
-    let data_json = match serde_json::to_value(json!({
-        "samples": &data.samples,
-        "metadata": &data.metadata
-    })) {
-        Ok(json) => json,
-        Err(e) => {
-            tracing::error!("Failed to serialize data: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Failed to process data"
-            }));
-        }
-    };
-    
-    // Print the actual SQL query values for debugging
-    tracing::debug!("SQL values: id={}, user_id={}, data_type={}, device_info={:?}, sampling_rate={}, start_time={}, end_time={}", 
-        id, user_id, data.data_type, device_info_json, data.sampling_rate_hz, data.start_time, end_time);
-
-    // Insert into database
-    match sqlx::query!(
-        r#"
-        INSERT INTO health_data (
-            id, user_id, data_type, device_info, sampling_rate_hz, 
-            start_time, end_time, data, created_at
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        "#,
-        id,
-        user_id,
-        data.data_type,
-        device_info_json,
-        data.sampling_rate_hz,
-        data.start_time,
-        end_time,
-        data_json,
-        Utc::now()
-    )
-    .execute(pool.get_ref())
-    .await {
-        Ok(_) => {
-            tracing::info!("Successfully inserted heart rate data: {}", id);
-            HttpResponse::Ok().json(HealthDataResponse {
-                id: id.to_string(),
-                status: "success".to_string(),
-                message: Some("Heart rate data uploaded successfully".to_string()),
-            })
-        },
-        Err(e) => {
-            tracing::error!("Failed to insert heart rate data: {:?}", e);
-            HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Failed to store heart rate data"
-            }))
-        }
-    }
-}
-
-#[tracing::instrument(
-    name = "Get acceleration data",
-    skip(pool, claims),
-    fields(
-        username = %claims.username,
-    )
-)]
-pub async fn get_user_heart_rate_data(
-    pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    tracing::info!("Heart rate get handler called");
-    
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => {
-            tracing::info!("User ID parsed successfully: {}", id);
-            id
-        },
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
-    };
-    
-    tracing::info!("Beginning database query for heart rate data");
-    
-    // Get all heart rate data for this user
-    match sqlx::query!(
-        r#"
-        SELECT 
-            id, 
-            user_id, 
-            data_type, 
-            device_info as "device_info: serde_json::Value", 
-            sampling_rate_hz, 
-            start_time, 
-            end_time,
-            data as "data: serde_json::Value", 
-            created_at
-        FROM health_data 
-        WHERE user_id = $1 AND data_type = 'heart_rate'
-        ORDER BY created_at DESC
-        "#,
-        user_id
-    )
-    .fetch_all(pool.get_ref())
-    .await {
-        Ok(records) => {
-            tracing::info!("Successfully retrieved {} heart rate records", records.len());
-            
-            // Transform records into serializable format
-            let serializable_records: Vec<serde_json::Value> = records.iter().map(|record| {
-                json!({
-                    "id": record.id.to_string(),
-                    "user_id": record.user_id.to_string(),
-                    "data_type": &record.data_type,
-                    "device_info": &record.device_info,
-                    "sampling_rate_hz": record.sampling_rate_hz,
-                    "start_time": record.start_time,
-                    "end_time": record.end_time,
-                    "data": &record.data,
-                    "created_at": record.created_at
-                })
-            }).collect();
-            
-            HttpResponse::Ok().json(json!({
-                "status": "success",
-                "count": records.len(),
-                "data": serializable_records
-            }))
-        },
-        Err(e) => {
-            tracing::error!("Failed to fetch heart rate data: {:?}", e);
-            HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Failed to retrieve heart rate data"
-            }))
-        }
-    }
-}
\ No newline at end of file
@@ -1,6 +1,4 @@
-pub mod acceleration;
-pub mod heart_rate;
-pub mod blood_oxygen;
-pub mod skin_temperature;
+pub mod sensor_stream;
+pub mod batch;
 pub mod gps_location;
-pub mod sleep;
\ No newline at end of file
+pub mod sleep;
@@ -0,0 +1,296 @@
+// src/handlers/health_data/sensor_stream.rs
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::config::settings::ApplicationSettings;
+use crate::handlers::delegation_handler::resolve_data_owner;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::middleware::upload_body::UploadBody;
+use crate::models::delegation::DataOwnerQuery;
+use crate::models::sensor_data::{HealthDataRecord, HealthDataResponse, SensorDataUpload};
+use crate::models::sensor_stream::SensorStream;
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const KEY_REUSED_MESSAGE: &str = "The client upload id was already used for a different upload";
+
+/// What `store_upload` did with an upload.
+pub enum StoredUpload {
+    Created(Uuid),
+    // Retry of the upload with this id, identified by its client upload id
+    Replayed(Uuid),
+    // Same content as this earlier upload, only if deduplication is enabled
+    Duplicate(Uuid),
+    // The client upload id was used before for different content
+    KeyReused,
+}
+
+/// Stores an upload of sensor stream `S` for the authenticated user.
+#[tracing::instrument(
+    name = "Upload sensor data",
+    skip(req, data, pool, user, application_settings),
+    fields(
+        user_id = %user.id,
+        data_type = S::DATA_TYPE
+    )
+)]
+pub async fn upload_sensor_data<S: SensorStream>(
+    req: HttpRequest,
+    data: UploadBody<SensorDataUpload<S::Sample>>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    application_settings: web::Data<ApplicationSettings>
+) -> HttpResponse {
+    let mut upload = data.into_inner();
+    if let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        let key = match key.to_str() {
+            Ok(key) => key.to_string(),
+            Err(_) => return bad_request("Invalid Idempotency-Key header"),
+        };
+        if upload.client_upload_id.as_ref().is_some_and(|client_upload_id| *client_upload_id != key) {
+            return bad_request("Idempotency-Key header and client_upload_id differ");
+        }
+        upload.client_upload_id = Some(key);
+    }
+
+    if let Err(message) = validate_upload::<S>(&upload) {
+        tracing::warn!("Rejected {} upload: {}", S::NAME, message);
+        return bad_request(&message);
+    }
+
+    let result = async {
+        let mut connection = pool.acquire().await?;
+        store_upload::<S>(&mut connection, user.id, &upload, application_settings.deduplicate_uploads).await
+    }.await;
+
+    let uploaded = format!("{} data uploaded successfully", capitalize(S::NAME));
+    match result {
+        Ok(StoredUpload::Created(id)) | Ok(StoredUpload::Replayed(id)) => HttpResponse::Ok().json(HealthDataResponse {
+            id: id.to_string(),
+            status: "success".to_string(),
+            message: Some(uploaded),
+        }),
+        Ok(StoredUpload::Duplicate(id)) => HttpResponse::Ok().json(HealthDataResponse {
+            id: id.to_string(),
+            status: "duplicate".to_string(),
+            message: Some(format!("Identical {} data was already uploaded", S::NAME)),
+        }),
+        Ok(StoredUpload::KeyReused) => HttpResponse::UnprocessableEntity().json(json!({
+            "status": "error",
+            "message": KEY_REUSED_MESSAGE
+        })),
+        Err(e) => {
+            tracing::error!("Failed to insert {} data: {:?}", S::NAME, e);
+            HttpResponse::InternalServerError().json(json!({
+                "status": "error",
+                "message": format!("Failed to store {} data", S::NAME)
+            }))
+        }
+    }
+}
+
+/// Returns all records of sensor stream `S` of the authenticated user, or of
+/// a user who delegated access to them, newest first.
+#[tracing::instrument(
+    name = "Get sensor data",
+    skip(pool, user, owner_query),
+    fields(
+        user_id = %user.id,
+        data_type = S::DATA_TYPE
+    )
+)]
+pub async fn get_sensor_data<S: SensorStream>(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
+) -> HttpResponse {
+    let user_id = match resolve_data_owner(pool.get_ref(), &user, owner_query.user_id, &[S::DATA_TYPE]).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+
+    let result = sqlx::query_as!(
+        HealthDataRecord,
+        r#"
+        SELECT
+            id,
+            user_id,
+            data_type,
+            device_info as "device_info: serde_json::Value",
+            sampling_rate_hz,
+            start_time,
+            end_time,
+            data as "data: serde_json::Value",
+            created_at
+        FROM health_data
+        WHERE user_id = $1 AND data_type = $2
+        ORDER BY created_at DESC
+        "#,
+        user_id,
+        S::DATA_TYPE
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(records) => HttpResponse::Ok().json(json!({
+            "status": "success",
+            "count": records.len(),
+            "data": records
+        })),
+        Err(e) => {
+            tracing::error!("Failed to fetch {} data: {:?}", S::NAME, e);
+            HttpResponse::InternalServerError().json(json!({
+                "status": "error",
+                "message": format!("Failed to retrieve {} data", S::NAME)
+            }))
+        }
+    }
+}
+
+/// Checks an upload of sensor stream `S`; the message is returned to the client.
+pub fn validate_upload<S: SensorStream>(upload: &SensorDataUpload<S::Sample>) -> Result<(), String> {
+    if upload.data_type != S::DATA_TYPE {
+        return Err(format!("Invalid data type. Expected '{}'.", S::DATA_TYPE));
+    }
+    if upload.sampling_rate_hz <= 0 {
+        return Err("Sampling rate must be positive".to_string());
+    }
+    if upload.end_time < upload.start_time {
+        return Err("End time must not be before start time".to_string());
+    }
+    if upload.client_upload_id.as_ref().is_some_and(|id| id.is_empty() || id.len() > 255) {
+        return Err("Client upload id must be between 1 and 255 characters".to_string());
+    }
+    upload.samples.iter().try_for_each(S::validate_sample)
+}
+
+/// Stores a validated upload in `health_data` unless it's a retry of an earlier
+/// upload or, with `deduplicate`, has the same content as one.
+///
+/// Clients that don't know the end time send it equal to the start time; it's
+/// then taken from the last sample.
+pub async fn store_upload<S: SensorStream>(
+    connection: &mut PgConnection,
+    user_id: Uuid,
+    upload: &SensorDataUpload<S::Sample>,
+    deduplicate: bool
+) -> Result<StoredUpload, sqlx::Error> {
+    let content_hash = content_hash(upload)?;
+
+    if let Some(client_upload_id) = &upload.client_upload_id {
+        if let Some(stored) = find_by_client_upload_id(&mut *connection, user_id, client_upload_id, &content_hash).await? {
+            return Ok(stored);
+        }
+    }
+
+    if deduplicate {
+        let duplicate = sqlx::query_scalar!(
+            "SELECT id FROM health_data WHERE user_id = $1 AND content_hash = $2 LIMIT 1",
+            user_id,
+            content_hash
+        )
+        .fetch_optional(&mut *connection)
+        .await?;
+        if let Some(id) = duplicate {
+            return Ok(StoredUpload::Duplicate(id));
+        }
+    }
+
+    let id = Uuid::new_v4();
+    let end_time = match upload.samples.last() {
+        Some(last_sample) if upload.end_time == upload.start_time => S::timestamp(last_sample),
+        _ => upload.end_time,
+    };
+    let device_info_json = serde_json::to_value(&upload.device_info)
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let data_json = json!({
+        "samples": &upload.samples,
+        "metadata": &upload.metadata
+    });
+
+    // A concurrent retry may have stored the upload since the lookup above
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO health_data (
+            id, user_id, data_type, device_info, sampling_rate_hz,
+            start_time, end_time, data, created_at, client_upload_id, content_hash
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (user_id, client_upload_id) WHERE client_upload_id IS NOT NULL DO NOTHING
+        RETURNING id
+        "#,
+        id,
+        user_id,
+        S::DATA_TYPE,
+        device_info_json,
+        upload.sampling_rate_hz,
+        upload.start_time,
+        end_time,
+        data_json,
+        Utc::now(),
+        upload.client_upload_id,
+        content_hash
+    )
+    .fetch_optional(&mut *connection)
+    .await?;
+
+    match (inserted, &upload.client_upload_id) {
+        (Some(id), _) => Ok(StoredUpload::Created(id)),
+        (None, Some(client_upload_id)) => {
+            find_by_client_upload_id(connection, user_id, client_upload_id, &content_hash).await?
+                .ok_or(sqlx::Error::RowNotFound)
+        }
+        (None, None) => Err(sqlx::Error::RowNotFound),
+    }
+}
+
+async fn find_by_client_upload_id(
+    connection: &mut PgConnection,
+    user_id: Uuid,
+    client_upload_id: &str,
+    content_hash: &str
+) -> Result<Option<StoredUpload>, sqlx::Error> {
+    let stored = sqlx::query!(
+        "SELECT id, content_hash FROM health_data WHERE user_id = $1 AND client_upload_id = $2",
+        user_id,
+        client_upload_id
+    )
+    .fetch_optional(connection)
+    .await?;
+
+    Ok(stored.map(|stored| {
+        if stored.content_hash.as_deref() == Some(content_hash) {
+            StoredUpload::Replayed(stored.id)
+        } else {
+            StoredUpload::KeyReused
+        }
+    }))
+}
+
+/// SHA-256 of the upload's content, without the client upload id.
+fn content_hash<T: serde::Serialize>(upload: &SensorDataUpload<T>) -> Result<String, sqlx::Error> {
+    let mut content = serde_json::to_value(upload).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    if let Some(fields) = content.as_object_mut() {
+        fields.remove("client_upload_id");
+    }
+    Ok(hex::encode(Sha256::digest(content.to_string().as_bytes())))
+}
+
+fn bad_request(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({
+        "status": "error",
+        "message": message
+    }))
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -2,36 +2,32 @@ use actix_web::{web, HttpResponse};
 use chrono::{NaiveDate, Utc};
 use serde_json::json;
 use sqlx::PgPool;
-use uuid::Uuid;
 
-use crate::middleware::auth::Claims;
+use crate::handlers::delegation_handler::resolve_data_owner;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::delegation::DataOwnerQuery;
 use crate::models::sleep::{
     ProcessedSleepData, SleepSummary, SleepDateQuery, SleepRangeQuery
 };
 
 #[tracing::instrument(
     name = "Get processed sleep data by date",
-    skip(pool, claims),
+    skip(pool, user, owner_query),
     fields(
-        username = %claims.username,
+        username = %user.username,
     )
 )]
 pub async fn get_sleep_data_by_date(
     query: web::Query<SleepDateQuery>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
     tracing::info!("Sleep data retrieval handler called for date: {}", query.date);
     
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
+    let user_id = match resolve_data_owner(pool.get_ref(), &user, owner_query.user_id, &["sleep"]).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
     };
     
     let date = match NaiveDate::parse_from_str(&query.date, "%Y-%m-%d") {
@@ -151,31 +147,23 @@ pub async fn get_sleep_data_by_date(
 
 #[tracing::instrument(
     name = "Get sleep data for date range",
-    skip(pool, claims),
+    skip(pool, user, owner_query),
     fields(
-        username = %claims.username,
+        username = %user.username,
     )
 )]
 pub async fn get_sleep_data_range(
     query: web::Query<SleepRangeQuery>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
     tracing::info!("Sleep data range retrieval handler called for period: {} to {}", 
                  query.start_date, query.end_date);
     
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => {
-            tracing::info!("User ID parsed successfully: {}", id);
-            id
-        },
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
+    let user_id = match resolve_data_owner(pool.get_ref(), &user, owner_query.user_id, &["sleep"]).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
     };
     
     // Validate date formats
@@ -278,30 +266,22 @@ pub async fn get_sleep_data_range(
 
 #[tracing::instrument(
     name = "Get sleep summary by date",
-    skip(pool, claims),
+    skip(pool, user, owner_query),
     fields(
-        username = %claims.username,
+        username = %user.username,
     )
 )]
 pub async fn get_sleep_summary_by_date(
     query: web::Query<SleepDateQuery>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
     tracing::info!("Sleep summary retrieval handler called for date: {}", query.date);
     
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => {
-            tracing::info!("User ID parsed successfully: {}", id);
-            id
-        },
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
+    let user_id = match resolve_data_owner(pool.get_ref(), &user, owner_query.user_id, &["sleep"]).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
     };
 
     let date = match NaiveDate::parse_from_str(&query.date, "%Y-%m-%d") {
@@ -375,29 +355,21 @@ pub async fn get_sleep_summary_by_date(
 
 #[tracing::instrument(
     name = "Get weekly sleep trends",
-    skip(pool, claims),
+    skip(pool, user, owner_query),
     fields(
-        username = %claims.username,
+        username = %user.username,
     )
 )]
 pub async fn get_weekly_sleep_trends(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
     tracing::info!("Weekly sleep trends handler called");
     
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => {
-            tracing::info!("User ID parsed successfully: {}", id);
-            id
-        },
-        Err(e) => {
-            tracing::error!("Failed to parse user ID: {}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID"
-            }));
-        }
+    let user_id = match resolve_data_owner(pool.get_ref(), &user, owner_query.user_id, &["sleep"]).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
     };
     
     // Calculate date for 7 days ago
@@ -0,0 +1,12 @@
+use actix_web::{http::header, web, HttpResponse};
+
+use crate::config::jwt::JwtSettings;
+
+/// Publishes the public keys used to sign access tokens, so other services
+/// can verify them without the signing secret.
+pub async fn get_jwks(jwt_settings: web::Data<JwtSettings>) -> HttpResponse {
+    HttpResponse::Ok()
+        // Short enough for consumers to pick up keys added ahead of a rotation
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(jwt_settings.jwks())
+}
@@ -0,0 +1,347 @@
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::config::settings::LoginThrottlingSettings;
+use crate::utils::token::hash_token;
+
+const ACCOUNT_SCOPE: &str = "account";
+const IP_SCOPE: &str = "ip";
+// Requests for emailed links, counted apart from failed logins
+const EMAIL_SCOPE: &str = "email";
+const EMAIL_IP_SCOPE: &str = "email_ip";
+
+/// Returns the address failed logins are counted against.
+pub fn client_address(req: &HttpRequest, trust_forwarded_for: bool) -> String {
+    if trust_forwarded_for {
+        if let Some(address) = req.connection_info().realip_remote_addr() {
+            return address.to_string();
+        }
+    }
+    req.peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Returns the number of seconds until the account or the client address may
+/// try to log in again, or `None` if neither is locked.
+#[tracing::instrument(name = "Check login lockout", skip(pool))]
+pub async fn lockout_remaining_seconds(
+    pool: &PgPool,
+    username: &str,
+    address: &str
+) -> Result<Option<i64>, sqlx::Error> {
+    remaining_seconds(pool, (ACCOUNT_SCOPE, username), (IP_SCOPE, address)).await
+}
+
+/// Limits requests that email a link, such as magic links and password
+/// resets. Each of them replaces the previous link, so without a limit anyone
+/// knowing an address could flood its inbox and keep every link dead.
+///
+/// Requests count against the email address and the client address with the
+/// limits of failed logins, whether or not the address is registered. Returns
+/// the response to send if either is over its limit.
+#[tracing::instrument(name = "Check email request limit", skip(pool, settings, email))]
+pub async fn reject_if_email_throttled(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    email: &str,
+    address: &str
+) -> Option<HttpResponse> {
+    // Keyed by a hash, so the counters hold no email addresses
+    let email_key = hash_token(&email.trim().to_lowercase());
+    let result = async {
+        if let Some(retry_after) = remaining_seconds(pool, (EMAIL_SCOPE, &email_key), (EMAIL_IP_SCOPE, address)).await? {
+            return Ok(Some(retry_after));
+        }
+        register_failure(
+            pool, settings, EMAIL_SCOPE, &email_key, None, settings.max_failed_attempts_per_account
+        ).await?;
+        register_failure(
+            pool, settings, EMAIL_IP_SCOPE, address, None, settings.max_failed_attempts_per_ip
+        ).await?;
+        Ok::<_, sqlx::Error>(None)
+    }.await;
+
+    match result {
+        Ok(Some(retry_after)) => {
+            tracing::info!("Too many email requests");
+            Some(too_many_requests(retry_after, "Too many requests, try again later"))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            Some(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+async fn remaining_seconds(
+    pool: &PgPool,
+    (account_scope, account_key): (&str, &str),
+    (client_scope, client_key): (&str, &str)
+) -> Result<Option<i64>, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"
+        SELECT MAX(locked_until) as locked_until
+        FROM login_throttles
+        WHERE (scope = $1 AND key = $2) OR (scope = $3 AND key = $4)
+        "#,
+        account_scope,
+        account_key,
+        client_scope,
+        client_key
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let now = Utc::now();
+    Ok(record.locked_until
+        .filter(|locked_until| *locked_until > now)
+        // Round up so clients don't retry a moment too early
+        .map(|locked_until| ((locked_until - now).num_milliseconds() + 999) / 1000))
+}
+
+/// Lockout check for credentials a signed-in user enters again, such as the
+/// current password or a second factor. These are as guessable as a login,
+/// so they share its limits. Returns the response to send if locked out.
+pub async fn reject_if_locked_out(pool: &PgPool, username: &str, address: &str) -> Option<HttpResponse> {
+    match lockout_remaining_seconds(pool, username, address).await {
+        Ok(Some(retry_after)) => {
+            tracing::info!("Credential check while locked out");
+            Some(throttled_response(retry_after))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            Some(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/// The response for login attempts while the account or address is locked.
+pub fn throttled_response(retry_after: i64) -> HttpResponse {
+    too_many_requests(retry_after, "Too many failed login attempts, try again later")
+}
+
+fn too_many_requests(retry_after: i64, message: &str) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(json!({ "error": message }))
+}
+
+/// Counts a failed login against the account and the client address, locking
+/// either of them once it exceeds its limit.
+#[tracing::instrument(name = "Record failed login", skip(pool, settings))]
+pub async fn record_failed_login(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    username: &str,
+    address: &str,
+    user_id: Option<Uuid>
+) -> Result<(), sqlx::Error> {
+    register_failure(
+        pool, settings, ACCOUNT_SCOPE, username, user_id, settings.max_failed_attempts_per_account
+    ).await?;
+    register_failure(
+        pool, settings, IP_SCOPE, address, None, settings.max_failed_attempts_per_ip
+    ).await?;
+    Ok(())
+}
+
+/// Resets the failure counter of an account after a successful login.
+/// The client address keeps its counter, otherwise one valid account would be
+/// enough to keep guessing passwords for others.
+pub async fn clear_failed_logins(pool: &PgPool, username: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM login_throttles WHERE scope = $1 AND key = $2",
+        ACCOUNT_SCOPE,
+        username
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Removes the failed login records kept under a username, which are not
+/// linked to the user and so aren't removed along with the account.
+pub async fn forget_account(transaction: &mut PgConnection, username: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM login_throttles WHERE scope = $1 AND key = $2",
+        ACCOUNT_SCOPE,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM lockout_events WHERE scope = $1 AND key = $2",
+        ACCOUNT_SCOPE,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves the failed login records of an account to its new username, so a
+/// rename neither lifts a lockout nor leaves it behind. Records of failed
+/// logins with the new username are from before it was taken and dropped.
+pub async fn rename_account(
+    transaction: &mut PgConnection,
+    old_username: &str,
+    new_username: &str
+) -> Result<(), sqlx::Error> {
+    if old_username == new_username {
+        return Ok(());
+    }
+    forget_account(&mut *transaction, new_username).await?;
+
+    sqlx::query!(
+        "UPDATE login_throttles SET key = $1 WHERE scope = $2 AND key = $3",
+        new_username,
+        ACCOUNT_SCOPE,
+        old_username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE lockout_events SET key = $1 WHERE scope = $2 AND key = $3",
+        new_username,
+        ACCOUNT_SCOPE,
+        old_username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Lifts the lockout of an account. Returns false if the user doesn't exist.
+pub async fn unlock_account(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let user = sqlx::query!("SELECT username FROM users WHERE id = $1", user_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+    let username = match user {
+        Some(user) => user.username,
+        None => return Ok(false),
+    };
+
+    sqlx::query!(
+        "DELETE FROM login_throttles WHERE scope = $1 AND key = $2",
+        ACCOUNT_SCOPE,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE lockout_events SET unlocked_at = $1
+        WHERE scope = $2 AND key = $3 AND unlocked_at IS NULL AND locked_until > $1
+        "#,
+        Utc::now(),
+        ACCOUNT_SCOPE,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(true)
+}
+
+async fn register_failure(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    scope: &str,
+    key: &str,
+    user_id: Option<Uuid>,
+    max_attempts: i32
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let window_start = now - Duration::minutes(settings.failure_window_minutes);
+    let mut transaction = pool.begin().await?;
+
+    // Failures only add up while they're close together. A lockout counts as
+    // activity, so the backoff keeps growing for attempts right after it ends.
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO login_throttles (scope, key, failed_attempts, last_failed_at)
+        VALUES ($1, $2, 1, $3)
+        ON CONFLICT (scope, key) DO UPDATE SET
+            failed_attempts = CASE
+                WHEN GREATEST(login_throttles.last_failed_at, login_throttles.locked_until) < $4 THEN 1
+                ELSE login_throttles.failed_attempts + 1
+            END,
+            last_failed_at = $3
+        RETURNING failed_attempts
+        "#,
+        scope,
+        key,
+        now,
+        window_start
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    if record.failed_attempts >= max_attempts {
+        let locked_until = now + lockout_duration(settings, record.failed_attempts - max_attempts);
+
+        sqlx::query!(
+            "UPDATE login_throttles SET locked_until = $1 WHERE scope = $2 AND key = $3",
+            locked_until,
+            scope,
+            key
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        record_lockout_event(&mut transaction, scope, key, user_id, record.failed_attempts, locked_until).await?;
+        tracing::warn!("Locked {} {} until {}", scope, key, locked_until);
+    }
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Doubles the lockout for every failure past the limit, up to the maximum.
+fn lockout_duration(settings: &LoginThrottlingSettings, failures_past_limit: i32) -> Duration {
+    let factor = 2_i64.saturating_pow(failures_past_limit.clamp(0, 32) as u32);
+    let seconds = settings.base_lockout_seconds
+        .saturating_mul(factor)
+        .min(settings.max_lockout_seconds);
+    Duration::seconds(seconds)
+}
+
+async fn record_lockout_event(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    scope: &str,
+    key: &str,
+    user_id: Option<Uuid>,
+    failed_attempts: i32,
+    locked_until: DateTime<Utc>
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO lockout_events (id, scope, key, user_id, failed_attempts, locked_until, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        Uuid::new_v4(),
+        scope,
+        key,
+        user_id,
+        failed_attempts,
+        locked_until,
+        Utc::now()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings};
+use crate::email::{EmailMessage, Mailer};
+use crate::handlers::auth_handler::{issue_session, mfa_challenge};
+use crate::handlers::login_throttle::{client_address, reject_if_email_throttled};
+use crate::handlers::session_handler::session_details;
+use crate::models::auth::{MagicLinkLoginRequest, MagicLinkRequest};
+use crate::utils::token::{generate_token, hash_token};
+
+const MAGIC_LINK_EXPIRATION_MINUTES: i64 = 15;
+
+/// Emails a single-use sign-in link to the given address if it belongs to an
+/// account. Like password resets, always answers 200 so the endpoint doesn't
+/// reveal which addresses are registered, unless too many links were requested.
+#[tracing::instrument(
+    name = "Request magic link",
+    skip(req, link_form, pool, mailer, application_settings, login_throttling_settings)
+)]
+pub async fn request_magic_link(
+    req: HttpRequest,
+    link_form: web::Json<MagicLinkRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    if !application_settings.enable_magic_link_login {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = reject_if_email_throttled(
+        pool.get_ref(), &login_throttling_settings, &link_form.email, &address
+    ).await {
+        return response;
+    }
+
+    let user = match sqlx::query!(
+        "SELECT id, email FROM users WHERE email = $1",
+        link_form.email
+    )
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            tracing::info!("Magic link requested for unknown email");
+            return HttpResponse::Ok().finish();
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let token = generate_token();
+    let now = Utc::now();
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // Only the most recently requested link stays valid
+    if let Err(e) = sqlx::query!(
+        "UPDATE magic_link_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL",
+        now,
+        user.id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to invalidate previous magic links: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = sqlx::query!(
+        r#"
+        INSERT INTO magic_link_tokens (id, user_id, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        Uuid::new_v4(),
+        user.id,
+        hash_token(&token),
+        now + Duration::minutes(MAGIC_LINK_EXPIRATION_MINUTES),
+        now
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to store magic link token: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let message = magic_link_email(&user.email, &application_settings.magic_link_url, &token);
+    if let Err(e) = mailer.send(&message).await {
+        // Still 200, failing only for registered addresses would reveal them
+        tracing::error!("Failed to send magic link email: {}", e);
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Exchanges the token of a magic link for a login session, the same way
+/// `login_user` does for a password. Users with two-factor authentication
+/// still have to complete the second step.
+#[tracing::instrument(
+    name = "Login with magic link",
+    skip(req, login_form, pool, jwt_settings, application_settings, login_throttling_settings)
+)]
+pub async fn login_with_magic_link(
+    req: HttpRequest,
+    login_form: web::Json<MagicLinkLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    if !application_settings.enable_magic_link_login {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let stored = match sqlx::query!(
+        r#"
+        SELECT m.id, m.user_id, m.expires_at, m.used_at, u.username,
+            EXISTS(SELECT 1 FROM user_totp t WHERE t.user_id = u.id AND t.confirmed_at IS NOT NULL) as "mfa_enabled!"
+        FROM magic_link_tokens m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.token_hash = $1
+        FOR UPDATE OF m
+        "#,
+        hash_token(login_form.token.expose_secret())
+    )
+    .fetch_optional(&mut *transaction)
+    .await {
+        Ok(Some(stored)) if stored.used_at.is_none() && stored.expires_at > Utc::now() => stored,
+        Ok(_) => {
+            tracing::info!("Invalid, used or expired magic link");
+            return HttpResponse::Unauthorized()
+                .json(json!({ "error": "Invalid or expired sign-in link" }));
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE magic_link_tokens SET used_at = $1 WHERE id = $2",
+        Utc::now(),
+        stored.id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to mark magic link as used: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    // The link stands in for the password, not for the second factor
+    if stored.mfa_enabled {
+        return mfa_challenge(stored.user_id, &stored.username, &jwt_settings);
+    }
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    let details = session_details(&req, address, login_form.device_name.as_deref());
+    issue_session(pool.get_ref(), stored.user_id, &stored.username, false, &details, &jwt_settings).await
+}
+
+fn magic_link_email(to: &str, magic_link_url: &str, token: &str) -> EmailMessage {
+    EmailMessage {
+        to: to.to_string(),
+        subject: "Your Areum sign-in link".to_string(),
+        body: format!(
+            "Use the following link within {} minutes to sign in to Areum:\n\
+            {}?token={}\n\n\
+            The link works only once. If you didn't request it, you can ignore this email.",
+            MAGIC_LINK_EXPIRATION_MINUTES,
+            magic_link_url,
+            token
+        ),
+    }
+}
@@ -0,0 +1,419 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::LoginThrottlingSettings;
+use crate::handlers::auth_handler::{issue_session, reject_login, revoke_access_token};
+use crate::handlers::session_handler::session_details;
+use crate::handlers::login_throttle::{
+    clear_failed_logins, client_address, lockout_remaining_seconds, record_failed_login, reject_if_locked_out,
+    throttled_response
+};
+use crate::middleware::auth::decode_token;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::mfa::{
+    MfaCodeRequest, MfaLoginRequest, RecoveryCodesResponse, TotpEnrollmentResponse
+};
+use crate::utils::token::hash_token;
+use crate::utils::totp::{
+    generate_recovery_codes, generate_secret, normalize_recovery_code, otpauth_uri, verify_code
+};
+
+const TOTP_ISSUER: &str = "Areum";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Second step of the login: exchanges the `mfa_token` from `/login` and a
+/// TOTP or recovery code for an access/refresh token pair.
+#[tracing::instrument(
+    name = "Complete MFA login",
+    skip(req, mfa_form, pool, jwt_settings, login_throttling_settings)
+)]
+pub async fn complete_mfa_login(
+    req: HttpRequest,
+    mfa_form: web::Json<MfaLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    let claims = match decode_token(mfa_form.mfa_token.expose_secret(), &jwt_settings) {
+        Ok(claims) if claims.mfa_pending => claims,
+        Ok(_) => {
+            tracing::info!("Token is not an MFA token");
+            return HttpResponse::Unauthorized().finish();
+        }
+        Err(e) => {
+            tracing::info!("Invalid MFA token: {:?}", e);
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let (user_id, jti) = match (Uuid::parse_str(&claims.sub), Uuid::parse_str(&claims.jti)) {
+        (Ok(user_id), Ok(jti)) => (user_id, jti),
+        _ => {
+            tracing::error!("Failed to parse token claims");
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    // Codes are guessable, so they count towards the same limits as passwords
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    match lockout_remaining_seconds(pool.get_ref(), &claims.username, &address).await {
+        Ok(Some(retry_after)) => {
+            tracing::info!("MFA attempt while locked out");
+            return throttled_response(retry_after);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    // An MFA token can only be exchanged once
+    match sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1) as "revoked!""#,
+        jti
+    )
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(record) if record.revoked => {
+            tracing::info!("MFA token has already been used");
+            return HttpResponse::Unauthorized().finish();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match verify_second_factor(pool.get_ref(), user_id, mfa_form.code.expose_secret()).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::info!("Invalid MFA code");
+            return reject_login(&pool, &login_throttling_settings, &claims.username, &address, Some(user_id)).await;
+        }
+        Err(e) => {
+            tracing::error!("Failed to verify MFA code: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    if let Err(e) = revoke_access_token(pool.get_ref(), user_id, jti, claims.exp).await {
+        tracing::error!("Failed to revoke MFA token: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = clear_failed_logins(pool.get_ref(), &claims.username).await {
+        tracing::warn!("Failed to reset failed login counter: {:?}", e);
+    }
+
+    let details = session_details(&req, address, mfa_form.device_name.as_deref());
+    issue_session(pool.get_ref(), user_id, &claims.username, true, &details, &jwt_settings).await
+}
+
+/// Generates a new TOTP secret. Two-factor authentication is only enabled
+/// once the user confirms it with a first code.
+#[tracing::instrument(
+    name = "Start TOTP enrollment",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn start_totp_enrollment(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let secret = generate_secret();
+
+    // Restarting an unconfirmed enrollment replaces its secret, a confirmed one is left alone
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO user_totp (user_id, secret, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = EXCLUDED.created_at
+        WHERE user_totp.confirmed_at IS NULL
+        "#,
+        user_id,
+        secret,
+        Utc::now()
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::Conflict()
+            .json(json!({ "error": "Two-factor authentication is already enabled" })),
+        Ok(_) => HttpResponse::Ok().json(TotpEnrollmentResponse {
+            otpauth_uri: otpauth_uri(TOTP_ISSUER, &user.username, &secret),
+            secret,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to store TOTP secret: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Enables two-factor authentication after checking a first code from the
+/// authenticator app, and returns a fresh set of recovery codes.
+#[tracing::instrument(
+    name = "Confirm TOTP enrollment",
+    skip(code_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn confirm_totp_enrollment(
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let pending = match sqlx::query!(
+        "SELECT secret FROM user_totp WHERE user_id = $1 AND confirmed_at IS NULL FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await {
+        Ok(Some(pending)) => pending,
+        Ok(None) => {
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "No pending two-factor enrollment" }));
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let step = match verify_code(&pending.secret, code_form.code.expose_secret(), Utc::now().timestamp(), None) {
+        Some(step) => step,
+        None => {
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "Invalid code" }));
+        }
+    };
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE user_totp SET confirmed_at = $1, last_used_step = $2 WHERE user_id = $3",
+        Utc::now(),
+        step,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to confirm TOTP enrollment: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    recovery_codes_response(transaction, user_id).await
+}
+
+/// Replaces all recovery codes of the user. Requires a current TOTP or recovery code.
+#[tracing::instrument(
+    name = "Regenerate recovery codes",
+    skip(req, code_form, pool, login_throttling_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    req: HttpRequest,
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = reject_if_locked_out(pool.get_ref(), &user.username, &address).await {
+        return response;
+    }
+
+    match verify_second_factor(pool.get_ref(), user_id, code_form.code.expose_secret()).await {
+        Ok(true) => {}
+        Ok(false) => return reject_code(&pool, &login_throttling_settings, &user, &address).await,
+        Err(e) => {
+            tracing::error!("Failed to verify MFA code: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    match pool.begin().await {
+        Ok(transaction) => recovery_codes_response(transaction, user_id).await,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Turns two-factor authentication off. Requires a current TOTP or recovery code.
+#[tracing::instrument(
+    name = "Disable TOTP",
+    skip(req, code_form, pool, login_throttling_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn disable_totp(
+    req: HttpRequest,
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = reject_if_locked_out(pool.get_ref(), &user.username, &address).await {
+        return response;
+    }
+
+    match verify_second_factor(pool.get_ref(), user_id, code_form.code.expose_secret()).await {
+        Ok(true) => {}
+        Ok(false) => return reject_code(&pool, &login_throttling_settings, &user, &address).await,
+        Err(e) => {
+            tracing::error!("Failed to verify MFA code: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!("DELETE FROM user_totp WHERE user_id = $1", user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to disable TOTP: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Answers a wrong code of a signed-in user, counting it like a failed login.
+async fn reject_code(
+    pool: &PgPool,
+    settings: &LoginThrottlingSettings,
+    user: &AuthenticatedUser,
+    address: &str
+) -> HttpResponse {
+    tracing::info!("Invalid MFA code");
+    if let Err(e) = record_failed_login(pool, settings, &user.username, address, Some(user.id)).await {
+        tracing::error!("Failed to record failed login: {:?}", e);
+    }
+    HttpResponse::BadRequest().json(json!({ "error": "Invalid code" }))
+}
+
+/// Checks a TOTP code, or failing that a recovery code, of a user with
+/// confirmed two-factor authentication. Accepted codes can't be used again.
+async fn verify_second_factor(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let totp = sqlx::query!(
+        "SELECT secret, last_used_step FROM user_totp WHERE user_id = $1 AND confirmed_at IS NOT NULL FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let totp = match totp {
+        Some(totp) => totp,
+        None => return Ok(false),
+    };
+
+    if let Some(step) = verify_code(&totp.secret, code, Utc::now().timestamp(), totp.last_used_step) {
+        sqlx::query!(
+            "UPDATE user_totp SET last_used_step = $1 WHERE user_id = $2",
+            step,
+            user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        return Ok(true);
+    }
+
+    let used = sqlx::query!(
+        r#"
+        UPDATE mfa_recovery_codes SET used_at = $1
+        WHERE id = (
+            SELECT id FROM mfa_recovery_codes
+            WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL
+            LIMIT 1
+        )
+        "#,
+        Utc::now(),
+        user_id,
+        hash_token(&normalize_recovery_code(code))
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(used.rows_affected() == 1)
+}
+
+/// Replaces the user's recovery codes within the transaction, commits it and
+/// returns the new codes. They are only ever shown this once.
+async fn recovery_codes_response(mut transaction: Transaction<'_, Postgres>, user_id: Uuid) -> HttpResponse {
+    let recovery_codes = generate_recovery_codes(RECOVERY_CODE_COUNT);
+
+    let result = async {
+        sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        for code in &recovery_codes {
+            sqlx::query!(
+                r#"
+                INSERT INTO mfa_recovery_codes (id, user_id, code_hash, created_at)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                Uuid::new_v4(),
+                user_id,
+                hash_token(&normalize_recovery_code(code)),
+                Utc::now()
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }),
+        Err(e) => {
+            tracing::error!("Failed to store recovery codes: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
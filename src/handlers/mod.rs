@@ -2,4 +2,19 @@ pub mod registration_handler;
 pub mod backend_health_handler;
 pub mod auth_handler;
 pub mod health_data;
-pub mod onboarding;
\ No newline at end of file
+pub mod onboarding;
+pub mod password_reset_handler;
+pub mod email_verification_handler;
+pub mod login_throttle;
+pub mod admin_handler;
+pub mod mfa_handler;
+pub mod api_key_handler;
+pub mod jwks_handler;
+pub mod account_handler;
+pub mod session_handler;
+pub mod magic_link_handler;
+pub mod oidc_handler;
+pub mod audit_handler;
+pub mod delegation_handler;
+pub mod share_link_handler;
+pub mod study_handler;
\ No newline at end of file
@@ -0,0 +1,383 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::oidc::{ExternalIdentity, OidcError, OidcSettings};
+use crate::config::settings::{LoginThrottlingSettings, PasswordHashingSettings};
+use crate::handlers::auth_handler::{issue_session, mfa_challenge};
+use crate::handlers::login_throttle::client_address;
+use crate::handlers::session_handler::session_details;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::identity::{IdentityInfo, LinkIdentityRequest, OidcLoginRequest, OidcNonceResponse};
+use crate::utils::password::hash_password;
+use crate::utils::token::{generate_token, hash_token};
+
+const NONCE_EXPIRATION_MINUTES: i64 = 10;
+
+/// Hands out a nonce for a sign-in with an identity provider. The app passes
+/// it to the provider, and the ID token is only accepted with it once.
+#[tracing::instrument(name = "Issue OpenID Connect nonce", skip(pool))]
+pub async fn issue_nonce(pool: web::Data<PgPool>) -> HttpResponse {
+    let nonce = generate_token();
+    let now = Utc::now();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!("DELETE FROM oidc_nonces WHERE expires_at <= $1", now)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO oidc_nonces (nonce_hash, expires_at, created_at) VALUES ($1, $2, $3)",
+            hash_token(&nonce),
+            now + Duration::minutes(NONCE_EXPIRATION_MINUTES),
+            now
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(OidcNonceResponse {
+            nonce,
+            expires_in: NONCE_EXPIRATION_MINUTES * 60,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to store nonce: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Verifies an ID token and uses up the nonce it carries, so that the same
+/// token can't be presented again.
+async fn verify_id_token(
+    pool: &PgPool,
+    oidc_settings: &OidcSettings,
+    provider: &str,
+    id_token: &str
+) -> Result<ExternalIdentity, HttpResponse> {
+    let identity = oidc_settings.verify(provider, id_token).await.map_err(oidc_error_response)?;
+
+    let consumed = sqlx::query!(
+        "DELETE FROM oidc_nonces WHERE nonce_hash = $1 AND expires_at > $2",
+        hash_token(&identity.nonce),
+        Utc::now()
+    )
+    .execute(pool)
+    .await;
+
+    match consumed {
+        Ok(result) if result.rows_affected() == 1 => Ok(identity),
+        Ok(_) => Err(oidc_error_response(OidcError::InvalidToken("unknown or used nonce".to_string()))),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            Err(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/// Logs in with an ID token of an external identity provider. Unknown
+/// identities get a new account, unless their email address already belongs
+/// to one; that account has to link the identity first.
+#[tracing::instrument(
+    name = "Login with OpenID Connect",
+    skip(req, login_form, pool, jwt_settings, oidc_settings, password_hashing_settings, login_throttling_settings),
+    fields(
+        provider = %login_form.provider
+    )
+)]
+pub async fn login_with_oidc(
+    req: HttpRequest,
+    login_form: web::Json<OidcLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    oidc_settings: web::Data<OidcSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    let identity = match verify_id_token(
+        pool.get_ref(), &oidc_settings, &login_form.provider, login_form.id_token.expose_secret()
+    ).await {
+        Ok(identity) => identity,
+        Err(response) => return response,
+    };
+
+    let linked = sqlx::query!(
+        r#"
+        UPDATE user_identities i SET last_login_at = $3
+        FROM users u
+        WHERE i.issuer = $1 AND i.subject = $2 AND u.id = i.user_id
+        RETURNING u.id, u.username,
+            EXISTS(SELECT 1 FROM user_totp t WHERE t.user_id = u.id AND t.confirmed_at IS NOT NULL) as "mfa_enabled!"
+        "#,
+        identity.issuer,
+        identity.subject,
+        Utc::now()
+    )
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    let (user_id, username, mfa_enabled) = match linked {
+        Ok(Some(user)) => (user.id, user.username, user.mfa_enabled),
+        Ok(None) => match create_account(pool.get_ref(), &identity, &password_hashing_settings).await {
+            Ok(Ok((user_id, username))) => (user_id, username, false),
+            Ok(Err(response)) => return response,
+            Err(e) => {
+                tracing::error!("Failed to create account for external identity: {:?}", e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        },
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // The provider stands in for the password, not for the second factor
+    if mfa_enabled {
+        return mfa_challenge(user_id, &username, &jwt_settings);
+    }
+
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    let details = session_details(&req, address, login_form.device_name.as_deref());
+    issue_session(pool.get_ref(), user_id, &username, false, &details, &jwt_settings).await
+}
+
+/// Creates an account for an identity that isn't linked to one yet. Returns
+/// the response to send instead if the identity can't get an account.
+async fn create_account(
+    pool: &PgPool,
+    identity: &ExternalIdentity,
+    password_hashing_settings: &PasswordHashingSettings
+) -> Result<Result<(Uuid, String), HttpResponse>, sqlx::Error> {
+    let email = match identity.email.as_deref() {
+        Some(email) if identity.email_verified => email,
+        _ => {
+            tracing::info!("External identity without a verified email address");
+            return Ok(Err(HttpResponse::BadRequest()
+                .json(json!({ "error": "The identity provider didn't confirm an email address" }))));
+        }
+    };
+
+    let existing = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS(SELECT 1 FROM users WHERE email = $1) as "email_taken!",
+            EXISTS(SELECT 1 FROM users WHERE username = $2) as "username_taken!"
+        "#,
+        email,
+        username_from_email(email)
+    )
+    .fetch_one(pool)
+    .await?;
+
+    // Linking by email alone would hand the account to whoever controls the
+    // identity, so the owner has to link it while logged in
+    if existing.email_taken {
+        tracing::info!("External identity with the email address of an existing account");
+        return Ok(Err(HttpResponse::Conflict().json(json!({
+            "error": "An account with this email address already exists. Log in and link the identity in the account settings."
+        }))));
+    }
+
+    let username = match existing.username_taken {
+        false => username_from_email(email),
+        true => format!("{}_{}", username_from_email(email), &Uuid::new_v4().simple().to_string()[..6]),
+    };
+    let user_id = Uuid::new_v4();
+    let now = Utc::now();
+    let mut transaction = pool.begin().await?;
+
+    // The account has no usable password until the user sets one with a password reset
+    sqlx::query!(
+        r#"
+        INSERT INTO users (id, username, password_hash, email, email_verified_at, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5, $5)
+        "#,
+        user_id,
+        username,
+        hash_password(&generate_token(), password_hashing_settings),
+        email,
+        now
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_identities (id, user_id, provider, issuer, subject, email, created_at, last_login_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        identity.provider,
+        identity.issuer,
+        identity.subject,
+        identity.email,
+        now
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    tracing::info!("Created account {} for external identity", user_id);
+
+    Ok(Ok((user_id, username)))
+}
+
+/// A username in the registration format based on the local part of the address.
+fn username_from_email(email: &str) -> String {
+    let local_part: String = email
+        .split('@')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        .take(40)
+        .collect();
+
+    match local_part.len() {
+        0..=2 => format!("user{}", local_part),
+        _ => local_part,
+    }
+}
+
+/// Links an external identity to the authenticated user's account, so it can
+/// be used to log in.
+#[tracing::instrument(
+    name = "Link external identity",
+    skip(link_form, pool, oidc_settings, user),
+    fields(
+        user_id = %user.id,
+        provider = %link_form.provider
+    )
+)]
+pub async fn link_identity(
+    link_form: web::Json<LinkIdentityRequest>,
+    pool: web::Data<PgPool>,
+    oidc_settings: web::Data<OidcSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let identity = match verify_id_token(
+        pool.get_ref(), &oidc_settings, &link_form.provider, link_form.id_token.expose_secret()
+    ).await {
+        Ok(identity) => identity,
+        Err(response) => return response,
+    };
+
+    let result = sqlx::query_as!(
+        IdentityInfo,
+        r#"
+        INSERT INTO user_identities (id, user_id, provider, issuer, subject, email, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (issuer, subject) DO NOTHING
+        RETURNING id, provider, email, created_at, last_login_at
+        "#,
+        Uuid::new_v4(),
+        user.id,
+        identity.provider,
+        identity.issuer,
+        identity.subject,
+        identity.email,
+        Utc::now()
+    )
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(linked)) => HttpResponse::Created().json(linked),
+        Ok(None) => HttpResponse::Conflict()
+            .json(json!({ "error": "This identity is already linked to an account" })),
+        Err(e) => {
+            tracing::error!("Failed to link identity: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the external identities linked to the authenticated user's account.
+#[tracing::instrument(
+    name = "List external identities",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_identities(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let identities = sqlx::query_as!(
+        IdentityInfo,
+        r#"
+        SELECT id, provider, email, created_at, last_login_at
+        FROM user_identities
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+        user.id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match identities {
+        Ok(identities) => HttpResponse::Ok().json(identities),
+        Err(e) => {
+            tracing::error!("Failed to fetch identities: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Unlinks an external identity. Logging in with it afterwards doesn't reach
+/// this account anymore.
+#[tracing::instrument(
+    name = "Unlink external identity",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn unlink_identity(
+    identity_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let result = sqlx::query!(
+        "DELETE FROM user_identities WHERE id = $1 AND user_id = $2",
+        identity_id.into_inner(),
+        user.id
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound()
+            .json(json!({ "error": "Identity not found" })),
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to unlink identity: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+fn oidc_error_response(error: OidcError) -> HttpResponse {
+    match error {
+        OidcError::UnknownProvider => HttpResponse::BadRequest()
+            .json(json!({ "error": "Unknown identity provider" })),
+        OidcError::InvalidToken(_) => {
+            tracing::info!("{}", error);
+            HttpResponse::Unauthorized().json(json!({ "error": "Invalid ID token" }))
+        }
+        OidcError::KeysUnavailable(_) => {
+            tracing::error!("{}", error);
+            HttpResponse::BadGateway().json(json!({ "error": "Identity provider unavailable" }))
+        }
+    }
+}
@@ -4,35 +4,24 @@ use sqlx::PgPool;
 use uuid::Uuid;
 use num_traits::cast::ToPrimitive;
 
-use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{
     ApiResponse, BasicInfoRequest, BasicInfoResponse
 };
 
 #[tracing::instrument(
     name = "Submit basic info",
-    skip(data, pool, claims),
+    skip(data, pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn submit_basic_info(
     data: web::Json<BasicInfoRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid user ID format");
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
     
     // Update or insert user profile
     let now = Utc::now();
@@ -206,27 +195,16 @@ pub async fn submit_basic_info(
 // Handler for getting basic info
 #[tracing::instrument(
     name = "Get basic info",
-    skip(pool, claims),
+    skip(pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn get_basic_info(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid user ID format");
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
 
     // Get user profile
     let profile = match sqlx::query!(
@@ -2,9 +2,8 @@ use actix_web::{web, HttpResponse};
 use chrono::{NaiveTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
-use serde_json::json;
 
-use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{
     ApiResponse, LifestyleHealthRequest, LifestyleHealthResponse
 };
@@ -13,18 +12,9 @@ use crate::models::onboarding::{MedicalConditionType, LifestyleInfo};
 pub async fn submit_lifestyle_health(
     data: web::Json<LifestyleHealthRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(e) => {
-            tracing::error!("Failed to parse user_id as UUID: {:?}", e);
-            return HttpResponse::InternalServerError().json(json!({
-                "status": "error",
-                "message": "Invalid user ID format"
-            }));
-        }
-    };
+    let user_id = user.id;
 
     // Create lifestyle info record first
     let lifestyle_id = Uuid::new_v4();
@@ -189,26 +179,16 @@ pub async fn submit_lifestyle_health(
 // Handler for getting lifestyle & health info
 #[tracing::instrument(
     name = "Get lifestyle health info",
-    skip(pool, claims),
+    skip(pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn get_lifestyle_health(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
 
     // Get lifestyle info
     let result = sqlx::query_as!(
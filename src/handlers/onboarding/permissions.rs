@@ -1,37 +1,29 @@
 use actix_web::{web, HttpResponse};
 use chrono::Utc;
+use serde_json::json;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::middleware::auth::Claims;
+use crate::handlers::audit_handler::record_audit_event;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{
     ApiResponse, PermissionsSetupRequest, PermissionsSetupResponse, 
     ThirdPartyConnectionResponse, PermissionsSettings
 };
+use crate::models::audit::{AuditEventType, NewAuditEvent};
 
 #[tracing::instrument(
     name = "Get permissions setup",
-    skip(pool, claims),
+    skip(pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn get_permissions_setup(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid user ID format");
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
 
     // Get permissions settings from database
     let result = sqlx::query_as!(
@@ -115,28 +107,31 @@ pub async fn get_permissions_setup(
 
 #[tracing::instrument(
     name = "Submit permissions setup",
-    skip(data,pool, claims),
+    skip(data,pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn submit_permissions_setup(
     data: web::Json<PermissionsSetupRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(ApiResponse {
+    let user_id = user.id;
+    let now = Utc::now();
+
+    // The settings are only changed along with their audit event
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse {
                 status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
+                message: Some(format!("Failed to begin transaction: {}", e)),
                 data: None::<()>,
             });
         }
     };
-    let now = Utc::now();
 
     // Update or insert permissions settings
     let result = sqlx::query!(
@@ -167,7 +162,7 @@ pub async fn submit_permissions_setup(
         data.background_usage_enabled,
         now
     )
-    .execute(pool.get_ref())
+    .execute(&mut *transaction)
     .await;
 
     if let Err(e) = result {
@@ -186,7 +181,7 @@ pub async fn submit_permissions_setup(
             "SELECT id FROM third_party_connection_types WHERE name = $1",
             connection.connection_type
         )
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(&mut *transaction)
         .await
         {
             Ok(Some(record)) => record.id,
@@ -229,7 +224,7 @@ pub async fn submit_permissions_setup(
             connection.connection_data,
             now
         )
-        .execute(pool.get_ref())
+        .execute(&mut *transaction)
         .await;
 
         if let Err(e) = result {
@@ -242,6 +237,27 @@ pub async fn submit_permissions_setup(
         }
     }
 
+    let event = NewAuditEvent::new(AuditEventType::PermissionsChanged, Some(user_id)).details(json!({
+        "heart_rate_enabled": data.heart_rate_enabled,
+        "temperature_enabled": data.temperature_enabled,
+        "spo2_enabled": data.spo2_enabled,
+        "accelerometer_enabled": data.accelerometer_enabled,
+        "notifications_enabled": data.notifications_enabled,
+        "background_usage_enabled": data.background_usage_enabled,
+        "third_party_connections": data.third_party_connections
+            .iter()
+            .map(|connection| connection.connection_type.as_str())
+            .collect::<Vec<_>>(),
+    }));
+    if let Err(e) = record_audit_event(&mut *transaction, event).await {
+        tracing::error!("Failed to record audit event: {:?}", e);
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            status: "error".to_string(),
+            message: Some(format!("Failed to record audit event: {}", e)),
+            data: None::<()>,
+        });
+    }
+
     // Update onboarding progress
     let result = sqlx::query!(
         r#"
@@ -255,7 +271,7 @@ pub async fn submit_permissions_setup(
         now,
         user_id
     )
-    .execute(pool.get_ref())
+    .execute(&mut *transaction)
     .await;
 
     if let Err(e) = result {
@@ -267,6 +283,15 @@ pub async fn submit_permissions_setup(
         });
     }
 
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            status: "error".to_string(),
+            message: Some(format!("Failed to commit transaction: {}", e)),
+            data: None::<()>,
+        });
+    }
+
     // Get the updated permissions settings and third-party connections for the response
     let permissions = match sqlx::query_as!(
         PermissionsSettings,
@@ -3,35 +3,24 @@ use chrono::Utc;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{
     ApiResponse, PersonalizationRequest, PersonalizationResponse
 };
 
 #[tracing::instrument(
     name = "Submit personalization",
-    skip(data, pool, claims),
+    skip(data, pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn submit_personalization(
     data: web::Json<PersonalizationRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid user ID format");
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,   
-            });
-        }
-    };
+    let user_id = user.id;
 
     let id = Uuid::new_v4();
     let now = Utc::now();
@@ -73,27 +62,16 @@ pub async fn submit_personalization(
 // Handler for getting personalization info
 #[tracing::instrument(
     name = "Get personalization info",
-    skip(pool, claims),
+    skip(pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn get_personalization(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid user ID format");
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
 
     // Get personalization info
     let personalization = match sqlx::query_as!(
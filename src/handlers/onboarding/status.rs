@@ -1,34 +1,23 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
-use uuid::Uuid;
 
-use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{ApiResponse, OnboardingStatusResponse};
 use crate::handlers::onboarding::common::get_or_create_onboarding_progress;
 
 /// Handler for getting a user's onboarding status
 #[tracing::instrument(
     name = "Get onboarding status",
-    skip(pool, claims),
+    skip(pool, user),
     fields(
-        user_id = %claims.sub
+        user_id = %user.id
     )
 )]
 pub async fn get_onboarding_status(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
+    user: AuthenticatedUser,
 ) -> HttpResponse {
-    // Parse user ID from claims
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(ApiResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                data: None::<()>,
-            });
-        }
-    };
+    let user_id = user.id;
 
     // Get or create onboarding progress record
     let _ = match get_or_create_onboarding_progress(user_id, &pool).await {
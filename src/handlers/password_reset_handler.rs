@@ -0,0 +1,212 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings, PasswordHashingSettings};
+use crate::email::{EmailMessage, Mailer};
+use crate::handlers::auth_handler::revoke_all_tokens;
+use crate::handlers::login_throttle::{client_address, reject_if_email_throttled};
+use crate::models::auth::{PasswordResetConfirmRequest, PasswordResetRequest};
+use crate::utils::password::{hash_password, validate_password};
+use crate::utils::token::{generate_token, hash_token};
+
+const RESET_TOKEN_EXPIRATION_MINUTES: i64 = 30;
+
+/// Sends a password reset link to the given address if it belongs to an account.
+///
+/// Always answers 200, unless too many links were requested, so that the
+/// endpoint can't be used to find out which email addresses are registered.
+#[tracing::instrument(
+    name = "Request password reset",
+    skip(req, reset_form, pool, mailer, application_settings, login_throttling_settings)
+)]
+pub async fn request_password_reset(
+    req: HttpRequest,
+    reset_form: web::Json<PasswordResetRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    let address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    if let Some(response) = reject_if_email_throttled(
+        pool.get_ref(), &login_throttling_settings, &reset_form.email, &address
+    ).await {
+        return response;
+    }
+
+    let user = match sqlx::query!(
+        "SELECT id, email FROM users WHERE email = $1",
+        reset_form.email
+    )
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            tracing::info!("Password reset requested for unknown email");
+            return HttpResponse::Ok().finish();
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let token = generate_token();
+    let now = Utc::now();
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // Only the most recently requested link stays valid
+    if let Err(e) = sqlx::query!(
+        "UPDATE password_reset_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL",
+        now,
+        user.id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to invalidate previous reset tokens: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = sqlx::query!(
+        r#"
+        INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        Uuid::new_v4(),
+        user.id,
+        hash_token(&token),
+        now + Duration::minutes(RESET_TOKEN_EXPIRATION_MINUTES),
+        now
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to store reset token: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let message = EmailMessage {
+        to: user.email,
+        subject: "Reset your Areum password".to_string(),
+        body: format!(
+            "Someone requested a password reset for your Areum account.\n\n\
+            Use the following link within {} minutes to choose a new password:\n\
+            {}?token={}\n\n\
+            If you didn't request this, you can ignore this email.",
+            RESET_TOKEN_EXPIRATION_MINUTES,
+            application_settings.password_reset_url,
+            token
+        ),
+    };
+
+    // Still 200, failing only for registered addresses would reveal them
+    if let Err(e) = mailer.send(&message).await {
+        tracing::error!("Failed to send password reset email: {}", e);
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+/// Sets a new password using a reset token. All existing sessions are revoked.
+#[tracing::instrument(
+    name = "Confirm password reset",
+    skip(confirm_form, pool, password_hashing_settings)
+)]
+pub async fn confirm_password_reset(
+    confirm_form: web::Json<PasswordResetConfirmRequest>,
+    pool: web::Data<PgPool>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>
+) -> HttpResponse {
+    if let Err(message) = validate_password(confirm_form.new_password.expose_secret()) {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": message }));
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let stored = match sqlx::query!(
+        r#"
+        SELECT id, user_id, expires_at, used_at
+        FROM password_reset_tokens
+        WHERE token_hash = $1
+        FOR UPDATE
+        "#,
+        hash_token(confirm_form.token.expose_secret())
+    )
+    .fetch_optional(&mut *transaction)
+    .await {
+        Ok(Some(stored)) if stored.used_at.is_none() && stored.expires_at > Utc::now() => stored,
+        Ok(_) => {
+            tracing::info!("Invalid, used or expired reset token");
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "Invalid or expired reset token" }));
+        }
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let now = Utc::now();
+    let password_hash = hash_password(
+        confirm_form.new_password.expose_secret(),
+        &password_hashing_settings
+    );
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3",
+        password_hash,
+        now,
+        stored.user_id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to update password: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = sqlx::query!(
+        "UPDATE password_reset_tokens SET used_at = $1 WHERE id = $2",
+        now,
+        stored.id
+    )
+    .execute(&mut *transaction)
+    .await {
+        tracing::error!("Failed to mark reset token as used: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    // Whoever knew the old password shouldn't stay logged in
+    if let Err(e) = revoke_all_tokens(&mut transaction, stored.user_id).await {
+        tracing::error!("Failed to revoke tokens after password reset: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().finish()
+}
@@ -1,17 +1,20 @@
 use actix_web::{web, HttpResponse};
 use secrecy::ExposeSecret;
 use serde_json::json;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use chrono::Utc;
 use uuid::Uuid;
 
+use crate::config::settings::{ApplicationSettings, PasswordHashingSettings};
+use crate::email::Mailer;
+use crate::handlers::email_verification_handler::{store_verification_token, verification_email};
 use crate::models::user::RegistrationRequest;
-use crate::utils::password::hash_password;
+use crate::utils::password::{hash_password, validate_password};
 
 #[tracing::instrument(
     name = "Adding a new user",
     // Don't show arguments
-    skip(user_form, pool),
+    skip(user_form, pool, password_hashing_settings, mailer, application_settings),
     fields(
         username = %user_form.username,
         email = %user_form
@@ -19,43 +22,42 @@ use crate::utils::password::hash_password;
 )]
 pub async fn register_user(
     user_form: web::Json<RegistrationRequest>,
-    pool: web::Data<PgPool>
+    pool: web::Data<PgPool>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>
 ) -> HttpResponse {
     tracing::info!("Received registration request for username: {}", user_form.username);
     // Validate input data
-    if user_form.username.is_empty() {
-        tracing::error!("Username cannot be empty");
+    if let Err(message) = validate_username(&user_form.username) {
+        tracing::error!("{}", message);
         return HttpResponse::BadRequest()
-            .json(json!({ "error": "Username cannot be empty" }));
+            .json(json!({ "error": message }));
     }
 
-    if user_form.username.len() < 3 || user_form.username.len() > 50 {
-        tracing::error!("Username must be between 3 and 50 characters");
+    if let Err(message) = validate_password(user_form.password.expose_secret()) {
+        tracing::error!("{}", message);
         return HttpResponse::BadRequest()
-            .json(json!({ "error": "Username must be between 3 and 50 characters" }));
+            .json(json!({ "error": message }));
     }
 
-    if user_form.password.expose_secret().is_empty() {
-        tracing::error!("Password cannot be empty");
+    if let Err(message) = validate_email(&user_form.email) {
+        tracing::error!("{}", message);
         return HttpResponse::BadRequest()
-            .json(json!({ "error": "Password cannot be empty" }));
-    }
-
-    if user_form.password.expose_secret().len() < 8 {
-        tracing::error!("Password must be at least 8 characters long");
-        return HttpResponse::BadRequest()
-            .json(json!({ "error": "Password must be at least 8 characters long" }));
-    }
-
-    if user_form.email.is_empty() || !user_form.email.contains('@') {
-        tracing::error!("Valid email address is required");
-        return HttpResponse::BadRequest()
-            .json(json!({ "error": "Valid email address is required" }));
+            .json(json!({ "error": message }));
     }
 
     // Proceed with user registration if validation passes
-    match insert_user(&user_form, &pool).await {
-        Ok(_) => HttpResponse::Ok().finish(),
+    let mut transaction = match pool.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let user_id = match insert_user(&user_form, &mut *transaction, &password_hashing_settings).await {
+        Ok(user_id) => user_id,
         Err(e) => {
             // Check for specific database errors like unique constraint violations
             if let Some(db_error) = e.as_database_error() {
@@ -67,32 +69,72 @@ pub async fn register_user(
             }
             // Log other errors and return generic error
             tracing::error!("Failed to execute query: {:?}", e);
-            HttpResponse::InternalServerError().finish()
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let token = match store_verification_token(&mut *transaction, user_id, None).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Failed to store verification token: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
         }
+    };
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        return HttpResponse::InternalServerError().finish();
     }
+
+    // The account exists at this point, a lost email can be resent later
+    let message = verification_email(&user_form.email, &application_settings.base_url, &token);
+    if let Err(e) = mailer.send(&message).await {
+        tracing::error!("Failed to send verification email: {}", e);
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+pub fn validate_username(username: &str) -> Result<(), &'static str> {
+    if username.is_empty() {
+        return Err("Username cannot be empty");
+    }
+    if username.len() < 3 || username.len() > 50 {
+        return Err("Username must be between 3 and 50 characters");
+    }
+    Ok(())
+}
+
+pub fn validate_email(email: &str) -> Result<(), &'static str> {
+    if email.is_empty() || !email.contains('@') {
+        return Err("Valid email address is required");
+    }
+    Ok(())
 }
 
 pub async fn insert_user(
     user_form: &web::Json<RegistrationRequest>,
-    pool: &PgPool
-) -> Result<(), sqlx::Error> {
+    executor: impl PgExecutor<'_>,
+    password_hashing_settings: &PasswordHashingSettings
+) -> Result<Uuid, sqlx::Error> {
+    let user_id = Uuid::new_v4();
     sqlx::query!(
         r#"
         INSERT INTO users (id, username, password_hash, email, created_at, updated_at)
         VALUES ($1, $2, $3, $4, $5, $6)
         "#,
-        Uuid::new_v4(),
+        user_id,
         &user_form.username,
-        &hash_password(&user_form.password.expose_secret()),
+        &hash_password(user_form.password.expose_secret(), password_hashing_settings),
         &user_form.email,
         Utc::now(),
         Utc::now()
     )
-    .execute(pool)
+    .execute(executor)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
         e
     })?;
-    Ok(())
+    Ok(user_id)
 }
\ No newline at end of file
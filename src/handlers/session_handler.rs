@@ -0,0 +1,162 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::session::{SessionDetails, SessionInfo};
+
+const MAX_DEVICE_NAME_LENGTH: usize = 100;
+
+/// Collects the details of a new session from the login request.
+pub fn session_details(req: &HttpRequest, ip_address: String, device_name: Option<&str>) -> SessionDetails {
+    SessionDetails {
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string()),
+        ip_address: Some(ip_address),
+        device_name: device_name
+            .map(|name| name.trim().chars().take(MAX_DEVICE_NAME_LENGTH).collect::<String>())
+            .filter(|name| !name.is_empty()),
+    }
+}
+
+pub async fn create_session(
+    executor: impl PgExecutor<'_>,
+    session_id: Uuid,
+    user_id: Uuid,
+    details: &SessionDetails,
+    expires_at: DateTime<Utc>
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query!(
+        r#"
+        INSERT INTO sessions (id, user_id, user_agent, ip_address, device_name, created_at, last_seen_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6, $7)
+        "#,
+        session_id,
+        user_id,
+        details.user_agent,
+        details.ip_address,
+        details.device_name,
+        now,
+        expires_at
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The details of an existing session, to carry them over to a replacement.
+pub async fn load_session_details(pool: &PgPool, session_id: Uuid) -> Result<SessionDetails, sqlx::Error> {
+    let details = sqlx::query_as!(
+        SessionDetails,
+        "SELECT user_agent, ip_address, device_name FROM sessions WHERE id = $1",
+        session_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(details.unwrap_or_default())
+}
+
+/// Terminates a session of the user and revokes its refresh tokens. Access
+/// tokens of the session are refused from then on. Returns false if there
+/// was no such active session.
+pub async fn end_session(transaction: &mut PgConnection, user_id: Uuid, session_id: Uuid) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let result = sqlx::query!(
+        "UPDATE sessions SET revoked_at = $1 WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+        now,
+        session_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND user_id = $3 AND revoked_at IS NULL",
+        now,
+        session_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Lists the active sessions of the authenticated user, most recently used first.
+#[tracing::instrument(
+    name = "List sessions",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_sessions(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+    let current_session_id = user.session_id();
+
+    let sessions = sqlx::query_as!(
+        SessionInfo,
+        r#"
+        SELECT
+            id, user_agent, ip_address, device_name, created_at, last_seen_at,
+            id IS NOT DISTINCT FROM $2 as "current!"
+        FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $3
+        ORDER BY last_seen_at DESC
+        "#,
+        user_id,
+        current_session_id,
+        Utc::now()
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match sessions {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => {
+            tracing::error!("Failed to fetch sessions: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Terminates one of the authenticated user's sessions, e.g. on a lost device.
+#[tracing::instrument(
+    name = "Terminate session",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn terminate_session(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let user_id = user.id;
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let ended = end_session(&mut transaction, user_id, session_id.into_inner()).await?;
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(ended)
+    }.await;
+
+    match result {
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "Session not found" })),
+        Ok(true) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to terminate session: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
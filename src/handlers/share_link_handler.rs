@@ -0,0 +1,403 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::{Duration, NaiveDate, NaiveTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings};
+use crate::handlers::audit_handler::record_audit_event;
+use crate::handlers::login_throttle::client_address;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::delegation::DELEGATION_DATA_TYPES;
+use crate::models::share_link::{
+    CreateShareLinkRequest, CreatedShareLinkResponse, ShareLinkInfo, SharedDataQuery, SharedDataResponse,
+    SharedHealthRecord, SharedSleepSummary
+};
+use crate::utils::token::{generate_token, hash_token};
+
+const MAX_SHARE_LINK_DAYS: i64 = 90;
+
+/// Creates a read-only link to some of the authenticated user's health data.
+#[tracing::instrument(
+    name = "Create share link",
+    skip(share_form, pool, application_settings, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn create_share_link(
+    share_form: web::Json<CreateShareLinkRequest>,
+    pool: web::Data<PgPool>,
+    application_settings: web::Data<ApplicationSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    if share_form.data_types.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "At least one data type is required" }));
+    }
+    if let Some(unknown) = share_form.data_types.iter().find(|data_type| !DELEGATION_DATA_TYPES.contains(&data_type.as_str())) {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Unknown data type: {}", unknown) }));
+    }
+    if share_form.end_date < share_form.start_date {
+        return HttpResponse::BadRequest().json(json!({ "error": "End date must not be before start date" }));
+    }
+    let now = Utc::now();
+    if share_form.expires_at <= now {
+        return HttpResponse::BadRequest().json(json!({ "error": "Expiry must be in the future" }));
+    }
+    if share_form.expires_at > now + Duration::days(MAX_SHARE_LINK_DAYS) {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": format!("Share links can be valid for at most {} days", MAX_SHARE_LINK_DAYS) }));
+    }
+
+    let mut data_types = share_form.data_types.clone();
+    data_types.sort();
+    data_types.dedup();
+
+    let token = generate_token();
+    let id = Uuid::new_v4();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO share_links (id, user_id, token_hash, data_types, start_date, end_date, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            id,
+            user.id,
+            hash_token(&token),
+            &data_types,
+            share_form.start_date,
+            share_form.end_date,
+            share_form.expires_at,
+            now
+        )
+        .execute(&mut *transaction)
+        .await?;
+        record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::ShareLinkCreated, Some(user.id)).details(json!({
+                "share_link_id": id,
+                "data_types": data_types,
+                "start_date": share_form.start_date,
+                "end_date": share_form.end_date,
+                "expires_at": share_form.expires_at
+            }))
+        ).await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(()) => HttpResponse::Created().json(CreatedShareLinkResponse {
+            id,
+            url: format!("{}/shared/{}", application_settings.base_url, token),
+            token,
+            expires_at: share_form.expires_at,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to create share link: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the authenticated user's share links that still work.
+#[tracing::instrument(
+    name = "List share links",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_share_links(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let share_links = sqlx::query_as!(
+        ShareLinkInfo,
+        r#"
+        SELECT id, data_types, start_date, end_date, expires_at, created_at, access_count, last_accessed_at
+        FROM share_links
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2
+        ORDER BY created_at DESC
+        "#,
+        user.id,
+        Utc::now()
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match share_links {
+        Ok(share_links) => HttpResponse::Ok().json(share_links),
+        Err(e) => {
+            tracing::error!("Failed to fetch share links: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Revokes one of the authenticated user's share links.
+#[tracing::instrument(
+    name = "Revoke share link",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn revoke_share_link(
+    share_link_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let share_link_id = share_link_id.into_inner();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let revoked = sqlx::query!(
+            "UPDATE share_links SET revoked_at = $1 WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+            Utc::now(),
+            share_link_id,
+            user.id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected() > 0;
+        if revoked {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::ShareLinkRevoked, Some(user.id))
+                    .details(json!({ "share_link_id": share_link_id }))
+            ).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(revoked)
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "Share link not found" })),
+        Err(e) => {
+            tracing::error!("Failed to revoke share link: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Shows the data behind a share link, as JSON or as a simple HTML page.
+/// Needs no account; every access is counted and recorded in the owner's
+/// audit log before anything is returned.
+#[tracing::instrument(
+    name = "View share link",
+    skip(req, token, query, pool, login_throttling_settings)
+)]
+pub async fn view_share_link(
+    req: HttpRequest,
+    token: web::Path<String>,
+    query: web::Query<SharedDataQuery>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    let html = match query.format.as_deref() {
+        Some("html") => true,
+        Some("json") => false,
+        Some(_) => return HttpResponse::BadRequest().json(json!({ "error": "Format must be json or html" })),
+        None => prefers_html(&req),
+    };
+    let ip_address = client_address(&req, login_throttling_settings.trust_forwarded_for);
+    let now = Utc::now();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let share_link = sqlx::query!(
+            r#"
+            UPDATE share_links s SET access_count = s.access_count + 1, last_accessed_at = $2
+            FROM users u
+            WHERE s.token_hash = $1 AND u.id = s.user_id
+                AND s.revoked_at IS NULL AND s.expires_at > $2 AND u.deletion_scheduled_for IS NULL
+            RETURNING s.id, s.user_id, s.data_types, s.start_date, s.end_date, s.expires_at
+            "#,
+            hash_token(&token),
+            now
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let Some(share_link) = share_link else {
+            return Ok(None);
+        };
+        record_audit_event(
+            &mut *transaction,
+            NewAuditEvent::new(AuditEventType::ShareLinkAccessed, Some(share_link.user_id))
+                .actor(None)
+                .ip_address(Some(&ip_address))
+                .details(json!({ "share_link_id": share_link.id, "data_types": share_link.data_types }))
+        ).await?;
+        transaction.commit().await?;
+
+        let health_data = fetch_health_data_in_period(
+            pool.get_ref(), share_link.user_id, &share_link.data_types, share_link.start_date, share_link.end_date
+        ).await?;
+        let sleep_summaries = if share_link.data_types.iter().any(|data_type| data_type == "sleep") {
+            fetch_sleep_summaries_in_period(
+                pool.get_ref(), share_link.user_id, share_link.start_date, share_link.end_date
+            ).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok::<_, sqlx::Error>(Some(SharedDataResponse {
+            data_types: share_link.data_types,
+            start_date: share_link.start_date,
+            end_date: share_link.end_date,
+            expires_at: share_link.expires_at,
+            health_data,
+            sleep_summaries,
+        }))
+    }.await;
+
+    let shared_data = match result {
+        Ok(Some(shared_data)) => shared_data,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "error": "Share link not found or expired" })),
+        Err(e) => {
+            tracing::error!("Failed to serve share link: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .insert_header((header::REFERRER_POLICY, "no-referrer"))
+        .insert_header(("X-Robots-Tag", "noindex"));
+    if html {
+        response
+            .insert_header((header::CONTENT_SECURITY_POLICY, "default-src 'none'; style-src 'unsafe-inline'"))
+            .content_type("text/html; charset=utf-8")
+            .body(render_shared_data(&shared_data))
+    } else {
+        response.json(shared_data)
+    }
+}
+
+/// The user's health data records of the given types that started within the
+/// period; both days are included.
+pub async fn fetch_health_data_in_period(
+    pool: &PgPool,
+    user_id: Uuid,
+    data_types: &[String],
+    start_date: NaiveDate,
+    end_date: NaiveDate
+) -> Result<Vec<SharedHealthRecord>, sqlx::Error> {
+    let start_time = start_date.and_time(NaiveTime::MIN).and_utc();
+    let end_time = (end_date + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+
+    sqlx::query_as!(
+        SharedHealthRecord,
+        r#"
+        SELECT data_type, device_info, sampling_rate_hz, start_time, end_time, data
+        FROM health_data
+        WHERE user_id = $1 AND data_type = ANY($2) AND start_time >= $3 AND start_time < $4
+        ORDER BY start_time
+        "#,
+        user_id,
+        data_types,
+        start_time,
+        end_time
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The user's nightly sleep summaries within the period, both days included.
+pub async fn fetch_sleep_summaries_in_period(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate
+) -> Result<Vec<SharedSleepSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        SharedSleepSummary,
+        r#"
+        SELECT night_date, data
+        FROM processed_sleep_data
+        WHERE user_id = $1 AND data_type = 'sleep_summary' AND night_date BETWEEN $2 AND $3
+        ORDER BY night_date
+        "#,
+        user_id,
+        start_date,
+        end_date
+    )
+    .fetch_all(pool)
+    .await
+}
+
+fn prefers_html(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(|accept| accept.contains("text/html"))
+        .unwrap_or(false)
+}
+
+fn render_shared_data(shared_data: &SharedDataResponse) -> String {
+    let mut sections = String::new();
+    if !shared_data.sleep_summaries.is_empty() {
+        sections.push_str("<h2>Sleep summaries</h2><table><tr><th>Night</th><th>Summary</th></tr>");
+        for summary in &shared_data.sleep_summaries {
+            sections.push_str(&format!(
+                "<tr><td>{}</td><td><pre>{}</pre></td></tr>",
+                summary.night_date,
+                escape_html(&pretty_json(&summary.data))
+            ));
+        }
+        sections.push_str("</table>");
+    }
+    for data_type in shared_data.data_types.iter().filter(|data_type| *data_type != "sleep") {
+        let records: Vec<&SharedHealthRecord> = shared_data.health_data.iter()
+            .filter(|record| &record.data_type == data_type)
+            .collect();
+        if records.is_empty() {
+            continue;
+        }
+        sections.push_str(&format!(
+            "<h2>{}</h2><table><tr><th>Start</th><th>End</th><th>Device</th><th>Data</th></tr>",
+            escape_html(&data_type.replace('_', " "))
+        ));
+        for record in records {
+            sections.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+                record.start_time.to_rfc3339(),
+                record.end_time.to_rfc3339(),
+                escape_html(&record.device_info.to_string()),
+                escape_html(&pretty_json(&record.data))
+            ));
+        }
+        sections.push_str("</table>");
+    }
+    if sections.is_empty() {
+        sections.push_str("<p>No data was recorded in this period.</p>");
+    }
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Shared health data</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:2em}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}}pre{{margin:0}}</style>\
+         </head><body><h1>Shared health data</h1><p>{} to {}, link valid until {}</p>{}</body></html>",
+        shared_data.start_date,
+        shared_data.end_date,
+        shared_data.expires_at.to_rfc3339(),
+        sections
+    )
+}
+
+fn pretty_json(value: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,817 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::audit_handler::record_audit_event;
+use crate::handlers::share_link_handler::{fetch_health_data_in_period, fetch_sleep_summaries_in_period};
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::audit::{AuditEventType, NewAuditEvent};
+use crate::models::delegation::DELEGATION_DATA_TYPES;
+use crate::models::study::{
+    AddOrganizationAdminRequest, CreateOrganizationRequest, CreateStudyRequest, DataTypeAggregate, EnrollRequest,
+    EnrollmentInfo, OrganizationInfo, ParticipantDataResponse, ParticipantInfo, StudyAggregateResponse, StudyInfo
+};
+use crate::utils::token::generate_invite_code;
+
+/// The data collection permissions a participant gave in the onboarding
+/// permissions setup.
+struct Consent {
+    heart_rate_enabled: bool,
+    temperature_enabled: bool,
+    spo2_enabled: bool,
+    accelerometer_enabled: bool,
+}
+
+impl Consent {
+    /// Sleep is derived from the accelerometer. GPS has no permission of its
+    /// own, enrolling is the consent for it.
+    fn permits(&self, data_type: &str) -> bool {
+        match data_type {
+            "acceleration" | "sleep" => self.accelerometer_enabled,
+            "heart_rate" => self.heart_rate_enabled,
+            "blood_oxygen" => self.spo2_enabled,
+            "skin_temperature" => self.temperature_enabled,
+            _ => true,
+        }
+    }
+
+    fn permitted(&self, data_types: &[String]) -> Vec<String> {
+        data_types.iter().filter(|data_type| self.permits(data_type)).cloned().collect()
+    }
+}
+
+/// Creates an organization with its first admin.
+#[tracing::instrument(name = "Create organization", skip(organization_form, pool))]
+pub async fn create_organization(
+    organization_form: web::Json<CreateOrganizationRequest>,
+    pool: web::Data<PgPool>
+) -> HttpResponse {
+    let name = organization_form.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Name is required" }));
+    }
+
+    let admin_id = match find_user_id(pool.get_ref(), &organization_form.admin_username).await {
+        Ok(Some(admin_id)) => admin_id,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let organization = OrganizationInfo {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        created_at: Utc::now(),
+    };
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            "INSERT INTO organizations (id, name, created_at) VALUES ($1, $2, $3)",
+            organization.id,
+            organization.name,
+            organization.created_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO organization_admins (organization_id, user_id, added_at) VALUES ($1, $2, $3)",
+            organization.id,
+            admin_id,
+            organization.created_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await
+    }.await;
+
+    match result {
+        Ok(()) => HttpResponse::Created().json(organization),
+        Err(e) if e.as_database_error().is_some_and(|db_error| db_error.constraint().is_some()) => {
+            HttpResponse::Conflict().json(json!({ "error": "Organization already exists" }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create organization: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the organizations the authenticated user is an admin of.
+#[tracing::instrument(
+    name = "List organizations",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_organizations(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let organizations = sqlx::query_as!(
+        OrganizationInfo,
+        r#"
+        SELECT o.id, o.name, o.created_at
+        FROM organizations o
+        JOIN organization_admins a ON a.organization_id = o.id
+        WHERE a.user_id = $1
+        ORDER BY o.name
+        "#,
+        user.id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match organizations {
+        Ok(organizations) => HttpResponse::Ok().json(organizations),
+        Err(e) => {
+            tracing::error!("Failed to fetch organizations: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Makes another user an admin of an organization the authenticated user administers.
+#[tracing::instrument(
+    name = "Add organization admin",
+    skip(admin_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn add_organization_admin(
+    organization_id: web::Path<Uuid>,
+    admin_form: web::Json<AddOrganizationAdminRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let organization_id = organization_id.into_inner();
+    if let Err(response) = require_organization_admin(pool.get_ref(), organization_id, &user).await {
+        return response;
+    }
+
+    let admin_id = match find_user_id(pool.get_ref(), &admin_form.username).await {
+        Ok(Some(admin_id)) => admin_id,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "error": "User not found" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO organization_admins (organization_id, user_id, added_at) VALUES ($1, $2, $3)
+        ON CONFLICT (organization_id, user_id) DO NOTHING
+        "#,
+        organization_id,
+        admin_id,
+        Utc::now()
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to add organization admin: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Creates a study in an organization the authenticated user administers.
+#[tracing::instrument(
+    name = "Create study",
+    skip(study_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn create_study(
+    organization_id: web::Path<Uuid>,
+    study_form: web::Json<CreateStudyRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let organization_id = organization_id.into_inner();
+    if let Err(response) = require_organization_admin(pool.get_ref(), organization_id, &user).await {
+        return response;
+    }
+
+    let name = study_form.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Name is required" }));
+    }
+    if study_form.data_types.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "At least one data type is required" }));
+    }
+    if let Some(unknown) = study_form.data_types.iter().find(|data_type| !DELEGATION_DATA_TYPES.contains(&data_type.as_str())) {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Unknown data type: {}", unknown) }));
+    }
+    if study_form.end_date < study_form.start_date {
+        return HttpResponse::BadRequest().json(json!({ "error": "End date must not be before start date" }));
+    }
+
+    let mut data_types = study_form.data_types.clone();
+    data_types.sort();
+    data_types.dedup();
+
+    let study = StudyInfo {
+        id: Uuid::new_v4(),
+        organization_id,
+        name: name.to_string(),
+        description: study_form.description.clone(),
+        data_types,
+        start_date: study_form.start_date,
+        end_date: study_form.end_date,
+        invite_code: generate_invite_code(),
+        created_at: Utc::now(),
+    };
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO studies (id, organization_id, name, description, data_types, start_date, end_date, invite_code, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        study.id,
+        study.organization_id,
+        study.name,
+        study.description,
+        &study.data_types,
+        study.start_date,
+        study.end_date,
+        study.invite_code,
+        study.created_at
+    )
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Created().json(study),
+        Err(e) => {
+            tracing::error!("Failed to create study: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the studies of an organization the authenticated user administers.
+#[tracing::instrument(
+    name = "List studies",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_studies(
+    organization_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let organization_id = organization_id.into_inner();
+    if let Err(response) = require_organization_admin(pool.get_ref(), organization_id, &user).await {
+        return response;
+    }
+
+    let studies = sqlx::query_as!(
+        StudyInfo,
+        r#"
+        SELECT id, organization_id, name, description, data_types, start_date, end_date, invite_code, created_at
+        FROM studies
+        WHERE organization_id = $1
+        ORDER BY created_at DESC
+        "#,
+        organization_id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match studies {
+        Ok(studies) => HttpResponse::Ok().json(studies),
+        Err(e) => {
+            tracing::error!("Failed to fetch studies: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Enrolls the authenticated user in the study with the given invite code.
+/// Their permissions setup has to allow all of the study's data types; it
+/// stays linked to the enrollment as the participant's consent.
+#[tracing::instrument(
+    name = "Enroll in study",
+    skip(enroll_form, pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn enroll_in_study(
+    enroll_form: web::Json<EnrollRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    // Invite codes are accepted regardless of case and spacing
+    let invite_code: String = enroll_form.invite_code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let study = match sqlx::query!(
+        r#"
+        SELECT s.id, s.name, o.name as organization_name, s.data_types, s.start_date, s.end_date
+        FROM studies s
+        JOIN organizations o ON o.id = s.organization_id
+        WHERE s.invite_code = $1
+        "#,
+        invite_code
+    )
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(study)) => study,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "error": "Invalid invite code" })),
+        Err(e) => {
+            tracing::error!("Database error occurred: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    if study.end_date < Utc::now().date_naive() {
+        return HttpResponse::BadRequest().json(json!({ "error": "The study has ended" }));
+    }
+
+    let permissions = match sqlx::query!(
+        r#"
+        SELECT id, heart_rate_enabled, temperature_enabled, spo2_enabled, accelerometer_enabled
+        FROM permissions_settings
+        WHERE user_id = $1
+        "#,
+        user.id
+    )
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(permissions) => permissions,
+        Err(e) => {
+            tracing::error!("Failed to fetch permissions settings: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let missing_permissions: Vec<&String> = match &permissions {
+        Some(permissions) => {
+            let consent = Consent {
+                heart_rate_enabled: permissions.heart_rate_enabled,
+                temperature_enabled: permissions.temperature_enabled,
+                spo2_enabled: permissions.spo2_enabled,
+                accelerometer_enabled: permissions.accelerometer_enabled,
+            };
+            study.data_types.iter().filter(|data_type| !consent.permits(data_type)).collect()
+        }
+        None => study.data_types.iter().collect(),
+    };
+    let permissions_settings_id = match permissions {
+        Some(permissions) if missing_permissions.is_empty() => permissions.id,
+        _ => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "The permissions setup has to allow all data types of the study",
+                "missing_permissions": missing_permissions
+            }));
+        }
+    };
+
+    let enrolled_at = Utc::now();
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        // Participants who withdrew can enroll again
+        let enrollment_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO study_enrollments (id, study_id, user_id, permissions_settings_id, enrolled_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (study_id, user_id) DO UPDATE
+            SET permissions_settings_id = EXCLUDED.permissions_settings_id,
+                enrolled_at = EXCLUDED.enrolled_at,
+                withdrawn_at = NULL
+            WHERE study_enrollments.withdrawn_at IS NOT NULL
+            RETURNING id
+            "#,
+            Uuid::new_v4(),
+            study.id,
+            user.id,
+            permissions_settings_id,
+            enrolled_at
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if enrollment_id.is_some() {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::StudyEnrolled, Some(user.id))
+                    .details(json!({ "study_id": study.id, "data_types": study.data_types }))
+            ).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(enrollment_id.is_some())
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Created().json(EnrollmentInfo {
+            study_id: study.id,
+            study_name: study.name,
+            organization_name: study.organization_name,
+            data_types: study.data_types,
+            start_date: study.start_date,
+            end_date: study.end_date,
+            enrolled_at,
+        }),
+        Ok(false) => HttpResponse::Conflict().json(json!({ "error": "Already enrolled in this study" })),
+        Err(e) => {
+            tracing::error!("Failed to enroll in study: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the studies the authenticated user takes part in.
+#[tracing::instrument(
+    name = "List enrollments",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_enrollments(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let enrollments = sqlx::query_as!(
+        EnrollmentInfo,
+        r#"
+        SELECT s.id as study_id, s.name as study_name, o.name as organization_name,
+            s.data_types, s.start_date, s.end_date, e.enrolled_at
+        FROM study_enrollments e
+        JOIN studies s ON s.id = e.study_id
+        JOIN organizations o ON o.id = s.organization_id
+        WHERE e.user_id = $1 AND e.withdrawn_at IS NULL
+        ORDER BY e.enrolled_at DESC
+        "#,
+        user.id
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match enrollments {
+        Ok(enrollments) => HttpResponse::Ok().json(enrollments),
+        Err(e) => {
+            tracing::error!("Failed to fetch enrollments: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Withdraws the authenticated user from a study. Study admins lose access
+/// to their data right away.
+#[tracing::instrument(
+    name = "Withdraw from study",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn withdraw_from_study(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let study_id = study_id.into_inner();
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let withdrawn = sqlx::query!(
+            "UPDATE study_enrollments SET withdrawn_at = $1 WHERE study_id = $2 AND user_id = $3 AND withdrawn_at IS NULL",
+            Utc::now(),
+            study_id,
+            user.id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected() > 0;
+        if withdrawn {
+            record_audit_event(
+                &mut *transaction,
+                NewAuditEvent::new(AuditEventType::StudyWithdrawn, Some(user.id))
+                    .details(json!({ "study_id": study_id }))
+            ).await?;
+        }
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(withdrawn)
+    }.await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({ "error": "Enrollment not found" })),
+        Err(e) => {
+            tracing::error!("Failed to withdraw from study: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the participants of a study for its admins.
+#[tracing::instrument(
+    name = "List study participants",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn list_participants(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let study = match study_for_admin(pool.get_ref(), study_id.into_inner(), &user).await {
+        Ok(study) => study,
+        Err(response) => return response,
+    };
+
+    match active_participants(pool.get_ref(), &study).await {
+        Ok(participants) => HttpResponse::Ok().json(participants),
+        Err(e) => {
+            tracing::error!("Failed to fetch participants: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Summarizes the data collected in a study's window, per data type, over
+/// the participants whose permissions allow it.
+#[tracing::instrument(
+    name = "Get study aggregate",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn get_study_aggregate(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let study = match study_for_admin(pool.get_ref(), study_id.into_inner(), &user).await {
+        Ok(study) => study,
+        Err(response) => return response,
+    };
+
+    let result = async {
+        let participants = active_participants(pool.get_ref(), &study).await?;
+        let (start_time, end_time) = study_window(&study);
+
+        let mut data_types = Vec::with_capacity(study.data_types.len());
+        for data_type in &study.data_types {
+            let user_ids: Vec<Uuid> = participants.iter()
+                .filter(|participant| participant.consented_data_types.contains(data_type))
+                .map(|participant| participant.user_id)
+                .collect();
+
+            let aggregate = if data_type == "sleep" {
+                let nights = sqlx::query!(
+                    r#"
+                    SELECT COUNT(DISTINCT user_id) as "participants_with_data!", COUNT(*) as "record_count!"
+                    FROM processed_sleep_data
+                    WHERE user_id = ANY($1) AND data_type = 'sleep_summary' AND night_date BETWEEN $2 AND $3
+                    "#,
+                    &user_ids,
+                    study.start_date,
+                    study.end_date
+                )
+                .fetch_one(pool.get_ref())
+                .await?;
+                DataTypeAggregate {
+                    data_type: data_type.clone(),
+                    consenting_participants: user_ids.len() as i64,
+                    participants_with_data: nights.participants_with_data,
+                    record_count: nights.record_count,
+                    sample_count: None,
+                }
+            } else {
+                let uploads = sqlx::query!(
+                    r#"
+                    SELECT COUNT(DISTINCT user_id) as "participants_with_data!", COUNT(*) as "record_count!",
+                        COALESCE(SUM(jsonb_array_length(data->'samples')), 0)::BIGINT as "sample_count!"
+                    FROM health_data
+                    WHERE user_id = ANY($1) AND data_type = $2 AND start_time >= $3 AND start_time < $4
+                    "#,
+                    &user_ids,
+                    data_type,
+                    start_time,
+                    end_time
+                )
+                .fetch_one(pool.get_ref())
+                .await?;
+                DataTypeAggregate {
+                    data_type: data_type.clone(),
+                    consenting_participants: user_ids.len() as i64,
+                    participants_with_data: uploads.participants_with_data,
+                    record_count: uploads.record_count,
+                    sample_count: Some(uploads.sample_count),
+                }
+            };
+            data_types.push(aggregate);
+        }
+
+        Ok::<_, sqlx::Error>(StudyAggregateResponse {
+            study_id: study.id,
+            start_date: study.start_date,
+            end_date: study.end_date,
+            participant_count: participants.len() as i64,
+            data_types,
+        })
+    }.await;
+
+    match result {
+        Ok(aggregate) => HttpResponse::Ok().json(aggregate),
+        Err(e) => {
+            tracing::error!("Failed to aggregate study data: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Returns one participant's data within the study window, limited to the
+/// study data types their permissions still allow. The read is recorded in
+/// the participant's audit log.
+#[tracing::instrument(
+    name = "Get participant data",
+    skip(pool, user),
+    fields(
+        user_id = %user.id
+    )
+)]
+pub async fn get_participant_data(
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    let (study_id, participant_id) = path.into_inner();
+    let study = match study_for_admin(pool.get_ref(), study_id, &user).await {
+        Ok(study) => study,
+        Err(response) => return response,
+    };
+
+    let participant = match active_participants(pool.get_ref(), &study).await {
+        Ok(participants) => participants.into_iter().find(|participant| participant.user_id == participant_id),
+        Err(e) => {
+            tracing::error!("Failed to fetch participants: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let Some(participant) = participant else {
+        return HttpResponse::NotFound().json(json!({ "error": "Participant not found" }));
+    };
+    let data_types = participant.consented_data_types;
+    if data_types.is_empty() {
+        return HttpResponse::Forbidden().json(json!({
+            "error": "The participant's permissions no longer allow any of the study's data types"
+        }));
+    }
+
+    let result = async {
+        // Reads are only served once they are on record
+        record_audit_event(
+            pool.get_ref(),
+            NewAuditEvent::new(AuditEventType::HealthDataAccessed, Some(participant_id))
+                .actor(Some(user.id))
+                .details(json!({ "study_id": study.id, "data_types": data_types }))
+        ).await?;
+
+        let health_data = fetch_health_data_in_period(
+            pool.get_ref(), participant_id, &data_types, study.start_date, study.end_date
+        ).await?;
+        let sleep_summaries = if data_types.iter().any(|data_type| data_type == "sleep") {
+            fetch_sleep_summaries_in_period(pool.get_ref(), participant_id, study.start_date, study.end_date).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok::<_, sqlx::Error>(ParticipantDataResponse {
+            study_id: study.id,
+            user_id: participant_id,
+            data_types,
+            start_date: study.start_date,
+            end_date: study.end_date,
+            health_data,
+            sleep_summaries,
+        })
+    }.await;
+
+    match result {
+        Ok(participant_data) => HttpResponse::Ok().json(participant_data),
+        Err(e) => {
+            tracing::error!("Failed to fetch participant data: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn find_user_id(pool: &PgPool, username: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT id FROM users WHERE username = $1 AND deletion_scheduled_for IS NULL",
+        username
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+async fn require_organization_admin(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user: &AuthenticatedUser
+) -> Result<(), HttpResponse> {
+    let is_admin = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM organization_admins WHERE organization_id = $1 AND user_id = $2) as "exists!""#,
+        organization_id,
+        user.id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up organization admin: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+
+    if is_admin {
+        Ok(())
+    } else {
+        Err(HttpResponse::Forbidden().json(json!({ "error": "Not an admin of this organization" })))
+    }
+}
+
+/// Loads a study if the user is an admin of its organization.
+async fn study_for_admin(pool: &PgPool, study_id: Uuid, user: &AuthenticatedUser) -> Result<StudyInfo, HttpResponse> {
+    let study = sqlx::query_as!(
+        StudyInfo,
+        r#"
+        SELECT id, organization_id, name, description, data_types, start_date, end_date, invite_code, created_at
+        FROM studies
+        WHERE id = $1
+        "#,
+        study_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch study: {:?}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+
+    let study = study.ok_or_else(|| HttpResponse::NotFound().json(json!({ "error": "Study not found" })))?;
+    require_organization_admin(pool, study.organization_id, user).await?;
+    Ok(study)
+}
+
+/// The participants who haven't withdrawn, with the study data types their
+/// current permissions allow.
+async fn active_participants(pool: &PgPool, study: &StudyInfo) -> Result<Vec<ParticipantInfo>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"
+        SELECT u.id as user_id, u.username, e.enrolled_at,
+            p.heart_rate_enabled, p.temperature_enabled, p.spo2_enabled, p.accelerometer_enabled
+        FROM study_enrollments e
+        JOIN users u ON u.id = e.user_id
+        JOIN permissions_settings p ON p.id = e.permissions_settings_id
+        WHERE e.study_id = $1 AND e.withdrawn_at IS NULL AND u.deletion_scheduled_for IS NULL
+        ORDER BY e.enrolled_at
+        "#,
+        study.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(records.into_iter()
+        .map(|record| {
+            let consent = Consent {
+                heart_rate_enabled: record.heart_rate_enabled,
+                temperature_enabled: record.temperature_enabled,
+                spo2_enabled: record.spo2_enabled,
+                accelerometer_enabled: record.accelerometer_enabled,
+            };
+            ParticipantInfo {
+                user_id: record.user_id,
+                username: record.username,
+                enrolled_at: record.enrolled_at,
+                consented_data_types: consent.permitted(&study.data_types),
+            }
+        })
+        .collect())
+}
+
+/// The study window as a half-open time range; both days are included.
+fn study_window(study: &StudyInfo) -> (DateTime<Utc>, DateTime<Utc>) {
+    (
+        study.start_date.and_time(NaiveTime::MIN).and_utc(),
+        (study.end_date + Duration::days(1)).and_time(NaiveTime::MIN).and_utc(),
+    )
+}
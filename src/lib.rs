@@ -10,26 +10,51 @@ mod models;
 mod utils;
 pub mod telemetry;
 mod middleware;
+mod email;
 
 use crate::routes::init_routes;
-use crate::config::jwt::JwtSettings;
+use crate::config::oidc::OidcSettings;
+use crate::config::settings::{get_jwt_settings, Settings};
+use crate::email::build_mailer;
+use crate::handlers::account_handler::run_account_purge;
+use crate::middleware::request_span::RedactingRootSpanBuilder;
+use crate::middleware::upload_body::json_error_handler;
 
 pub fn run(
     listener: TcpListener,
     db_pool: PgPool,
-    jwt_settings: JwtSettings
+    settings: Settings
 ) -> Result<Server, std::io::Error> {
     // Wrap using web::Data, which boils down to an Arc smart pointer
     let db_pool = web::Data::new(db_pool);
-    let jwt_settings = web::Data::new(jwt_settings);
+    let jwt_settings = web::Data::new(get_jwt_settings(&settings)?);
+    let password_hashing_settings = web::Data::new(settings.password_hashing);
+    let default_payload_limit = settings.application.payload_limits.default_bytes;
+    let application_settings = web::Data::new(settings.application);
+    let login_throttling_settings = web::Data::new(settings.login_throttling);
+    let mailer = web::Data::from(build_mailer(&settings.email));
+    let account_deletion_settings = web::Data::new(settings.account_deletion);
+    let oidc_settings = web::Data::new(OidcSettings::new(&settings.oidc));
+
+    // Erase accounts whose deletion grace period is over in the background
+    tokio::spawn(run_account_purge(db_pool.get_ref().clone(), account_deletion_settings.get_ref().clone()));
 
     let server = HttpServer::new( move || {
         App::new()
-            .wrap(TracingLogger::default())
+            .wrap(TracingLogger::<RedactingRootSpanBuilder>::new())
             .configure(init_routes)
             // Get a pointer copy and attach it to the application state
             .app_data(db_pool.clone())
             .app_data(jwt_settings.clone())
+            .app_data(password_hashing_settings.clone())
+            .app_data(application_settings.clone())
+            .app_data(login_throttling_settings.clone())
+            .app_data(mailer.clone())
+            .app_data(account_deletion_settings.clone())
+            .app_data(oidc_settings.clone())
+            .app_data(web::JsonConfig::default()
+                .limit(default_payload_limit)
+                .error_handler(json_error_handler))
     })
     .listen(listener)?
     .run();
@@ -4,7 +4,7 @@ use sqlx::postgres::PgPoolOptions;
 use std::time::Duration;
 
 use areum_backend::run;
-use areum_backend::config::settings::{get_config, get_jwt_settings};
+use areum_backend::config::settings::get_config;
 use areum_backend::telemetry::{get_subscriber, init_subscriber};
 
 #[tokio::main]
@@ -16,8 +16,6 @@ async fn main() -> std::io::Result<()> {
 
     // Panic if we can't read the config
     let config = get_config().expect("Failed to read the config.");
-    // Get JWT settings
-    let jwt_settings = get_jwt_settings(&config);
     // Only try to establish connection when actually used
     let conection_pool = PgPoolOptions::new()
         .acquire_timeout(Duration::from_secs(2))
@@ -28,5 +26,5 @@ async fn main() -> std::io::Result<()> {
     let address = format!("{}:{}", config.application.host, config.application.port);
     let listener = TcpListener::bind(&address)?;
     
-    run(listener, conection_pool, jwt_settings)?.await
+    run(listener, conection_pool, config)?.await
 }
\ No newline at end of file
@@ -1,29 +1,72 @@
 // src/middleware/auth.rs
-use std::{future::{ready, Ready}};
+use std::{future::{ready, Ready}, rc::Rc};
 use actix_web::{
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform}, error::ErrorUnauthorized, http::header, web, Error, HttpMessage
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform}, error::{ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized}, http::header, web, Error, HttpMessage
 };
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use serde::{Deserialize, Serialize};
-use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
 
 use crate::config::jwt::JwtSettings;
+use crate::config::settings::{ApplicationSettings, BATCH_UPLOAD_PATH};
+use crate::middleware::authenticated_user::{AuthenticatedUser, Credential};
+use crate::utils::token::hash_token;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,  // Subject (user id)
     pub username: String,
     pub exp: usize,   // Expiration time (as UTC timestamp)
+    pub iat: usize,   // Issued at (as UTC timestamp)
+    pub jti: String,  // Unique token id, used for revocation
+    #[serde(default)]
+    pub mfa: bool,    // Whether a second factor was verified for this login
+    #[serde(default)]
+    pub mfa_pending: bool, // Only valid for completing a two-step login
+    #[serde(default)]
+    pub roles: Vec<String>, // See `models::role::Role`
+    #[serde(default)]
+    pub sid: Option<String>, // Session (refresh token family) the token belongs to
+}
+
+/// Decodes and validates a token signed with one of the configured keys.
+pub fn decode_token(token: &str, jwt_settings: &JwtSettings) -> Result<Claims, jsonwebtoken::errors::Error> {
+    jwt_settings.decode::<Claims>(token)
 }
 
 // Create the middleware
-pub struct AuthMiddleware;
+#[derive(Default)]
+pub struct AuthMiddleware {
+    require_verified_email: bool,
+    allow_api_keys: bool,
+}
+
+impl AuthMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also rejects users whose email address isn't verified yet, if
+    /// `require_email_verification` is enabled in the application settings.
+    pub fn require_verified_email(mut self) -> Self {
+        self.require_verified_email = true;
+        self
+    }
+
+    /// Also accepts device API keys (`Authorization: ApiKey <key>`) for the
+    /// upload endpoints covered by the key's scopes.
+    pub fn allow_api_keys(mut self) -> Self {
+        self.allow_api_keys = true;
+        self
+    }
+}
 
 // Middleware factory
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -34,17 +77,23 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddlewareService { service }))
+        ready(Ok(AuthMiddlewareService {
+            service: Rc::new(service),
+            require_verified_email: self.require_verified_email,
+            allow_api_keys: self.allow_api_keys,
+        }))
     }
 }
 
 pub struct AuthMiddlewareService<S> {
-    service: S,
+    service: Rc<S>,
+    require_verified_email: bool,
+    allow_api_keys: bool,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddlewareService<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -58,6 +107,11 @@ where
         // Extract JWT from Authorization header
         let auth_header = req.headers().get(header::AUTHORIZATION);
         let jwt_settings = req.app_data::<web::Data<JwtSettings>>().cloned();
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        let require_verified_email = self.require_verified_email && req
+            .app_data::<web::Data<ApplicationSettings>>()
+            .map(|settings| settings.require_email_verification)
+            .unwrap_or(false);
 
         // No JWT settings in app state
         if jwt_settings.is_none() {
@@ -66,6 +120,13 @@ where
             });
         }
 
+        // No database pool in app state
+        if pool.is_none() {
+            return Box::pin(async move {
+                Err(ErrorInternalServerError("Database pool not found"))
+            });
+        }
+
         // No auth header
         if auth_header.is_none() {
             return Box::pin(async move {
@@ -74,7 +135,28 @@ where
         }
 
         let auth_header = auth_header.unwrap().to_str().unwrap_or_default();
-        
+
+        // Device API keys are an alternative to JWTs where explicitly allowed
+        if let Some(api_key) = auth_header.strip_prefix("ApiKey ") {
+            if !self.allow_api_keys {
+                return Box::pin(async move {
+                    Err(ErrorUnauthorized("API keys are not accepted for this endpoint"))
+                });
+            }
+
+            let api_key = api_key.to_string();
+            let pool = pool.unwrap();
+            let service = self.service.clone();
+
+            return Box::pin(async move {
+                let user = authenticate_api_key(&pool, &api_key, req.path(), require_verified_email).await?;
+                req.extensions_mut().insert(user);
+
+                let res = service.call(req).await?;
+                Ok(res)
+            });
+        }
+
         // Check if it's a Bearer token
         if !auth_header.starts_with("Bearer ") {
             return Box::pin(async move {
@@ -85,13 +167,10 @@ where
         // Extract the token
         let token = &auth_header[7..]; // Skip "Bearer "
         let jwt_settings = jwt_settings.unwrap();
+        let pool = pool.unwrap();
 
         // Decode the token
-        let token_data = match decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(jwt_settings.secret.expose_secret().as_bytes()),
-            &Validation::new(Algorithm::HS256),
-        ) {
+        let claims = match decode_token(token, &jwt_settings) {
             Ok(c) => c,
             Err(e) => {
                 return Box::pin(async move {
@@ -101,14 +180,198 @@ where
             }
         };
 
-        // Store the claims in the request extensions for handlers to access
-        req.extensions_mut().insert(token_data.claims);
+        // Tokens from the first step of a two-step login don't grant access
+        if claims.mfa_pending {
+            return Box::pin(async move {
+                Err(ErrorUnauthorized("Two-factor authentication required"))
+            });
+        }
 
-        let fut = self.service.call(req);
+        let service = self.service.clone();
 
         Box::pin(async move {
-            let res = fut.await?;
+            // A valid signature is not enough, the token may have been revoked
+            let user = authenticate_token(&pool, claims, require_verified_email).await?;
+
+            // Store the user in the request extensions for the `AuthenticatedUser` extractor
+            req.extensions_mut().insert(user);
+
+            let res = service.call(req).await?;
             Ok(res)
         })
     }
-}
\ No newline at end of file
+}
+
+/// Rejects tokens that were revoked individually (logout), that were issued
+/// before the user's `tokens_valid_after` cutoff (logout everywhere) or whose
+/// session was terminated, and optionally users who haven't verified their
+/// email address.
+async fn authenticate_token(
+    pool: &PgPool,
+    claims: Claims,
+    require_verified_email: bool
+) -> Result<AuthenticatedUser, Error> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| ErrorUnauthorized("Invalid token"))?;
+    let jti = Uuid::parse_str(&claims.jti)
+        .map_err(|_| ErrorUnauthorized("Invalid token"))?;
+    let session_id = match claims.sid.as_deref() {
+        Some(sid) => Some(Uuid::parse_str(sid).map_err(|_| ErrorUnauthorized("Invalid token"))?),
+        None => None,
+    };
+
+    let record = sqlx::query!(
+        r#"
+        SELECT
+            u.tokens_valid_after,
+            u.email_verified_at,
+            EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $2) as "revoked!",
+            EXISTS(SELECT 1 FROM sessions WHERE id = $3 AND revoked_at IS NOT NULL) as "session_ended!"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        user_id,
+        jti,
+        session_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check token revocation: {:?}", e);
+        ErrorInternalServerError("Failed to validate token")
+    })?;
+
+    let record = match record {
+        Some(record) => record,
+        None => {
+            tracing::info!("Token subject {} no longer exists", user_id);
+            return Err(ErrorUnauthorized("Invalid token"));
+        }
+    };
+
+    if record.revoked {
+        tracing::info!("Rejected revoked token {}", jti);
+        return Err(ErrorUnauthorized("Token has been revoked"));
+    }
+
+    // `iat` only has second precision, so compare against the cutoff's second
+    if let Some(valid_after) = record.tokens_valid_after {
+        if (claims.iat as i64) < valid_after.timestamp() {
+            tracing::info!("Rejected token {} issued before the revocation cutoff", jti);
+            return Err(ErrorUnauthorized("Token has been revoked"));
+        }
+    }
+
+    if record.session_ended {
+        tracing::info!("Rejected token {} of a terminated session", jti);
+        return Err(ErrorUnauthorized("Session has been terminated"));
+    }
+
+    if require_verified_email && record.email_verified_at.is_none() {
+        tracing::info!("Rejected user {} with unverified email address", user_id);
+        return Err(ErrorForbidden("Email address not verified"));
+    }
+
+    // Like API keys, only touch the session once a minute
+    if let Some(session_id) = session_id {
+        if let Err(e) = sqlx::query!(
+            "UPDATE sessions SET last_seen_at = $1 WHERE id = $2 AND last_seen_at < $3",
+            Utc::now(),
+            session_id,
+            Utc::now() - Duration::minutes(1)
+        )
+        .execute(pool)
+        .await {
+            tracing::warn!("Failed to record session activity: {:?}", e);
+        }
+    }
+
+    Ok(AuthenticatedUser {
+        id: user_id,
+        username: claims.username,
+        roles: claims.roles,
+        scopes: None,
+        credential: Credential::AccessToken {
+            jti,
+            expires_at: claims.exp,
+            session_id,
+            mfa: claims.mfa,
+        },
+    })
+}
+
+/// Looks up an API key and checks that it covers the requested upload endpoint.
+/// Returns the key's owner, limited to the key's scopes.
+async fn authenticate_api_key(
+    pool: &PgPool,
+    api_key: &str,
+    path: &str,
+    require_verified_email: bool
+) -> Result<AuthenticatedUser, Error> {
+    let record = sqlx::query!(
+        r#"
+        SELECT k.id, k.user_id, k.scopes, u.username, u.email_verified_at
+        FROM api_keys k
+        JOIN users u ON u.id = k.user_id
+        WHERE k.key_hash = $1 AND k.revoked_at IS NULL AND u.deletion_scheduled_for IS NULL
+        "#,
+        hash_token(api_key)
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up API key: {:?}", e);
+        ErrorInternalServerError("Failed to validate API key")
+    })?;
+
+    let record = match record {
+        Some(record) => record,
+        None => {
+            tracing::info!("Rejected unknown or revoked API key");
+            return Err(ErrorUnauthorized("Invalid API key"));
+        }
+    };
+
+    let user = AuthenticatedUser {
+        id: record.user_id,
+        username: record.username,
+        // Keys act for devices, not for the user's administrative roles
+        roles: Vec::new(),
+        scopes: Some(record.scopes),
+        credential: Credential::ApiKey,
+    };
+
+    // API keys can only upload, and only the data types they were created for.
+    // Batches are checked per upload by the handler.
+    let segment = path.rsplit('/').next().unwrap_or_default();
+    let allowed = match segment.strip_prefix("upload_") {
+        Some(data_type) => user.has_scope(data_type),
+        None => path == BATCH_UPLOAD_PATH,
+    };
+    if !allowed {
+        tracing::info!("API key {} used outside of its scopes for {}", record.id, path);
+        return Err(ErrorForbidden("API key is not allowed to access this endpoint"));
+    }
+
+    if require_verified_email && record.email_verified_at.is_none() {
+        tracing::info!("Rejected user {} with unverified email address", record.user_id);
+        return Err(ErrorForbidden("Email address not verified"));
+    }
+
+    // Only touch the row once a minute, gateways upload a lot
+    if let Err(e) = sqlx::query!(
+        r#"
+        UPDATE api_keys SET last_used_at = $1
+        WHERE id = $2 AND (last_used_at IS NULL OR last_used_at < $3)
+        "#,
+        Utc::now(),
+        record.id,
+        Utc::now() - Duration::minutes(1)
+    )
+    .execute(pool)
+    .await {
+        tracing::warn!("Failed to record API key usage: {:?}", e);
+    }
+
+    Ok(user)
+}
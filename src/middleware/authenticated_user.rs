@@ -0,0 +1,68 @@
+// src/middleware/authenticated_user.rs
+use std::future::{ready, Ready};
+use actix_web::{dev::Payload, error::ErrorUnauthorized, Error, FromRequest, HttpMessage, HttpRequest};
+use uuid::Uuid;
+
+use crate::models::role::Role;
+
+/// The user a request was authenticated as. Set by `AuthMiddleware`, so the
+/// extractor only works on routes wrapped with it and answers
+/// `401 Unauthorized` everywhere else.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub id: Uuid,
+    pub username: String,
+    pub roles: Vec<String>, // See `models::role::Role`
+    pub scopes: Option<Vec<String>>, // Data types the credential is limited to, if any
+    pub credential: Credential,
+}
+
+/// How the request was authenticated.
+#[derive(Debug, Clone)]
+pub enum Credential {
+    AccessToken {
+        jti: Uuid,
+        expires_at: usize,         // As UTC timestamp
+        session_id: Option<Uuid>,
+        mfa: bool,                 // Whether a second factor was verified for this login
+    },
+    ApiKey,
+}
+
+impl AuthenticatedUser {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|r| r == role.as_str())
+    }
+
+    /// Whether the credential may access data of the given type.
+    pub fn has_scope(&self, data_type: &str) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.iter().any(|scope| scope == data_type))
+    }
+
+    /// The login session of the access token, if the request was made with one.
+    pub fn session_id(&self) -> Option<Uuid> {
+        match self.credential {
+            Credential::AccessToken { session_id, .. } => session_id,
+            Credential::ApiKey => None,
+        }
+    }
+
+    /// Whether a second factor was verified for the login behind the request.
+    pub fn mfa(&self) -> bool {
+        matches!(self.credential, Credential::AccessToken { mfa: true, .. })
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedUser>()
+                .cloned()
+                .ok_or_else(|| ErrorUnauthorized("Not authenticated"))
+        )
+    }
+}
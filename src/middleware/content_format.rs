@@ -0,0 +1,171 @@
+// src/middleware/content_format.rs
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Serialization formats of the health data endpoints. All of them use the
+/// same serde models, so their contents are equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFormat {
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl ContentFormat {
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type.trim().to_ascii_lowercase().as_str() {
+            "application/json" => Some(Self::Json),
+            "application/cbor" => Some(Self::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Self::MessagePack),
+            _ => None,
+        }
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Cbor => "application/cbor",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// The format of a request body; bodies without `Content-Type` are JSON.
+    pub fn of_request(headers: &HeaderMap) -> Result<Self, String> {
+        let content_type = match headers.get(header::CONTENT_TYPE) {
+            Some(content_type) => content_type.to_str().unwrap_or_default(),
+            None => return Ok(Self::Json),
+        };
+        let media_type = content_type.split(';').next().unwrap_or_default();
+        Self::from_media_type(media_type)
+            .ok_or_else(|| format!("Unsupported content type '{}'", media_type.trim()))
+    }
+
+    /// The format the client prefers according to its `Accept` header, JSON
+    /// if it accepts none of the others.
+    pub fn accepted(headers: &HeaderMap) -> Self {
+        let accept = match headers.get(header::ACCEPT).and_then(|accept| accept.to_str().ok()) {
+            Some(accept) => accept,
+            None => return Self::Json,
+        };
+
+        let mut preferred = (Self::Json, 0.0);
+        for entry in accept.split(',') {
+            let mut parts = entry.split(';');
+            let format = parts.next().and_then(Self::from_media_type);
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .find_map(|quality| quality.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if let Some(format) = format {
+                if quality > preferred.1 {
+                    preferred = (format, quality);
+                }
+            }
+        }
+        preferred.0
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            Self::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+            Self::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            }
+            // With field names, so that structs are maps like in JSON
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Answers in the format of the request's `Accept` header: JSON responses
+/// are converted to CBOR or MessagePack if the client prefers them.
+pub struct ContentNegotiation;
+
+impl<S, B> Transform<S, ServiceRequest> for ContentNegotiation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = ContentNegotiationService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ContentNegotiationService { service: Rc::new(service) }))
+    }
+}
+
+pub struct ContentNegotiationService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ContentNegotiationService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let format = ContentFormat::accepted(req.headers());
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let mut res = service.call(req).await?.map_into_boxed_body();
+            res.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+
+            let is_json = res.headers().get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .is_some_and(|content_type| content_type.starts_with("application/json"));
+            if format == ContentFormat::Json || !is_json {
+                return Ok(res);
+            }
+
+            let (req, response) = res.into_parts();
+            let (response, response_body) = response.into_parts();
+            let bytes = body::to_bytes(response_body).await.map_err(actix_web::error::ErrorInternalServerError)?;
+            let encoded = serde_json::from_slice::<serde_json::Value>(&bytes)
+                .map_err(|e| e.to_string())
+                .and_then(|value| format.serialize(&value));
+
+            let response = match encoded {
+                Ok(encoded) => {
+                    let mut response = response.set_body(BoxBody::new(encoded));
+                    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(format.media_type()));
+                    response
+                }
+                Err(e) => {
+                    tracing::error!("Failed to convert response to {:?}: {}", format, e);
+                    HttpResponse::InternalServerError().finish()
+                }
+            };
+            Ok(ServiceResponse::new(req, response))
+        })
+    }
+}
@@ -1 +1,6 @@
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod role;
+pub mod authenticated_user;
+pub mod upload_body;
+pub mod content_format;
+pub mod request_span;
@@ -0,0 +1,58 @@
+// src/middleware/request_span.rs
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::{Error, HttpMessage};
+use tracing::field::Empty;
+use tracing::Span;
+use tracing_actix_web::{root_span, DefaultRootSpanBuilder, RequestId, RootSpanBuilder};
+
+/// Paths whose last segment is a bearer secret, e.g. a share link token.
+const SECRET_PATH_PREFIXES: [&str; 1] = ["/shared/"];
+
+/// Root span of every request, as `DefaultRootSpanBuilder` builds it, except
+/// that secrets in the request path are masked before they reach the logs.
+pub struct RedactingRootSpanBuilder;
+
+impl RootSpanBuilder for RedactingRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let path = request.path();
+        let Some(prefix) = SECRET_PATH_PREFIXES.iter().find(|prefix| path.starts_with(*prefix)) else {
+            return root_span!(request);
+        };
+
+        let target = match request.query_string() {
+            "" => format!("{}[REDACTED]", prefix),
+            query => format!("{}[REDACTED]?{}", prefix, query),
+        };
+        let http_route = request.match_pattern().unwrap_or_else(|| "default".to_string());
+        // TracingLogger stores the id before it asks for the root span
+        let request_id = request.extensions().get::<RequestId>().copied().unwrap();
+        let connection_info = request.connection_info();
+        let user_agent = request
+            .headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("");
+
+        tracing::info_span!(
+            "HTTP request",
+            http.method = %request.method(),
+            http.route = %http_route,
+            http.host = %connection_info.host(),
+            http.client_ip = %connection_info.realip_remote_addr().unwrap_or(""),
+            http.user_agent = %user_agent,
+            http.target = %target,
+            http.status_code = Empty,
+            otel.name = %format!("{} {}", request.method(), http_route),
+            otel.kind = "server",
+            otel.status_code = Empty,
+            request_id = %request_id,
+            exception.message = Empty,
+            exception.details = Empty,
+        )
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
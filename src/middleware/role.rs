@@ -0,0 +1,87 @@
+// src/middleware/role.rs
+use std::future::{ready, Ready};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform}, error::{ErrorForbidden, ErrorUnauthorized}, Error, HttpMessage
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::role::Role;
+
+/// Restricts a scope to users with the given role.
+///
+/// Relies on the user set by `AuthMiddleware`, so `AuthMiddleware` has to be
+/// registered after this one (actix runs the last registered middleware first):
+/// `.wrap(RequireRole::new(Role::Admin)).wrap(AuthMiddleware::new())`
+pub struct RequireRole {
+    role: Role,
+}
+
+impl RequireRole {
+    pub fn new(role: Role) -> Self {
+        Self { role }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequireRoleService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleService { service, role: self.role }))
+    }
+}
+
+pub struct RequireRoleService<S> {
+    service: S,
+    role: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let has_role = req.extensions()
+            .get::<AuthenticatedUser>()
+            .map(|user| user.has_role(self.role));
+
+        match has_role {
+            Some(true) => {}
+            Some(false) => {
+                let role = self.role;
+                return Box::pin(async move {
+                    tracing::info!("Rejected request without the {} role", role);
+                    Err(ErrorForbidden("Insufficient role"))
+                });
+            }
+            None => {
+                return Box::pin(async move {
+                    Err(ErrorUnauthorized("Not authenticated"))
+                });
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,153 @@
+// src/middleware/upload_body.rs
+use std::io::Read;
+
+use actix_web::dev::Payload;
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::http::header::{CONTENT_ENCODING, CONTENT_LENGTH};
+use actix_web::{web, Error, FromRequest, HttpRequest, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::config::settings::{ApplicationSettings, PayloadLimitSettings};
+use crate::middleware::content_format::ContentFormat;
+
+/// The body of an upload, deserialized from JSON, CBOR or MessagePack as
+/// given by `Content-Type`. Bodies can be compressed with
+/// `Content-Encoding: gzip`, `br` or `zstd`.
+///
+/// The size limit of the route, see `PayloadLimitSettings`, applies to the
+/// body as sent and after decompression, so that a small compressed body
+/// can't expand into an arbitrarily large one.
+pub struct UploadBody<T>(pub T);
+
+impl<T> UploadBody<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for UploadBody<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for UploadBody<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let limit = req.app_data::<web::Data<ApplicationSettings>>()
+            .map(|settings| settings.payload_limits.limit_for(req.path()))
+            .unwrap_or_else(|| PayloadLimitSettings::default().sensor_upload_bytes);
+        let encoding = req.headers().get(CONTENT_ENCODING)
+            .map(|value| value.to_str().unwrap_or_default().trim().to_ascii_lowercase());
+        let content_length = req.headers().get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        let format = ContentFormat::of_request(req.headers());
+        let mut payload = payload.take();
+
+        Box::pin(async move {
+            let format = format.map_err(|message| upload_error(HttpResponse::UnsupportedMediaType(), message))?;
+            if content_length.is_some_and(|length| length > limit) {
+                return Err(payload_too_large(limit));
+            }
+
+            let mut body = web::BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk?;
+                if body.len() + chunk.len() > limit {
+                    return Err(payload_too_large(limit));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            let body = match encoding.as_deref() {
+                None | Some("identity") => body.to_vec(),
+                Some(encoding @ ("gzip" | "br" | "zstd")) => {
+                    let encoding = encoding.to_string();
+                    match web::block(move || decompress(&encoding, &body, limit)).await? {
+                        Ok(body) => body,
+                        Err(DecompressError::TooLarge) => return Err(payload_too_large(limit)),
+                        Err(DecompressError::Invalid(encoding)) => {
+                            return Err(upload_error(
+                                HttpResponse::BadRequest(),
+                                format!("Invalid {} encoded body", encoding),
+                            ));
+                        }
+                    }
+                }
+                Some(encoding) => {
+                    return Err(upload_error(
+                        HttpResponse::UnsupportedMediaType(),
+                        format!("Unsupported content encoding '{}'", encoding),
+                    ));
+                }
+            };
+
+            format.deserialize(&body)
+                .map(UploadBody)
+                .map_err(|e| upload_error(HttpResponse::BadRequest(), format!("Invalid upload: {}", e)))
+        })
+    }
+}
+
+enum DecompressError {
+    TooLarge,
+    Invalid(String),
+}
+
+/// Decompresses a body, failing as soon as the output exceeds `limit`.
+fn decompress(encoding: &str, body: &[u8], limit: usize) -> Result<Vec<u8>, DecompressError> {
+    let invalid = || DecompressError::Invalid(encoding.to_string());
+    let decoder: Box<dyn Read + '_> = match encoding {
+        "gzip" => Box::new(flate2::read::MultiGzDecoder::new(body)),
+        "br" => Box::new(brotli::Decompressor::new(body, 4096)),
+        _ => Box::new(zstd::stream::read::Decoder::new(body).map_err(|_| invalid())?),
+    };
+
+    let mut decompressed = Vec::new();
+    // One byte more than allowed tells an oversized body from one of exactly the limit
+    decoder.take(limit as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| invalid())?;
+    if decompressed.len() > limit {
+        tracing::warn!("Rejected {} body that decompresses to more than {} bytes", encoding, limit);
+        return Err(DecompressError::TooLarge);
+    }
+    Ok(decompressed)
+}
+
+/// `413 Payload Too Large` telling the client the maximum size.
+fn payload_too_large(limit: usize) -> Error {
+    let response = HttpResponse::PayloadTooLarge().json(json!({
+        "status": "error",
+        "message": format!("Request body exceeds the maximum size of {} bytes", limit),
+        "max_size_bytes": limit
+    }));
+    InternalError::from_response("Payload too large", response).into()
+}
+
+/// Error handler of the `JsonConfig` of all other endpoints, so that they
+/// answer oversized bodies the same way.
+pub fn json_error_handler(error: JsonPayloadError, _req: &HttpRequest) -> Error {
+    match error {
+        JsonPayloadError::OverflowKnownLength { limit, .. } | JsonPayloadError::Overflow { limit } => {
+            payload_too_large(limit)
+        }
+        error => error.into(),
+    }
+}
+
+fn upload_error(mut response: actix_web::HttpResponseBuilder, message: String) -> Error {
+    let response = response.json(json!({
+        "status": "error",
+        "message": message
+    }));
+    InternalError::from_response("Invalid upload", response).into()
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use secrecy::SecretString;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteAccountRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub password: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AccountDeletionResponse {
+    // Id of the deletion receipt that is recorded once the account is erased
+    pub receipt_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub current_password: SecretString,
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub new_password: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: String,
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub password: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangeUsernameRequest {
+    pub username: String,
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct LockoutEvent {
+    pub id: Uuid,
+    pub scope: String, // "account" or "ip"
+    pub key: String,   // Username or client address
+    pub user_id: Option<Uuid>,
+    pub failed_attempts: i32,
+    pub locked_until: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub unlocked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct LockoutEventsQuery {
+    // Only return lockouts that are still in effect
+    #[serde(default)]
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserRolesResponse {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+}
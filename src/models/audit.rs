@@ -0,0 +1,155 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventType {
+    LoginSucceeded,
+    LoginFailed,
+    TokenRefreshed,
+    RefreshTokenReused,       // A rotated refresh token was presented again, its session was revoked
+    PermissionsChanged,       // Data collection permissions and third-party connections
+    AccountDeletionRequested,
+    AccountRestored,          // A pending deletion was cancelled
+    AccountErased,
+    DelegationGranted,
+    DelegationAccepted,
+    DelegationRevoked,
+    HealthDataAccessed,       // Health data read by someone other than its owner
+    ShareLinkCreated,
+    ShareLinkRevoked,
+    ShareLinkAccessed,
+    StudyEnrolled,
+    StudyWithdrawn,
+    RoleGranted,
+    RoleRevoked,              // Also revoked all tokens of the user
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventType::LoginSucceeded => "login_succeeded",
+            AuditEventType::LoginFailed => "login_failed",
+            AuditEventType::TokenRefreshed => "token_refreshed",
+            AuditEventType::RefreshTokenReused => "refresh_token_reused",
+            AuditEventType::PermissionsChanged => "permissions_changed",
+            AuditEventType::AccountDeletionRequested => "account_deletion_requested",
+            AuditEventType::AccountRestored => "account_restored",
+            AuditEventType::AccountErased => "account_erased",
+            AuditEventType::DelegationGranted => "delegation_granted",
+            AuditEventType::DelegationAccepted => "delegation_accepted",
+            AuditEventType::DelegationRevoked => "delegation_revoked",
+            AuditEventType::HealthDataAccessed => "health_data_accessed",
+            AuditEventType::ShareLinkCreated => "share_link_created",
+            AuditEventType::ShareLinkRevoked => "share_link_revoked",
+            AuditEventType::ShareLinkAccessed => "share_link_accessed",
+            AuditEventType::StudyEnrolled => "study_enrolled",
+            AuditEventType::StudyWithdrawn => "study_withdrawn",
+            AuditEventType::RoleGranted => "role_granted",
+            AuditEventType::RoleRevoked => "role_revoked",
+        }
+    }
+}
+
+impl fmt::Display for AuditEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "login_succeeded" => Ok(AuditEventType::LoginSucceeded),
+            "login_failed" => Ok(AuditEventType::LoginFailed),
+            "token_refreshed" => Ok(AuditEventType::TokenRefreshed),
+            "refresh_token_reused" => Ok(AuditEventType::RefreshTokenReused),
+            "permissions_changed" => Ok(AuditEventType::PermissionsChanged),
+            "account_deletion_requested" => Ok(AuditEventType::AccountDeletionRequested),
+            "account_restored" => Ok(AuditEventType::AccountRestored),
+            "account_erased" => Ok(AuditEventType::AccountErased),
+            "delegation_granted" => Ok(AuditEventType::DelegationGranted),
+            "delegation_accepted" => Ok(AuditEventType::DelegationAccepted),
+            "delegation_revoked" => Ok(AuditEventType::DelegationRevoked),
+            "health_data_accessed" => Ok(AuditEventType::HealthDataAccessed),
+            "share_link_created" => Ok(AuditEventType::ShareLinkCreated),
+            "share_link_revoked" => Ok(AuditEventType::ShareLinkRevoked),
+            "share_link_accessed" => Ok(AuditEventType::ShareLinkAccessed),
+            "study_enrolled" => Ok(AuditEventType::StudyEnrolled),
+            "study_withdrawn" => Ok(AuditEventType::StudyWithdrawn),
+            "role_granted" => Ok(AuditEventType::RoleGranted),
+            "role_revoked" => Ok(AuditEventType::RoleRevoked),
+            other => Err(format!("{} is not a known event type", other)),
+        }
+    }
+}
+
+/// An event to append to the audit log.
+pub struct NewAuditEvent {
+    pub event_type: AuditEventType,
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl NewAuditEvent {
+    /// An event concerning the given user, caused by the user themselves.
+    pub fn new(event_type: AuditEventType, user_id: Option<Uuid>) -> Self {
+        Self {
+            event_type,
+            user_id,
+            actor_id: user_id,
+            ip_address: None,
+            details: None,
+        }
+    }
+
+    pub fn actor(mut self, actor_id: Option<Uuid>) -> Self {
+        self.actor_id = actor_id;
+        self
+    }
+
+    pub fn ip_address(mut self, ip_address: Option<&str>) -> Self {
+        self.ip_address = ip_address.map(|address| address.to_string());
+        self
+    }
+
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub event_type: String, // See `AuditEventType`
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountAuditQuery {
+    pub event_type: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct AuditEventsQuery {
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub event_type: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
@@ -8,9 +8,64 @@ pub struct LoginRequest {
     #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
             deserialize_with = "crate::models::user::deserialize_secret_string")]
     pub password: SecretString,
+    // Shown in the list of sessions, e.g. "Pixel 8"
+    #[serde(default)]
+    pub device_name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
-}
\ No newline at end of file
+    pub refresh_token: String,
+    pub expires_in: i64, // Access token lifetime in seconds
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RefreshRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub refresh_token: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LogoutRequest {
+    #[serde(default,
+            serialize_with = "crate::models::user::serialize_optional_secret_string",
+            deserialize_with = "crate::models::user::deserialize_optional_secret_string")]
+    pub refresh_token: Option<SecretString>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkLoginRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string",
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub token: SecretString,
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasswordResetConfirmRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub token: SecretString,
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub new_password: SecretString,
+}
+#[derive(Serialize, Deserialize)]
+pub struct VerifyEmailQuery {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub token: SecretString,
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Data types read access can be delegated for. `sleep` covers all sleep endpoints.
+pub const DELEGATION_DATA_TYPES: [&str; 6] = [
+    "acceleration",
+    "heart_rate",
+    "blood_oxygen",
+    "skin_temperature",
+    "gps_location",
+    "sleep",
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateDelegationRequest {
+    pub username: String, // The account to invite
+    pub data_types: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DelegationInfo {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub owner_username: String,
+    pub delegate_id: Uuid,
+    pub delegate_username: String,
+    pub data_types: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>, // Pending invitation while unset
+}
+
+/// Selects whose data a `/health` read endpoint returns.
+#[derive(Deserialize)]
+pub struct DataOwnerQuery {
+    // Another user who delegated access to the requesting user; defaults to the requesting user
+    pub user_id: Option<Uuid>,
+}
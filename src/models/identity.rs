@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct OidcLoginRequest {
+    pub provider: String, // Name of a configured provider, e.g. "google"
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string",
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub id_token: SecretString,
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LinkIdentityRequest {
+    pub provider: String,
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string",
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub id_token: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IdentityInfo {
+    pub id: Uuid,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OidcNonceResponse {
+    pub nonce: String,
+    pub expires_in: i64, // Seconds
+}
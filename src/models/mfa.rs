@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use secrecy::SecretString;
+
+/// Returned by `/login` instead of a token pair when the user has two-factor authentication enabled.
+#[derive(Serialize, Deserialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: i64, // MFA token lifetime in seconds
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MfaLoginRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub mfa_token: SecretString,
+    // Either a TOTP code or a recovery code
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub code: SecretString,
+    // See `LoginRequest::device_name`
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TotpEnrollmentResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MfaCodeRequest {
+    #[serde(serialize_with = "crate::models::user::serialize_secret_string", 
+            deserialize_with = "crate::models::user::deserialize_secret_string")]
+    pub code: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
@@ -1,5 +1,17 @@
 pub mod user;
 pub mod auth;
 pub mod sensor_data;
+pub mod sensor_stream;
 pub mod sleep;
-pub mod onboarding;
\ No newline at end of file
+pub mod onboarding;
+pub mod admin;
+pub mod mfa;
+pub mod role;
+pub mod api_key;
+pub mod account;
+pub mod session;
+pub mod identity;
+pub mod audit;
+pub mod delegation;
+pub mod share_link;
+pub mod study;
\ No newline at end of file
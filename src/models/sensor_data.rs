@@ -38,30 +38,6 @@ pub struct HeartRateSample {
     pub confidence: Option<f64>,  // confidence score between 0 and 1
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct AccelerationDataUpload {
-    pub data_type: String,
-    pub device_info: DeviceInfo,
-    pub sampling_rate_hz: i32,
-    pub start_time: DateTime<Utc>,
-    pub end_time: DateTime<Utc>,
-    pub samples: Vec<AccelerationSample>,
-    #[serde(default)]
-    pub metadata: Option<serde_json::Value>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct HeartRateDataUpload {
-    pub data_type: String,
-    pub device_info: DeviceInfo,
-    pub sampling_rate_hz: i32,
-    pub start_time: DateTime<Utc>,
-    pub end_time: DateTime<Utc>,
-    pub samples: Vec<HeartRateSample>,
-    #[serde(default)]
-    pub metadata: Option<serde_json::Value>,
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BloodOxygenSample {
     pub timestamp: DateTime<Utc>,
@@ -70,18 +46,6 @@ pub struct BloodOxygenSample {
     pub confidence: Option<f64>,  // confidence score between 0 and 1
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BloodOxygenDataUpload {
-    pub data_type: String,
-    pub device_info: DeviceInfo,
-    pub sampling_rate_hz: i32,
-    pub start_time: DateTime<Utc>,
-    pub end_time: DateTime<Utc>,
-    pub samples: Vec<BloodOxygenSample>,
-    #[serde(default)]
-    pub metadata: Option<serde_json::Value>,
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SkinTemperatureSample {
     pub timestamp: DateTime<Utc>,
@@ -92,18 +56,6 @@ pub struct SkinTemperatureSample {
     pub body_location: Option<String>,  // Optional body location (e.g., "wrist", "forehead")
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SkinTemperatureDataUpload {
-    pub data_type: String,
-    pub device_info: DeviceInfo,
-    pub sampling_rate_hz: i32,
-    pub start_time: DateTime<Utc>,
-    pub end_time: DateTime<Utc>,
-    pub samples: Vec<SkinTemperatureSample>,
-    #[serde(default)]
-    pub metadata: Option<serde_json::Value>,
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GpsLocationSample {
     pub timestamp: DateTime<Utc>,
@@ -115,16 +67,21 @@ pub struct GpsLocationSample {
     pub bearing: Option<f64>,
 }
 
+/// An upload of one sensor stream, see `models::sensor_stream`.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct GpsLocationDataUpload {
+pub struct SensorDataUpload<S> {
     pub data_type: String,
     pub device_info: DeviceInfo,
     pub sampling_rate_hz: i32,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
-    pub samples: Vec<GpsLocationSample>,
+    pub samples: Vec<S>,
     #[serde(default)]
     pub metadata: Option<serde_json::Value>,
+    // Id chosen by the client to make retries idempotent, unique per user.
+    // Also accepted as `Idempotency-Key` header.
+    #[serde(default)]
+    pub client_upload_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -153,3 +110,42 @@ pub struct HealthDataTimeQuery {
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
 }
+
+/// How `POST /health/batch` treats uploads that can't be stored.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    // One rejected upload rejects the whole batch
+    #[default]
+    AllOrNothing,
+    // The other uploads are stored anyway
+    BestEffort,
+}
+
+/// Uploads of several sensor streams, each tagged by its `data_type`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchUploadRequest {
+    #[serde(default)]
+    pub mode: BatchMode,
+    pub uploads: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub data_type: Option<String>,
+    // "success", "duplicate", "error", or "not_stored" for valid uploads of a rejected batch
+    pub status: String,
+    pub id: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchUploadResponse {
+    // "success" if no upload was rejected, "partial" if others of a best
+    // effort batch are stored, otherwise "error" (answered with 400)
+    pub status: String,
+    // Uploads whose data is stored, including duplicates of earlier uploads
+    pub stored: usize,
+    pub results: Vec<BatchItemResult>,
+}
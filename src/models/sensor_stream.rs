@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::models::sensor_data::{
+    AccelerationSample, BloodOxygenSample, GpsLocationSample, HeartRateSample, SkinTemperatureSample
+};
+
+/// A kind of sensor data that is uploaded as `SensorDataUpload<Self::Sample>`
+/// and stored in `health_data` under `DATA_TYPE`.
+///
+/// Adding a sensor means declaring its sample type and a stream type
+/// implementing this trait, then registering it with
+/// `routes::health_data::sensor_stream`.
+pub trait SensorStream: 'static {
+    /// The `data_type` of uploads and stored records, also used in the endpoint paths.
+    const DATA_TYPE: &'static str;
+    /// Used in messages, e.g. "heart rate".
+    const NAME: &'static str;
+
+    type Sample: Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    fn timestamp(sample: &Self::Sample) -> DateTime<Utc>;
+
+    /// Checks the values of a sample; the message is returned to the client.
+    fn validate_sample(_sample: &Self::Sample) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct Acceleration;
+pub struct HeartRate;
+pub struct BloodOxygen;
+pub struct SkinTemperature;
+pub struct GpsLocation;
+
+impl SensorStream for Acceleration {
+    const DATA_TYPE: &'static str = "acceleration";
+    const NAME: &'static str = "acceleration";
+    type Sample = AccelerationSample;
+
+    fn timestamp(sample: &AccelerationSample) -> DateTime<Utc> {
+        sample.timestamp
+    }
+
+    fn validate_sample(sample: &AccelerationSample) -> Result<(), String> {
+        if ![sample.x, sample.y, sample.z].iter().all(|value| value.is_finite()) {
+            return Err("Acceleration values must be finite numbers".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl SensorStream for HeartRate {
+    const DATA_TYPE: &'static str = "heart_rate";
+    const NAME: &'static str = "heart rate";
+    type Sample = HeartRateSample;
+
+    fn timestamp(sample: &HeartRateSample) -> DateTime<Utc> {
+        sample.timestamp
+    }
+
+    fn validate_sample(sample: &HeartRateSample) -> Result<(), String> {
+        if !(1..=300).contains(&sample.heart_rate) {
+            return Err(format!("Heart rate must be between 1 and 300 bpm, got {}", sample.heart_rate));
+        }
+        validate_confidence(sample.confidence)
+    }
+}
+
+impl SensorStream for BloodOxygen {
+    const DATA_TYPE: &'static str = "blood_oxygen";
+    const NAME: &'static str = "blood oxygen";
+    type Sample = BloodOxygenSample;
+
+    fn timestamp(sample: &BloodOxygenSample) -> DateTime<Utc> {
+        sample.timestamp
+    }
+
+    fn validate_sample(sample: &BloodOxygenSample) -> Result<(), String> {
+        if !(0.0..=100.0).contains(&sample.spo2) {
+            return Err(format!("SpO2 must be a percentage between 0 and 100, got {}", sample.spo2));
+        }
+        validate_confidence(sample.confidence)
+    }
+}
+
+impl SensorStream for SkinTemperature {
+    const DATA_TYPE: &'static str = "skin_temperature";
+    const NAME: &'static str = "skin temperature";
+    type Sample = SkinTemperatureSample;
+
+    fn timestamp(sample: &SkinTemperatureSample) -> DateTime<Utc> {
+        sample.timestamp
+    }
+
+    fn validate_sample(sample: &SkinTemperatureSample) -> Result<(), String> {
+        // Generous bounds, cold extremities read far below core temperature
+        if !(0.0..=50.0).contains(&sample.temperature) {
+            return Err(format!("Temperature must be between 0 and 50 °C, got {}", sample.temperature));
+        }
+        validate_confidence(sample.confidence)
+    }
+}
+
+impl SensorStream for GpsLocation {
+    const DATA_TYPE: &'static str = "gps_location";
+    const NAME: &'static str = "GPS location";
+    type Sample = GpsLocationSample;
+
+    fn timestamp(sample: &GpsLocationSample) -> DateTime<Utc> {
+        sample.timestamp
+    }
+
+    fn validate_sample(sample: &GpsLocationSample) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&sample.latitude) || !(-180.0..=180.0).contains(&sample.longitude) {
+            return Err(format!("Invalid coordinates: {}, {}", sample.latitude, sample.longitude));
+        }
+        Ok(())
+    }
+}
+
+fn validate_confidence(confidence: Option<f64>) -> Result<(), String> {
+    match confidence {
+        Some(confidence) if !(0.0..=1.0).contains(&confidence) => {
+            Err(format!("Confidence must be between 0 and 1, got {}", confidence))
+        }
+        _ => Ok(()),
+    }
+}
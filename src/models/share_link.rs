@@ -0,0 +1,64 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateShareLinkRequest {
+    pub data_types: Vec<String>, // See `DELEGATION_DATA_TYPES`
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Returned once on creation, the only time the token is available.
+#[derive(Serialize, Deserialize)]
+pub struct CreatedShareLinkResponse {
+    pub id: Uuid,
+    pub token: String,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShareLinkInfo {
+    pub id: Uuid,
+    pub data_types: Vec<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub access_count: i32,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct SharedDataQuery {
+    pub format: Option<String>, // "json" or "html", otherwise decided by the Accept header
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SharedHealthRecord {
+    pub data_type: String,
+    pub device_info: serde_json::Value,
+    pub sampling_rate_hz: i32,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SharedSleepSummary {
+    pub night_date: NaiveDate,
+    pub data: serde_json::Value,
+}
+
+/// What a share link shows.
+#[derive(Serialize, Deserialize)]
+pub struct SharedDataResponse {
+    pub data_types: Vec<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub expires_at: DateTime<Utc>,
+    pub health_data: Vec<SharedHealthRecord>,
+    pub sleep_summaries: Vec<SharedSleepSummary>,
+}
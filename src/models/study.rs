@@ -0,0 +1,99 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::share_link::{SharedHealthRecord, SharedSleepSummary};
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    pub admin_username: String, // The organization's first admin
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OrganizationInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddOrganizationAdminRequest {
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateStudyRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub data_types: Vec<String>, // See `DELEGATION_DATA_TYPES`
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StudyInfo {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub data_types: Vec<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub invite_code: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnrollRequest {
+    pub invite_code: String,
+}
+
+/// A study as its participants see it.
+#[derive(Serialize, Deserialize)]
+pub struct EnrollmentInfo {
+    pub study_id: Uuid,
+    pub study_name: String,
+    pub organization_name: String,
+    pub data_types: Vec<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub enrolled_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParticipantInfo {
+    pub user_id: Uuid,
+    pub username: String,
+    pub enrolled_at: DateTime<Utc>,
+    pub consented_data_types: Vec<String>, // Study data types the participant's permissions still allow
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DataTypeAggregate {
+    pub data_type: String,
+    pub consenting_participants: i64,
+    pub participants_with_data: i64,
+    pub record_count: i64,       // Uploads, or nights for `sleep`
+    pub sample_count: Option<i64>, // Not set for `sleep`
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StudyAggregateResponse {
+    pub study_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub participant_count: i64,
+    pub data_types: Vec<DataTypeAggregate>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParticipantDataResponse {
+    pub study_id: Uuid,
+    pub user_id: Uuid,
+    pub data_types: Vec<String>, // The consented study data types the response covers
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub health_data: Vec<SharedHealthRecord>,
+    pub sleep_summaries: Vec<SharedSleepSummary>,
+}
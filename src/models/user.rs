@@ -38,4 +38,22 @@ where
 {
     let s = String::deserialize(deserializer)?;
     Ok(SecretString::new(s.into_boxed_str()))
+}
+
+pub fn serialize_optional_secret_string<S>(secret: &Option<SecretString>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match secret {
+        Some(_) => serializer.serialize_some("[REDACTED]"),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_optional_secret_string<'de, D>(deserializer: D) -> Result<Option<SecretString>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    Ok(s.map(|s| SecretString::new(s.into_boxed_str())))
 }
\ No newline at end of file
@@ -0,0 +1,80 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::{
+    AccountDeletionSettings, ApplicationSettings, LoginThrottlingSettings, PasswordHashingSettings,
+};
+use crate::email::Mailer;
+use crate::handlers::account_handler::{
+    change_email, change_password, change_username, request_account_deletion, restore_account,
+};
+use crate::handlers::audit_handler::list_account_audit_events;
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::account::{ChangeEmailRequest, ChangePasswordRequest, ChangeUsernameRequest, DeleteAccountRequest};
+use crate::models::audit::AccountAuditQuery;
+
+#[put("/account/password", wrap = "AuthMiddleware::new()")]
+async fn password(
+    req: HttpRequest,
+    password_form: web::Json<ChangePasswordRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    change_password(req, password_form, pool, jwt_settings, password_hashing_settings, login_throttling_settings, user).await
+}
+
+#[put("/account/email", wrap = "AuthMiddleware::new()")]
+async fn email(
+    req: HttpRequest,
+    email_form: web::Json<ChangeEmailRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    change_email(req, email_form, pool, mailer, application_settings, login_throttling_settings, user).await
+}
+
+#[put("/account/username", wrap = "AuthMiddleware::new()")]
+async fn username(
+    username_form: web::Json<ChangeUsernameRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    change_username(username_form, pool, user).await
+}
+
+#[delete("/account", wrap = "AuthMiddleware::new()")]
+async fn delete_account(
+    req: HttpRequest,
+    delete_form: web::Json<DeleteAccountRequest>,
+    pool: web::Data<PgPool>,
+    account_deletion_settings: web::Data<AccountDeletionSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    request_account_deletion(req, delete_form, pool, account_deletion_settings, login_throttling_settings, user).await
+}
+
+#[post("/account/restore", wrap = "AuthMiddleware::new()")]
+async fn restore(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    restore_account(pool, user).await
+}
+
+#[get("/account/audit", wrap = "AuthMiddleware::new()")]
+async fn audit(
+    pool: web::Data<PgPool>,
+    query: web::Query<AccountAuditQuery>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_account_audit_events(pool, query, user).await
+}
\ No newline at end of file
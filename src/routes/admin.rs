@@ -0,0 +1,71 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::admin_handler::{
+    get_user_roles, grant_user_role, list_lockout_events, revoke_user_role, unlock_user_account
+};
+use crate::handlers::audit_handler::search_audit_events;
+use crate::handlers::study_handler::create_organization;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::admin::LockoutEventsQuery;
+use crate::models::audit::AuditEventsQuery;
+use crate::models::study::CreateOrganizationRequest;
+
+#[get("/lockouts")]
+async fn lockouts(
+    pool: web::Data<PgPool>,
+    query: web::Query<LockoutEventsQuery>
+) -> HttpResponse {
+    list_lockout_events(pool, query).await
+}
+
+#[post("/users/{user_id}/unlock")]
+async fn unlock_user(
+    pool: web::Data<PgPool>,
+    user_id: web::Path<Uuid>
+) -> HttpResponse {
+    unlock_user_account(pool, user_id).await
+}
+
+#[get("/users/{user_id}/roles")]
+async fn user_roles(
+    pool: web::Data<PgPool>,
+    user_id: web::Path<Uuid>
+) -> HttpResponse {
+    get_user_roles(pool, user_id).await
+}
+
+#[put("/users/{user_id}/roles/{role}")]
+async fn grant_role(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, String)>,
+    admin: AuthenticatedUser
+) -> HttpResponse {
+    grant_user_role(pool, path, admin).await
+}
+
+#[delete("/users/{user_id}/roles/{role}")]
+async fn revoke_role(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, String)>,
+    admin: AuthenticatedUser
+) -> HttpResponse {
+    revoke_user_role(pool, path, admin).await
+}
+
+#[get("/audit")]
+async fn audit_events(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditEventsQuery>
+) -> HttpResponse {
+    search_audit_events(pool, query).await
+}
+
+#[post("/organizations")]
+async fn organizations(
+    organization_form: web::Json<CreateOrganizationRequest>,
+    pool: web::Data<PgPool>
+) -> HttpResponse {
+    create_organization(organization_form, pool).await
+}
\ No newline at end of file
@@ -0,0 +1,34 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::api_key_handler::{create_api_key, list_api_keys, revoke_api_key};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::api_key::CreateApiKeyRequest;
+
+#[post("/api_keys", wrap = "AuthMiddleware::new()")]
+async fn create(
+    key_form: web::Json<CreateApiKeyRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    create_api_key(key_form, pool, user).await
+}
+
+#[get("/api_keys", wrap = "AuthMiddleware::new()")]
+async fn list(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_api_keys(pool, user).await
+}
+
+#[delete("/api_keys/{key_id}", wrap = "AuthMiddleware::new()")]
+async fn revoke(
+    key_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    revoke_api_key(key_id, pool, user).await
+}
@@ -1,16 +1,50 @@
 // src/routes/auth.rs
-use actix_web::{post, web, HttpResponse};
+use actix_web::{post, web, HttpRequest, HttpResponse};
 use sqlx::PgPool;
 
-use crate::handlers::auth_handler::login_user;
-use crate::models::auth::LoginRequest;
+use crate::handlers::auth_handler::{login_user, logout_all_sessions, logout_user, refresh_access_token};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::auth::{LoginRequest, LogoutRequest, RefreshRequest};
 use crate::config::jwt::JwtSettings;
+use crate::config::settings::{LoginThrottlingSettings, PasswordHashingSettings};
 
 #[post("/login")]
 async fn login(
+    req: HttpRequest,
     login_form: web::Json<LoginRequest>, 
     pool: web::Data<PgPool>,
-    jwt_settings: web::Data<JwtSettings>
+    jwt_settings: web::Data<JwtSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
 ) -> HttpResponse {
-    login_user(login_form, pool, jwt_settings).await
+    login_user(req, login_form, pool, jwt_settings, password_hashing_settings, login_throttling_settings).await
+}
+
+#[post("/refresh")]
+async fn refresh(
+    req: HttpRequest,
+    refresh_form: web::Json<RefreshRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    refresh_access_token(req, refresh_form, pool, jwt_settings, login_throttling_settings).await
+}
+
+#[post("/logout", wrap = "AuthMiddleware::new()")]
+async fn logout(
+    logout_form: Option<web::Json<LogoutRequest>>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    logout_user(logout_form, pool, user).await
+}
+
+#[post("/logout_all", wrap = "AuthMiddleware::new()")]
+async fn logout_all(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    logout_all_sessions(pool, user).await
 }
\ No newline at end of file
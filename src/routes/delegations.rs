@@ -0,0 +1,53 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::delegation_handler::{
+    accept_delegation, create_delegation, list_granted_delegations, list_received_delegations, revoke_delegation
+};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::delegation::CreateDelegationRequest;
+
+#[post("/delegations", wrap = "AuthMiddleware::new()")]
+async fn create(
+    delegation_form: web::Json<CreateDelegationRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    create_delegation(delegation_form, pool, user).await
+}
+
+#[get("/delegations/granted", wrap = "AuthMiddleware::new()")]
+async fn granted(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_granted_delegations(pool, user).await
+}
+
+#[get("/delegations/received", wrap = "AuthMiddleware::new()")]
+async fn received(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_received_delegations(pool, user).await
+}
+
+#[post("/delegations/{delegation_id}/accept", wrap = "AuthMiddleware::new()")]
+async fn accept(
+    delegation_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    accept_delegation(delegation_id, pool, user).await
+}
+
+#[delete("/delegations/{delegation_id}", wrap = "AuthMiddleware::new()")]
+async fn revoke(
+    delegation_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    revoke_delegation(delegation_id, pool, user).await
+}
@@ -0,0 +1,27 @@
+use actix_web::{get, post, web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::config::settings::ApplicationSettings;
+use crate::email::Mailer;
+use crate::handlers::email_verification_handler::{resend_verification_email, verify_email_address};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::auth::VerifyEmailQuery;
+
+#[get("/verify_email")]
+async fn verify_email(
+    query: web::Query<VerifyEmailQuery>,
+    pool: web::Data<PgPool>
+) -> HttpResponse {
+    verify_email_address(query, pool).await
+}
+
+#[post("/verify_email/resend", wrap = "AuthMiddleware::new()")]
+async fn resend_verification(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    resend_verification_email(pool, mailer, application_settings, user).await
+}
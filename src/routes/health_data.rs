@@ -1,27 +1,13 @@
-// Update src/routes/health_data.rs
-use actix_web::{post, get, web, HttpResponse};
-use crate::handlers::health_data::{
-    acceleration::upload_acceleration_data, 
-    acceleration::get_user_acceleration_data, 
-    heart_rate::upload_heart_rate_data, 
-    heart_rate::get_user_heart_rate_data,
-    blood_oxygen::upload_blood_oxygen_data,
-    blood_oxygen::get_user_blood_oxygen_data,
-    skin_temperature::upload_skin_temperature_data,
-    skin_temperature::get_user_skin_temperature_data,
-    gps_location::upload_gps_location_data,
-    gps_location::get_user_gps_location_data,
-    gps_location::get_health_data_with_gps
-};
-use crate::middleware::auth::Claims;
-use crate::models::sensor_data::{
-    AccelerationDataUpload, 
-    HeartRateDataUpload, 
-    BloodOxygenDataUpload,
-    SkinTemperatureDataUpload,
-    GpsLocationDataUpload,
-    HealthDataTimeQuery
-};
+use actix_web::{get, web, HttpResponse};
+use crate::config::settings::{ApplicationSettings, BATCH_UPLOAD_PATH};
+use crate::handlers::health_data::batch::upload_batch;
+use crate::handlers::health_data::gps_location::get_health_data_with_gps;
+use crate::handlers::health_data::sensor_stream::{get_sensor_data, upload_sensor_data};
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::middleware::upload_body::UploadBody;
+use crate::models::delegation::DataOwnerQuery;
+use crate::models::sensor_data::{BatchUploadRequest, HealthDataTimeQuery};
+use crate::models::sensor_stream::SensorStream;
 use crate::models::sleep::{
     SleepDateQuery, SleepRangeQuery
 };
@@ -33,131 +19,75 @@ use crate::handlers::health_data::sleep::{
     get_weekly_sleep_trends
 };
 
-#[post("/upload_acceleration")]
-async fn upload_acceleration(
-    data: web::Json<AccelerationDataUpload>,
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    upload_acceleration_data(data, pool, claims).await
-}
-
-#[post("/upload_heart_rate")]
-async fn upload_heart_rate(
-    data: web::Json<HeartRateDataUpload>,
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    upload_heart_rate_data(data, pool, claims).await
-}
-
-#[post("/upload_blood_oxygen")]
-async fn upload_blood_oxygen(
-    data: web::Json<BloodOxygenDataUpload>,
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    upload_blood_oxygen_data(data, pool, claims).await
-}
-
-#[get("/acceleration_data")]
-async fn get_acceleration_data(
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    get_user_acceleration_data(pool, claims).await
-}
-
-#[get("/heart_rate_data")]
-async fn get_heart_rate_data(
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    get_user_heart_rate_data(pool, claims).await
+/// Registers the endpoints of a sensor stream: `POST /upload_<data_type>` and
+/// `GET /<data_type>_data`.
+pub fn sensor_stream<S: SensorStream>(cfg: &mut web::ServiceConfig) {
+    cfg.route(&format!("/upload_{}", S::DATA_TYPE), web::post().to(upload_sensor_data::<S>))
+        .route(&format!("/{}_data", S::DATA_TYPE), web::get().to(get_sensor_data::<S>));
 }
 
-#[get("/blood_oxygen_data")]
-async fn get_blood_oxygen_data(
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    get_user_blood_oxygen_data(pool, claims).await
-}
-
-#[post("/upload_skin_temperature")]
-async fn upload_skin_temperature(
-    data: web::Json<SkinTemperatureDataUpload>,
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    upload_skin_temperature_data(data, pool, claims).await
-}
-
-#[get("/skin_temperature_data")]
-async fn get_skin_temperature_data(
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    get_user_skin_temperature_data(pool, claims).await
-}
-
-#[post("/upload_gps_location")]
-async fn upload_gps_location(
-    data: web::Json<GpsLocationDataUpload>,
-    pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
-) -> HttpResponse {
-    upload_gps_location_data(data, pool, claims).await
+/// Registers `POST /batch` at `BATCH_UPLOAD_PATH`, which the API key checks
+/// and payload limits look for.
+pub fn batch_upload(cfg: &mut web::ServiceConfig) {
+    let path = BATCH_UPLOAD_PATH.strip_prefix("/health")
+        .expect("Batch uploads are in the /health scope");
+    cfg.route(path, web::post().to(batch));
 }
 
-#[get("/gps_location_data")]
-async fn get_gps_location_data(
+async fn batch(
+    request: UploadBody<BatchUploadRequest>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    application_settings: web::Data<ApplicationSettings>
 ) -> HttpResponse {
-    get_user_gps_location_data(pool, claims).await
+    upload_batch(request, pool, user, application_settings).await
 }
 
 #[get("/health_data_with_gps")]
 async fn get_health_with_gps(
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>,
-    params: web::Query<HealthDataTimeQuery>
+    user: AuthenticatedUser,
+    params: web::Query<HealthDataTimeQuery>,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
-    get_health_data_with_gps(pool, claims, params).await
+    get_health_data_with_gps(pool, user, params, owner_query).await
 }
 
 #[get("/sleep_data")]
 async fn get_sleep_data(
     query: web::Query<SleepDateQuery>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
-    get_sleep_data_by_date(query, pool, claims).await
+    get_sleep_data_by_date(query, pool, user, owner_query).await
 }
 
 #[get("/sleep_data_range")]
 async fn get_sleep_range(
     query: web::Query<SleepRangeQuery>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
-    get_sleep_data_range(query, pool, claims).await
+    get_sleep_data_range(query, pool, user, owner_query).await
 }
 
 #[get("/sleep_summary")]
 async fn get_sleep_summary(
     query: web::Query<SleepDateQuery>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
-    get_sleep_summary_by_date(query, pool, claims).await
+    get_sleep_summary_by_date(query, pool, user, owner_query).await
 }
 
 #[get("/sleep_trends")]
 async fn get_sleep_trends(
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser,
+    owner_query: web::Query<DataOwnerQuery>
 ) -> HttpResponse {
-    get_weekly_sleep_trends(pool, claims).await
+    get_weekly_sleep_trends(pool, user, owner_query).await
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::oidc::OidcSettings;
+use crate::config::settings::{LoginThrottlingSettings, PasswordHashingSettings};
+use crate::handlers::oidc_handler::{issue_nonce, link_identity, list_identities, login_with_oidc, unlink_identity};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::identity::{LinkIdentityRequest, OidcLoginRequest};
+
+#[post("/login/oidc/nonce")]
+async fn nonce(pool: web::Data<PgPool>) -> HttpResponse {
+    issue_nonce(pool).await
+}
+
+#[post("/login/oidc")]
+async fn login(
+    req: HttpRequest,
+    login_form: web::Json<OidcLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    oidc_settings: web::Data<OidcSettings>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    login_with_oidc(req, login_form, pool, jwt_settings, oidc_settings, password_hashing_settings, login_throttling_settings).await
+}
+
+#[get("/account/identities", wrap = "AuthMiddleware::new()")]
+async fn list(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_identities(pool, user).await
+}
+
+#[post("/account/identities", wrap = "AuthMiddleware::new()")]
+async fn link(
+    link_form: web::Json<LinkIdentityRequest>,
+    pool: web::Data<PgPool>,
+    oidc_settings: web::Data<OidcSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    link_identity(link_form, pool, oidc_settings, user).await
+}
+
+#[delete("/account/identities/{identity_id}", wrap = "AuthMiddleware::new()")]
+async fn unlink(
+    identity_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    unlink_identity(identity_id, pool, user).await
+}
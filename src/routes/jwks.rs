@@ -0,0 +1,9 @@
+use actix_web::{get, web, Responder};
+
+use crate::config::jwt::JwtSettings;
+use crate::handlers::jwks_handler::get_jwks;
+
+#[get("/.well-known/jwks.json")]
+async fn jwks(jwt_settings: web::Data<JwtSettings>) -> impl Responder {
+    get_jwks(jwt_settings).await
+}
@@ -0,0 +1,32 @@
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings};
+use crate::email::Mailer;
+use crate::handlers::magic_link_handler::{login_with_magic_link, request_magic_link};
+use crate::models::auth::{MagicLinkLoginRequest, MagicLinkRequest};
+
+#[post("/login/magic_link/request")]
+async fn request_link(
+    req: HttpRequest,
+    link_form: web::Json<MagicLinkRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    request_magic_link(req, link_form, pool, mailer, application_settings, login_throttling_settings).await
+}
+
+#[post("/login/magic_link")]
+async fn login(
+    req: HttpRequest,
+    login_form: web::Json<MagicLinkLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    login_with_magic_link(req, login_form, pool, jwt_settings, application_settings, login_throttling_settings).await
+}
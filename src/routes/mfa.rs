@@ -0,0 +1,61 @@
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::config::jwt::JwtSettings;
+use crate::config::settings::LoginThrottlingSettings;
+use crate::handlers::mfa_handler::{
+    complete_mfa_login, confirm_totp_enrollment, disable_totp, regenerate_recovery_codes, start_totp_enrollment
+};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::mfa::{MfaCodeRequest, MfaLoginRequest};
+
+#[post("/login/mfa")]
+async fn login_mfa(
+    req: HttpRequest,
+    mfa_form: web::Json<MfaLoginRequest>,
+    pool: web::Data<PgPool>,
+    jwt_settings: web::Data<JwtSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    complete_mfa_login(req, mfa_form, pool, jwt_settings, login_throttling_settings).await
+}
+
+#[post("/mfa/totp/enroll", wrap = "AuthMiddleware::new()")]
+async fn enroll_totp(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    start_totp_enrollment(pool, user).await
+}
+
+#[post("/mfa/totp/confirm", wrap = "AuthMiddleware::new()")]
+async fn confirm_totp(
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    confirm_totp_enrollment(code_form, pool, user).await
+}
+
+#[post("/mfa/totp/disable", wrap = "AuthMiddleware::new()")]
+async fn disable(
+    req: HttpRequest,
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    disable_totp(req, code_form, pool, login_throttling_settings, user).await
+}
+
+#[post("/mfa/recovery_codes", wrap = "AuthMiddleware::new()")]
+async fn recovery_codes(
+    req: HttpRequest,
+    code_form: web::Json<MfaCodeRequest>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    regenerate_recovery_codes(req, code_form, pool, login_throttling_settings, user).await
+}
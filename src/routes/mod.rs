@@ -6,32 +6,97 @@ pub mod auth;
 pub mod protected;
 pub mod health_data;
 pub mod onboarding;
+pub mod password_reset;
+pub mod email_verification;
+pub mod admin;
+pub mod mfa;
+pub mod api_keys;
+pub mod jwks;
+pub mod account;
+pub mod sessions;
+pub mod magic_link;
+pub mod identities;
+pub mod delegations;
+pub mod share_links;
+pub mod studies;
 
 use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::content_format::ContentNegotiation;
+use crate::middleware::role::RequireRole;
+use crate::models::role::Role;
+use crate::models::sensor_stream::{Acceleration, BloodOxygen, GpsLocation, HeartRate, SkinTemperature};
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(registration::register)
         .service(backend_health::backend_health)
-        .service(auth::login);
+        .service(auth::login)
+        .service(auth::refresh)
+        .service(auth::logout)
+        .service(auth::logout_all)
+        .service(magic_link::request_link)
+        .service(magic_link::login)
+        .service(identities::nonce)
+        .service(identities::login)
+        .service(password_reset::request_reset)
+        .service(password_reset::confirm_reset)
+        .service(email_verification::verify_email)
+        .service(email_verification::resend_verification)
+        .service(mfa::login_mfa)
+        .service(mfa::enroll_totp)
+        .service(mfa::confirm_totp)
+        .service(mfa::disable)
+        .service(mfa::recovery_codes)
+        .service(api_keys::create)
+        .service(api_keys::list)
+        .service(api_keys::revoke)
+        .service(jwks::jwks)
+        .service(account::delete_account)
+        .service(account::restore)
+        .service(account::password)
+        .service(account::email)
+        .service(account::username)
+        .service(account::audit)
+        .service(sessions::list)
+        .service(sessions::terminate)
+        .service(identities::list)
+        .service(identities::link)
+        .service(identities::unlink)
+        .service(delegations::create)
+        .service(delegations::granted)
+        .service(delegations::received)
+        .service(delegations::accept)
+        .service(delegations::revoke)
+        .service(share_links::create)
+        .service(share_links::list)
+        .service(share_links::revoke)
+        .service(share_links::view)
+        .service(studies::organizations)
+        .service(studies::add_admin)
+        .service(studies::create)
+        .service(studies::studies)
+        .service(studies::enroll)
+        .service(studies::enrollments)
+        .service(studies::withdraw)
+        .service(studies::participants)
+        .service(studies::aggregate)
+        .service(studies::participant_data);
 
     cfg.service(
         web::scope("/protected")
+            .wrap(AuthMiddleware::new())
             .service(protected::protected_resource)
     );
 
     cfg.service(
         web::scope("/health")
-            .wrap(AuthMiddleware)
-            .service(health_data::upload_acceleration)
-            .service(health_data::get_acceleration_data)
-            .service(health_data::upload_heart_rate)
-            .service(health_data::get_heart_rate_data)
-            .service(health_data::upload_blood_oxygen)
-            .service(health_data::get_blood_oxygen_data)
-            .service(health_data::upload_skin_temperature)
-            .service(health_data::get_skin_temperature_data)
-            .service(health_data::upload_gps_location)
-            .service(health_data::get_gps_location_data)
+            .wrap(AuthMiddleware::new().require_verified_email().allow_api_keys())
+            .wrap(ContentNegotiation)
+            .configure(health_data::sensor_stream::<Acceleration>)
+            .configure(health_data::sensor_stream::<HeartRate>)
+            .configure(health_data::sensor_stream::<BloodOxygen>)
+            .configure(health_data::sensor_stream::<SkinTemperature>)
+            .configure(health_data::sensor_stream::<GpsLocation>)
+            .configure(health_data::batch_upload)
             .service(health_data::get_health_with_gps)
             .service(health_data::get_sleep_data)
             .service(health_data::get_sleep_range)
@@ -40,7 +105,7 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     );
     cfg.service(
         web::scope("/onboarding")
-            .wrap(AuthMiddleware)
+            .wrap(AuthMiddleware::new())
             .service(onboarding::onboarding_status)
             .service(onboarding::submit_basic_info)
             .service(onboarding::get_basic_info)
@@ -51,5 +116,18 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
             .service(onboarding::submit_personalization)
             .service(onboarding::get_personalization)
     );
+    cfg.service(
+        web::scope("/admin")
+            .wrap(RequireRole::new(Role::Admin))
+            // Registered last so it runs first and authenticates the user
+            .wrap(AuthMiddleware::new())
+            .service(admin::lockouts)
+            .service(admin::unlock_user)
+            .service(admin::user_roles)
+            .service(admin::grant_role)
+            .service(admin::revoke_role)
+            .service(admin::audit_events)
+            .service(admin::organizations)
+    );
 
 }
\ No newline at end of file
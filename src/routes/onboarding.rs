@@ -2,7 +2,7 @@ use actix_web::{get, post, web, HttpResponse};
 use sqlx::PgPool;
 
 
-use crate::middleware::auth::Claims;
+use crate::middleware::authenticated_user::AuthenticatedUser;
 use crate::models::onboarding::{
     BasicInfoRequest, 
     LifestyleHealthRequest, 
@@ -21,9 +21,9 @@ use crate::handlers::onboarding::{
 #[get("/status")]
 pub async fn onboarding_status(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    status::get_onboarding_status(pool, claims).await
+    status::get_onboarding_status(pool, user).await
 }
 
 // Basic info submission endpoint
@@ -31,18 +31,18 @@ pub async fn onboarding_status(
 pub async fn submit_basic_info(
     data: web::Json<BasicInfoRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    basic_info::submit_basic_info(data, pool, claims).await
+    basic_info::submit_basic_info(data, pool, user).await
 }
 
 // Get basic info endpoint
 #[get("/basic_info")]
 pub async fn get_basic_info(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    basic_info::get_basic_info(pool, claims).await
+    basic_info::get_basic_info(pool, user).await
 }
 
 // Lifestyle and health submission endpoint
@@ -50,18 +50,18 @@ pub async fn get_basic_info(
 pub async fn submit_lifestyle_health(
     data: web::Json<LifestyleHealthRequest>,
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    lifestyle_health::submit_lifestyle_health(data, pool, claims).await
+    lifestyle_health::submit_lifestyle_health(data, pool, user).await
 }
 
 // Get lifestyle health endpoint
 #[get("/lifestyle_health")]
 pub async fn get_lifestyle_health(
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    lifestyle_health::get_lifestyle_health(pool, claims).await
+    lifestyle_health::get_lifestyle_health(pool, user).await
 }
 
 // Permissions setup submission endpoint
@@ -69,18 +69,18 @@ pub async fn get_lifestyle_health(
 pub async fn submit_permissions_setup(
     data: web::Json<PermissionsSetupRequest>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    permissions::submit_permissions_setup(data, pool, claims).await
+    permissions::submit_permissions_setup(data, pool, user).await
 }
 
 // Get permissions setup endpoint
 #[get("/permissions_setup")]
 pub async fn get_permissions_setup(
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    permissions::get_permissions_setup(pool, claims).await
+    permissions::get_permissions_setup(pool, user).await
 }
 
 // Personalization submission endpoint
@@ -88,16 +88,16 @@ pub async fn get_permissions_setup(
 pub async fn submit_personalization(
     data: web::Json<PersonalizationRequest>,
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    personalization::submit_personalization(data, pool, claims).await
+    personalization::submit_personalization(data, pool, user).await
 }
 
 // Get personalization endpoint
 #[get("/personalization")]
 pub async fn get_personalization(
     pool: web::Data<sqlx::PgPool>,
-    claims: web::ReqData<Claims>
+    user: AuthenticatedUser
 ) -> HttpResponse {
-    personalization::get_personalization(pool, claims).await
+    personalization::get_personalization(pool, user).await
 }
\ No newline at end of file
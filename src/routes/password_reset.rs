@@ -0,0 +1,28 @@
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings, PasswordHashingSettings};
+use crate::email::Mailer;
+use crate::handlers::password_reset_handler::{confirm_password_reset, request_password_reset};
+use crate::models::auth::{PasswordResetConfirmRequest, PasswordResetRequest};
+
+#[post("/password_reset/request")]
+async fn request_reset(
+    req: HttpRequest,
+    reset_form: web::Json<PasswordResetRequest>,
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    request_password_reset(req, reset_form, pool, mailer, application_settings, login_throttling_settings).await
+}
+
+#[post("/password_reset/confirm")]
+async fn confirm_reset(
+    confirm_form: web::Json<PasswordResetConfirmRequest>,
+    pool: web::Data<PgPool>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>
+) -> HttpResponse {
+    confirm_password_reset(confirm_form, pool, password_hashing_settings).await
+}
\ No newline at end of file
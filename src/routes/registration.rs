@@ -1,10 +1,18 @@
 use actix_web::{post, web, HttpResponse};
 use sqlx::PgPool;
 
+use crate::config::settings::{ApplicationSettings, PasswordHashingSettings};
+use crate::email::Mailer;
 use crate::handlers::registration_handler::register_user;
 use crate::models::user::RegistrationRequest;
 
 #[post("/register_user")]
-async fn register(user_form: web::Json<RegistrationRequest>, pool: web::Data<PgPool>) -> HttpResponse {
-    register_user(user_form, pool).await
+async fn register(
+    user_form: web::Json<RegistrationRequest>,
+    pool: web::Data<PgPool>,
+    password_hashing_settings: web::Data<PasswordHashingSettings>,
+    mailer: web::Data<dyn Mailer>,
+    application_settings: web::Data<ApplicationSettings>
+) -> HttpResponse {
+    register_user(user_form, pool, password_hashing_settings, mailer, application_settings).await
 }
\ No newline at end of file
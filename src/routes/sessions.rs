@@ -0,0 +1,24 @@
+use actix_web::{delete, get, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::session_handler::{list_sessions, terminate_session};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+
+#[get("/sessions", wrap = "AuthMiddleware::new()")]
+async fn list(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_sessions(pool, user).await
+}
+
+#[delete("/sessions/{session_id}", wrap = "AuthMiddleware::new()")]
+async fn terminate(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    terminate_session(session_id, pool, user).await
+}
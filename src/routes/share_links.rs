@@ -0,0 +1,47 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::settings::{ApplicationSettings, LoginThrottlingSettings};
+use crate::handlers::share_link_handler::{create_share_link, list_share_links, revoke_share_link, view_share_link};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::share_link::{CreateShareLinkRequest, SharedDataQuery};
+
+#[post("/share_links", wrap = "AuthMiddleware::new()")]
+async fn create(
+    share_form: web::Json<CreateShareLinkRequest>,
+    pool: web::Data<PgPool>,
+    application_settings: web::Data<ApplicationSettings>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    create_share_link(share_form, pool, application_settings, user).await
+}
+
+#[get("/share_links", wrap = "AuthMiddleware::new()")]
+async fn list(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_share_links(pool, user).await
+}
+
+#[delete("/share_links/{share_link_id}", wrap = "AuthMiddleware::new()")]
+async fn revoke(
+    share_link_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    revoke_share_link(share_link_id, pool, user).await
+}
+
+#[get("/shared/{token}")]
+async fn view(
+    req: HttpRequest,
+    token: web::Path<String>,
+    query: web::Query<SharedDataQuery>,
+    pool: web::Data<PgPool>,
+    login_throttling_settings: web::Data<LoginThrottlingSettings>
+) -> HttpResponse {
+    view_share_link(req, token, query, pool, login_throttling_settings).await
+}
@@ -0,0 +1,101 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::handlers::study_handler::{
+    add_organization_admin, create_study, enroll_in_study, get_participant_data, get_study_aggregate,
+    list_enrollments, list_organizations, list_participants, list_studies, withdraw_from_study
+};
+use crate::middleware::auth::AuthMiddleware;
+use crate::middleware::authenticated_user::AuthenticatedUser;
+use crate::models::study::{AddOrganizationAdminRequest, CreateStudyRequest, EnrollRequest};
+
+#[get("/organizations", wrap = "AuthMiddleware::new()")]
+async fn organizations(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_organizations(pool, user).await
+}
+
+#[post("/organizations/{organization_id}/admins", wrap = "AuthMiddleware::new()")]
+async fn add_admin(
+    organization_id: web::Path<Uuid>,
+    admin_form: web::Json<AddOrganizationAdminRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    add_organization_admin(organization_id, admin_form, pool, user).await
+}
+
+#[post("/organizations/{organization_id}/studies", wrap = "AuthMiddleware::new()")]
+async fn create(
+    organization_id: web::Path<Uuid>,
+    study_form: web::Json<CreateStudyRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    create_study(organization_id, study_form, pool, user).await
+}
+
+#[get("/organizations/{organization_id}/studies", wrap = "AuthMiddleware::new()")]
+async fn studies(
+    organization_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_studies(organization_id, pool, user).await
+}
+
+#[post("/studies/enroll", wrap = "AuthMiddleware::new()")]
+async fn enroll(
+    enroll_form: web::Json<EnrollRequest>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    enroll_in_study(enroll_form, pool, user).await
+}
+
+#[get("/studies/enrollments", wrap = "AuthMiddleware::new()")]
+async fn enrollments(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_enrollments(pool, user).await
+}
+
+#[delete("/studies/{study_id}/enrollment", wrap = "AuthMiddleware::new()")]
+async fn withdraw(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    withdraw_from_study(study_id, pool, user).await
+}
+
+#[get("/studies/{study_id}/participants", wrap = "AuthMiddleware::new()")]
+async fn participants(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    list_participants(study_id, pool, user).await
+}
+
+#[get("/studies/{study_id}/aggregate", wrap = "AuthMiddleware::new()")]
+async fn aggregate(
+    study_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    get_study_aggregate(study_id, pool, user).await
+}
+
+#[get("/studies/{study_id}/participants/{user_id}/data", wrap = "AuthMiddleware::new()")]
+async fn participant_data(
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser
+) -> HttpResponse {
+    get_participant_data(path, pool, user).await
+}
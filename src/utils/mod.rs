@@ -1 +1,3 @@
-pub mod password;
\ No newline at end of file
+pub mod password;
+pub mod token;
+pub mod totp;
\ No newline at end of file
@@ -1,9 +1,67 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
+use argon2::{Algorithm, Argon2, Params, Version};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
 
-pub fn hash_password(password: &str) -> String {
-    hash(password, DEFAULT_COST).expect("Failed to hash password")
+use crate::config::settings::PasswordHashingSettings;
+
+/// Checks a new password against the password policy.
+pub fn validate_password(password: &str) -> Result<(), &'static str> {
+    if password.is_empty() {
+        return Err("Password cannot be empty");
+    }
+    if password.len() < 8 {
+        return Err("Password must be at least 8 characters long");
+    }
+    Ok(())
+}
+
+fn argon2_hasher(settings: &PasswordHashingSettings) -> Argon2<'static> {
+    let params = Params::new(
+        settings.memory_kib,
+        settings.iterations,
+        settings.parallelism,
+        None
+    ).expect("Invalid password hashing parameters");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Hashes a password with Argon2id using the configured cost parameters.
+pub fn hash_password(password: &str, settings: &PasswordHashingSettings) -> String {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    argon2_hasher(settings)
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash password")
+        .to_string()
 }
 
+/// Verifies a password against an Argon2 hash or a legacy bcrypt hash.
 pub fn verify_password(password: &str, hash: &str) -> bool {
-    verify(password, hash).unwrap_or(false)
-}
\ No newline at end of file
+    match PasswordHash::new(hash) {
+        // The cost parameters are read from the PHC string itself
+        Ok(parsed) if parsed.algorithm.as_str().starts_with("argon2") => {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        }
+        _ => bcrypt::verify(password, hash).unwrap_or(false),
+    }
+}
+
+/// Returns true if the hash isn't Argon2id with the configured parameters,
+/// i.e. it should be replaced after the next successful verification.
+pub fn needs_rehash(hash: &str, settings: &PasswordHashingSettings) -> bool {
+    let parsed = match PasswordHash::new(hash) {
+        Ok(parsed) => parsed,
+        Err(_) => return true,
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != settings.memory_kib
+                || params.t_cost() != settings.iterations
+                || params.p_cost() != settings.parallelism
+        }
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,25 @@
+use rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Generates a random opaque token (256 bits, hex encoded).
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hashes a token for storage so that a database leak doesn't expose usable tokens.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Without look-alike characters such as 0/O and 1/I, for codes typed in by hand
+const INVITE_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Generates a short invite code that is easy to read out and type.
+pub fn generate_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..10)
+        .map(|_| INVITE_CODE_ALPHABET[rng.gen_range(0..INVITE_CODE_ALPHABET.len())] as char)
+        .collect()
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{heart_rate_upload, spawn_app, spawn_app_with, TestApp};
+
+async fn delete_account(client: &Client, test_app: &TestApp, token: &str, password: &str) -> reqwest::Response {
+    client
+        .delete(format!("{}/account", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "password": password }))
+        .send()
+        .await
+        .expect("Failed to execute deletion request.")
+}
+
+async fn login(client: &Client, test_app: &TestApp, user_id: Uuid) -> String {
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    let login_json = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": "password123"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    login_json["token"].as_str().unwrap().to_string()
+}
+
+async fn user_exists(test_app: &TestApp, user_id: Uuid) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn deleting_the_account_requires_the_password() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let response = delete_account(&client, &test_app, &token, "wrong-password").await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let scheduled: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT deletion_scheduled_for FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert!(scheduled.is_none(), "Nothing should be scheduled");
+}
+
+#[tokio::test]
+async fn deleted_account_can_be_restored_during_the_grace_period() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Make sure the token was issued strictly before the revocation cutoff
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // Act
+    let response = delete_account(&client, &test_app, &token, "password123").await;
+
+    // Assert
+    assert_eq!(202, response.status().as_u16());
+    let deletion_json = response.json::<serde_json::Value>().await.unwrap();
+    let scheduled_for: DateTime<Utc> = deletion_json["scheduled_for"].as_str().unwrap().parse().unwrap();
+    assert!(scheduled_for > Utc::now() + Duration::days(29), "Deletion should wait for the grace period");
+    assert!(deletion_json["receipt_id"].is_string());
+
+    let response = client
+        .post(format!("{}/account/restore", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16(), "Existing sessions should be signed out");
+
+    let new_token = login(&client, &test_app, user_id).await;
+    let response = client
+        .post(format!("{}/account/restore", &test_app.address))
+        .header("Authorization", format!("Bearer {}", new_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let second_restore = client
+        .post(format!("{}/account/restore", &test_app.address))
+        .header("Authorization", format!("Bearer {}", new_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, second_restore.status().as_u16(), "Nothing left to restore");
+}
+
+#[tokio::test]
+async fn account_is_erased_after_the_grace_period() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.account_deletion.grace_period_days = 0;
+        config.account_deletion.purge_interval_seconds = 1;
+    }).await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+    let upload = client
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&heart_rate_upload(72))
+        .send()
+        .await
+        .expect("Failed to execute upload request.");
+    assert_eq!(200, upload.status().as_u16());
+
+    // Act
+    let response = delete_account(&client, &test_app, &token, "password123").await;
+    let receipt_id: Uuid = response.json::<serde_json::Value>().await.unwrap()["receipt_id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    for _ in 0..50 {
+        if !user_exists(&test_app, user_id).await {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    // Assert
+    assert!(!user_exists(&test_app, user_id).await, "Account should be erased");
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM health_data WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(0, remaining);
+    let (health_data_records,): (i64,) = sqlx::query_as(
+        "SELECT health_data_records FROM account_deletion_receipts WHERE id = $1"
+    )
+    .bind(receipt_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("A deletion receipt should be recorded");
+    assert_eq!(1, health_data_records);
+}
+
+#[tokio::test]
+async fn accounts_are_not_erased_before_the_grace_period_ends() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.account_deletion.purge_interval_seconds = 1;
+    }).await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let response = delete_account(&client, &test_app, &token, "password123").await;
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+    // Assert
+    assert_eq!(202, response.status().as_u16());
+    assert!(user_exists(&test_app, user_id).await);
+    let second = delete_account(&client, &test_app, &login(&client, &test_app, user_id).await, "password123").await;
+    assert_eq!(409, second.status().as_u16());
+}
+
+#[tokio::test]
+async fn wrong_passwords_for_deleting_the_account_are_throttled() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.login_throttling.max_failed_attempts_per_account = 3;
+    }).await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    for _ in 0..3 {
+        assert_eq!(400, delete_account(&client, &test_app, &token, "wrong-password").await.status().as_u16());
+    }
+    let response = delete_account(&client, &test_app, &token, "password123").await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+    let scheduled: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT deletion_scheduled_for FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert!(scheduled.is_none(), "Nothing should be scheduled");
+}
@@ -0,0 +1,183 @@
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{extract_link_param, spawn_app, spawn_app_with, TestApp};
+
+async fn put_account(client: &Client, test_app: &TestApp, path: &str, token: &str, body: serde_json::Value) -> reqwest::Response {
+    client
+        .put(format!("{}/account/{}", &test_app.address, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn changing_the_password_revokes_other_sessions() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+
+    // Make sure the token was issued strictly before the revocation cutoff
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // Act
+    let wrong_password = put_account(&client, &test_app, "password", token, json!({
+        "current_password": "wrong-password",
+        "new_password": "new-password456"
+    })).await;
+    let response = put_account(&client, &test_app, "password", token, json!({
+        "current_password": "password123",
+        "new_password": "new-password456"
+    })).await;
+
+    // Assert
+    assert_eq!(400, wrong_password.status().as_u16());
+    assert_eq!(200, response.status().as_u16());
+    let new_session = response.json::<serde_json::Value>().await.unwrap();
+    let new_token = new_session["token"].as_str().unwrap();
+
+    let old_session = put_account(&client, &test_app, "username", token, json!({ "username": "unused" })).await;
+    assert_eq!(401, old_session.status().as_u16(), "The old access token should be revoked");
+    let old_refresh = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": login_json["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(401, old_refresh.status().as_u16(), "The old refresh token should be revoked");
+    let current_session = client
+        .get(format!("{}/health/heart_rate_data", &test_app.address))
+        .header("Authorization", format!("Bearer {}", new_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, current_session.status().as_u16(), "The returned session should work");
+
+    assert_eq!(401, test_app.login(&username, "password123").await.status().as_u16());
+    assert_eq!(200, test_app.login(&username, "new-password456").await.status().as_u16());
+}
+
+#[tokio::test]
+async fn new_email_address_is_used_once_verified() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let new_email = format!("new-{}@example.com", username);
+
+    // Act
+    let response = put_account(&client, &test_app, "email", token, json!({
+        "email": new_email,
+        "password": "password123"
+    })).await;
+
+    // Assert
+    assert_eq!(202, response.status().as_u16());
+    let current_email = || async {
+        sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE username = $1")
+            .bind(&username)
+            .fetch_one(&test_app.db_pool)
+            .await
+            .unwrap()
+    };
+    assert_eq!(format!("{}@example.com", username), current_email().await, "The address changes only once verified");
+
+    let email = test_app.last_email_to(&new_email);
+    let token = extract_link_param(email["body"].as_str().unwrap(), "token");
+    let verify_response = client
+        .get(format!("{}/verify_email?token={}", &test_app.address, token))
+        .send()
+        .await
+        .expect("Failed to execute verification request.");
+    assert_eq!(200, verify_response.status().as_u16());
+    assert_eq!(new_email, current_email().await);
+}
+
+#[tokio::test]
+async fn email_and_username_of_other_users_are_rejected() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let (other_username, _) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+
+    // Act
+    let taken_email = put_account(&client, &test_app, "email", token, json!({
+        "email": format!("{}@example.com", other_username),
+        "password": "password123"
+    })).await;
+    let wrong_password = put_account(&client, &test_app, "email", token, json!({
+        "email": format!("new-{}@example.com", username),
+        "password": "wrong-password"
+    })).await;
+    let taken_username = put_account(&client, &test_app, "username", token, json!({
+        "username": other_username
+    })).await;
+
+    // Assert
+    assert_eq!(400, taken_email.status().as_u16());
+    assert_eq!(400, wrong_password.status().as_u16());
+    assert_eq!(400, taken_username.status().as_u16());
+    assert!(test_app.sent_emails().iter().all(|email| email["to"] != format!("new-{}@example.com", username)));
+}
+
+#[tokio::test]
+async fn username_can_be_changed_with_the_registration_rules() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let new_username = format!("renamed{}", Uuid::new_v4());
+
+    // Act
+    let too_short = put_account(&client, &test_app, "username", token, json!({ "username": "ab" })).await;
+    let response = put_account(&client, &test_app, "username", token, json!({ "username": new_username })).await;
+
+    // Assert
+    assert_eq!(400, too_short.status().as_u16());
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(200, test_app.login(&new_username, "password123").await.status().as_u16());
+}
+
+#[tokio::test]
+async fn wrong_current_passwords_are_throttled_like_logins() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.login_throttling.max_failed_attempts_per_account = 3;
+    }).await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+
+    // Act
+    for _ in 0..2 {
+        let response = put_account(&client, &test_app, "password", token, json!({
+            "current_password": "wrong-password",
+            "new_password": "new-password456"
+        })).await;
+        assert_eq!(400, response.status().as_u16());
+    }
+    let response = put_account(&client, &test_app, "email", token, json!({
+        "email": format!("new-{}@example.com", username),
+        "password": "wrong-password"
+    })).await;
+    assert_eq!(400, response.status().as_u16());
+    let response = put_account(&client, &test_app, "password", token, json!({
+        "current_password": "password123",
+        "new_password": "new-password456"
+    })).await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16(), "Even the correct password should be throttled");
+    assert!(response.headers().contains_key("Retry-After"));
+    assert_eq!(429, test_app.login(&username, "password123").await.status().as_u16());
+}
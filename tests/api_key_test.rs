@@ -0,0 +1,149 @@
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{heart_rate_upload, sensor_upload, spawn_app, TestApp};
+
+fn blood_oxygen_upload() -> serde_json::Value {
+    sensor_upload("blood_oxygen", json!([{"timestamp": "2025-03-10T12:00:00Z", "spo2": 98.5, "confidence": 0.95}]))
+}
+
+async fn create_api_key(client: &Client, test_app: &TestApp, token: &str, scopes: &[&str]) -> serde_json::Value {
+    let response = client
+        .post(format!("{}/api_keys", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Bedroom gateway",
+            "scopes": scopes
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16(), "API key creation should succeed");
+
+    response.json::<serde_json::Value>().await
+        .expect("Failed to parse API key as JSON")
+}
+
+async fn upload_with_key(client: &Client, test_app: &TestApp, path: &str, key: &str, body: &serde_json::Value) -> u16 {
+    client
+        .post(format!("{}/health/{}", &test_app.address, path))
+        .header("Authorization", format!("ApiKey {}", key))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute upload request.")
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn api_key_can_upload_data_within_its_scopes() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+    let api_key = create_api_key(&client, &test_app, &token, &["heart_rate"]).await;
+    let key = api_key["key"].as_str().unwrap();
+
+    // Act
+    let status = upload_with_key(&client, &test_app, "upload_heart_rate", key, &heart_rate_upload(72)).await;
+
+    // Assert
+    assert_eq!(200, status, "Upload with API key should succeed");
+    let saved = sqlx::query!(
+        "SELECT COUNT(*) as \"count!\" FROM health_data WHERE user_id = $1 AND data_type = 'heart_rate'",
+        user_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch saved data.");
+    assert_eq!(1, saved.count, "Data should be stored for the key's owner");
+
+    let keys = client
+        .get(format!("{}/api_keys", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert!(keys[0]["last_used_at"].is_string(), "Last use should be recorded");
+    assert!(keys[0].get("key").is_none(), "The key itself should never be listed");
+}
+
+#[tokio::test]
+async fn api_key_is_rejected_outside_its_scopes() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    let api_key = create_api_key(&client, &test_app, &token, &["heart_rate"]).await;
+    let key = api_key["key"].as_str().unwrap();
+
+    // Act
+    let other_upload = upload_with_key(&client, &test_app, "upload_blood_oxygen", key, &blood_oxygen_upload()).await;
+    let read = client
+        .get(format!("{}/health/heart_rate_data", &test_app.address))
+        .header("Authorization", format!("ApiKey {}", key))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let outside_health = client
+        .get(format!("{}/api_keys", &test_app.address))
+        .header("Authorization", format!("ApiKey {}", key))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, other_upload);
+    assert_eq!(403, read.status().as_u16(), "API keys can only upload");
+    assert_eq!(401, outside_health.status().as_u16(), "API keys are only accepted for health uploads");
+}
+
+#[tokio::test]
+async fn revoked_api_key_is_rejected() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    let api_key = create_api_key(&client, &test_app, &token, &["heart_rate"]).await;
+    let key = api_key["key"].as_str().unwrap();
+
+    // Act
+    let response = client
+        .delete(format!("{}/api_keys/{}", &test_app.address, api_key["id"].as_str().unwrap()))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(401, upload_with_key(&client, &test_app, "upload_heart_rate", key, &heart_rate_upload(72)).await);
+}
+
+#[tokio::test]
+async fn creating_an_api_key_with_unknown_scope_returns_400() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let response = client
+        .post(format!("{}/api_keys", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Bedroom gateway",
+            "scopes": ["heart_rate", "everything"]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,280 @@
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, spawn_app_with, TestApp};
+
+async fn account_audit(client: &Client, test_app: &TestApp, token: &str, query: &str) -> Vec<serde_json::Value> {
+    let response = client
+        .get(format!("{}/account/audit{}", &test_app.address, query))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    response.json::<Vec<serde_json::Value>>().await.unwrap()
+}
+
+fn event_types(events: &[serde_json::Value]) -> Vec<&str> {
+    events.iter().map(|event| event["event_type"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn logins_and_refreshes_show_up_in_the_users_audit_log() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let (_, other_login_json) = test_app.register_and_login().await;
+    let failed_login = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({ "username": username, "password": "wrong-password" }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    assert_eq!(401, failed_login.status().as_u16());
+    let refresh_response = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": login_json["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(200, refresh_response.status().as_u16());
+    let token = refresh_response.json::<serde_json::Value>().await.unwrap()["token"].as_str().unwrap().to_string();
+
+    // Act
+    let events = account_audit(&client, &test_app, &token, "").await;
+    let failures = account_audit(&client, &test_app, &token, "?event_type=login_failed").await;
+    let other_events = account_audit(&client, &test_app, other_login_json["token"].as_str().unwrap(), "").await;
+
+    // Assert
+    assert_eq!(vec!["token_refreshed", "login_failed", "login_succeeded"], event_types(&events), "Newest first");
+    assert!(events.iter().all(|event| event["ip_address"].is_string()));
+    assert_eq!(events[2]["actor_id"], events[2]["user_id"]);
+    assert_eq!(1, failures.len());
+    assert!(failures[0]["actor_id"].is_null(), "Failed guesses aren't attributed to the account owner");
+    assert!(failures[0]["details"].is_null(), "The username of a known account isn't repeated");
+    assert_eq!(vec!["login_succeeded"], event_types(&other_events), "Users only see their own events");
+}
+
+#[tokio::test]
+async fn permission_changes_and_deletion_requests_are_recorded() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let permissions_response = client
+        .post(format!("{}/onboarding/permissions_setup", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "heart_rate_enabled": true,
+            "temperature_enabled": false,
+            "spo2_enabled": true,
+            "accelerometer_enabled": false,
+            "notifications_enabled": true,
+            "background_usage_enabled": false,
+            "third_party_connections": [
+                { "connection_type": "apple_health", "connection_data": null }
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, permissions_response.status().as_u16());
+    let delete_response = client
+        .delete(format!("{}/account", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "password": "password123" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(202, delete_response.status().as_u16());
+    let receipt_id = delete_response.json::<serde_json::Value>().await.unwrap()["receipt_id"].clone();
+    let user_id = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM audit_events WHERE event_type = 'permissions_changed' ORDER BY created_at DESC LIMIT 1")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let response = client
+        .get(format!("{}/admin/audit?user_id={}", &test_app.address, user_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let events = response.json::<Vec<serde_json::Value>>().await.unwrap();
+    assert_eq!(vec!["account_deletion_requested", "permissions_changed", "login_succeeded"], event_types(&events));
+    assert_eq!(receipt_id, events[0]["details"]["receipt_id"]);
+    assert_eq!(false, events[1]["details"]["temperature_enabled"]);
+    assert_eq!(json!(["apple_health"]), events[1]["details"]["third_party_connections"]);
+}
+
+#[tokio::test]
+async fn rejected_permission_changes_are_neither_applied_nor_recorded() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+    let permissions = || async {
+        sqlx::query_as::<_, (bool, bool)>("SELECT heart_rate_enabled, spo2_enabled FROM permissions_settings WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&test_app.db_pool)
+            .await
+            .unwrap()
+    };
+    let before = permissions().await;
+
+    // Act: the unknown connection type is only noticed after the settings were written
+    let response = client
+        .post(format!("{}/onboarding/permissions_setup", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "heart_rate_enabled": true,
+            "temperature_enabled": false,
+            "spo2_enabled": true,
+            "accelerometer_enabled": false,
+            "notifications_enabled": true,
+            "background_usage_enabled": false,
+            "third_party_connections": [
+                { "connection_type": "unknown_service", "connection_data": null }
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(before, permissions().await);
+    let events = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM audit_events WHERE event_type = 'permissions_changed'")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(0, events);
+}
+
+#[tokio::test]
+async fn audit_search_is_limited_to_admins_and_validates_filters() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let (admin_id, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let unknown_username = format!("nobody{}", Uuid::new_v4());
+    let failed_login = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({ "username": unknown_username, "password": "password123" }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    assert_eq!(401, failed_login.status().as_u16());
+
+    // Act
+    let as_user = client
+        .get(format!("{}/admin/audit", &test_app.address))
+        .header("Authorization", format!("Bearer {}", login_json["token"].as_str().unwrap()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let unknown_type = client
+        .get(format!("{}/admin/audit?event_type=coffee_break", &test_app.address))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let too_many = client
+        .get(format!("{}/admin/audit?limit=5000", &test_app.address))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let admin_logins = client
+        .get(format!("{}/admin/audit?actor_id={}&event_type=login_succeeded", &test_app.address, admin_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let failures = client
+        .get(format!("{}/admin/audit?event_type=login_failed&limit=1", &test_app.address))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(403, as_user.status().as_u16());
+    assert_eq!(400, unknown_type.status().as_u16());
+    assert_eq!(400, too_many.status().as_u16());
+    assert_eq!(1, admin_logins.len());
+    assert_eq!(admin_id.to_string(), admin_logins[0]["user_id"]);
+    assert_eq!(1, failures.len());
+    assert!(failures[0]["actor_id"].is_null(), "Failed guesses aren't attributed to the account owner");
+    assert!(failures[0]["user_id"].is_null());
+    assert_eq!(unknown_username, failures[0]["details"]["username"]);
+}
+
+#[tokio::test]
+async fn audit_events_are_append_only_and_outlive_erased_accounts() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.account_deletion.grace_period_days = 0;
+        config.account_deletion.purge_interval_seconds = 1;
+    }).await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let update = sqlx::query("UPDATE audit_events SET ip_address = NULL WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&test_app.db_pool)
+        .await;
+    let delete = sqlx::query("DELETE FROM audit_events WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&test_app.db_pool)
+        .await;
+    let delete_response = client
+        .delete(format!("{}/account", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "password": "password123" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(202, delete_response.status().as_u16());
+    let mut erased = false;
+    for _ in 0..50 {
+        let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_events WHERE user_id = $1 AND event_type = 'account_erased'")
+            .bind(user_id)
+            .fetch_one(&test_app.db_pool)
+            .await
+            .unwrap();
+        if events > 0 {
+            erased = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    // Assert
+    assert!(update.is_err(), "Events can't be changed");
+    assert!(delete.is_err(), "Events can't be deleted");
+    assert!(erased, "The erasure should be recorded");
+    let remaining: Vec<String> = sqlx::query_scalar("SELECT event_type FROM audit_events WHERE user_id = $1 ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(vec!["login_succeeded", "account_deletion_requested", "account_erased"], remaining);
+}
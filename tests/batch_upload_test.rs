@@ -0,0 +1,128 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+
+mod common;
+use common::utils::{heart_rate_upload, sensor_upload, spawn_app, TestApp};
+
+fn blood_oxygen_upload() -> Value {
+    sensor_upload("blood_oxygen", json!([{"timestamp": "2025-03-10T12:00:00Z", "spo2": 98.5}]))
+}
+
+fn gps_location_upload() -> Value {
+    sensor_upload("gps_location", json!([{"timestamp": "2025-03-10T12:00:00Z", "latitude": 47.37, "longitude": 8.54}]))
+}
+
+fn invalid_skin_temperature_upload() -> Value {
+    sensor_upload("skin_temperature", json!([{"timestamp": "2025-03-10T12:00:00Z", "temperature": 80.0}]))
+}
+
+async fn post_batch(test_app: &TestApp, authorization: &str, body: &Value) -> (u16, Value) {
+    let response = Client::new()
+        .post(format!("{}/health/batch", &test_app.address))
+        .header("Authorization", authorization)
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute batch request.");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("Failed to parse batch response as JSON"))
+}
+
+async fn stored_data_types(test_app: &TestApp) -> Vec<String> {
+    sqlx::query_scalar::<_, String>("SELECT data_type FROM health_data ORDER BY data_type")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn batch_stores_uploads_of_different_streams() {
+    let test_app = spawn_app().await;
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (status, body) = post_batch(&test_app, &format!("Bearer {}", token), &json!({
+        "uploads": [heart_rate_upload(72), blood_oxygen_upload(), gps_location_upload()]
+    })).await;
+
+    assert_eq!(200, status);
+    assert_eq!(body["status"], "success");
+    assert_eq!(body["stored"], 3);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[1]["index"], 1);
+    assert_eq!(results[1]["data_type"], "blood_oxygen");
+    assert!(results.iter().all(|item| item["status"] == "success" && item["id"].is_string()));
+
+    let owners = sqlx::query_scalar::<_, uuid::Uuid>("SELECT DISTINCT user_id FROM health_data")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(vec![user_id], owners);
+    assert_eq!(vec!["blood_oxygen", "gps_location", "heart_rate"], stored_data_types(&test_app).await);
+}
+
+#[tokio::test]
+async fn all_or_nothing_batch_with_a_rejected_upload_stores_nothing() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (status, body) = post_batch(&test_app, &format!("Bearer {}", token), &json!({
+        "uploads": [heart_rate_upload(72), invalid_skin_temperature_upload(), {"data_type": "steps"}]
+    })).await;
+
+    assert_eq!(400, status);
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["stored"], 0);
+    assert_eq!(body["results"][0]["status"], "not_stored");
+    assert_eq!(body["results"][1]["status"], "error");
+    assert_eq!(body["results"][2]["message"], "Unknown data type 'steps'");
+    assert!(stored_data_types(&test_app).await.is_empty());
+}
+
+#[tokio::test]
+async fn best_effort_batch_stores_the_valid_uploads() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (status, body) = post_batch(&test_app, &format!("Bearer {}", token), &json!({
+        "mode": "best_effort",
+        "uploads": [heart_rate_upload(72), invalid_skin_temperature_upload(), gps_location_upload()]
+    })).await;
+
+    assert_eq!(200, status);
+    assert_eq!(body["status"], "partial");
+    assert_eq!(body["stored"], 2);
+    assert_eq!(body["results"][1]["status"], "error");
+    assert!(body["results"][1]["message"].as_str().unwrap().contains("Temperature"));
+    assert_eq!(vec!["gps_location", "heart_rate"], stored_data_types(&test_app).await);
+}
+
+#[tokio::test]
+async fn api_key_batch_rejects_uploads_outside_its_scopes() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let api_key = Client::new()
+        .post(format!("{}/api_keys", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Bedroom gateway",
+            "scopes": ["heart_rate"]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Value>()
+        .await
+        .unwrap();
+    let key = api_key["key"].as_str().unwrap();
+
+    let (status, body) = post_batch(&test_app, &format!("ApiKey {}", key), &json!({
+        "mode": "best_effort",
+        "uploads": [heart_rate_upload(72), blood_oxygen_upload()]
+    })).await;
+
+    assert_eq!(200, status);
+    assert_eq!(body["results"][0]["status"], "success");
+    assert_eq!(body["results"][1]["status"], "error");
+    assert_eq!(vec!["heart_rate"], stored_data_types(&test_app).await);
+}
@@ -0,0 +1,165 @@
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+mod common;
+use common::utils::{heart_rate_upload, sensor_upload, spawn_app, TestApp};
+
+const CBOR: &str = "application/cbor";
+const MSGPACK: &str = "application/msgpack";
+
+/// The shared heart rate fixture with a second sample and metadata, so every
+/// field kind goes through the binary codecs.
+fn detailed_heart_rate_upload() -> Value {
+    let mut upload = heart_rate_upload(72);
+    upload["samples"].as_array_mut().unwrap().push(
+        json!({"timestamp": "2025-03-10T12:00:01Z", "heart_rate": 73, "confidence": 0.5})
+    );
+    upload["metadata"] = json!({"activity": "resting"});
+    upload
+}
+
+fn encode<T: Serialize>(content_type: &str, value: &T) -> Vec<u8> {
+    match content_type {
+        CBOR => {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(value, &mut bytes).unwrap();
+            bytes
+        }
+        MSGPACK => rmp_serde::to_vec_named(value).unwrap(),
+        _ => serde_json::to_vec(value).unwrap(),
+    }
+}
+
+fn decode(content_type: &str, bytes: &[u8]) -> Value {
+    match content_type {
+        CBOR => ciborium::from_reader(bytes).unwrap(),
+        MSGPACK => rmp_serde::from_slice(bytes).unwrap(),
+        _ => serde_json::from_slice(bytes).unwrap(),
+    }
+}
+
+async fn post(test_app: &TestApp, token: &str, path: &str, content_type: &str, body: &Value) -> (u16, Value) {
+    let response = Client::new()
+        .post(format!("{}{}", &test_app.address, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", content_type)
+        .header("Accept", content_type)
+        .body(encode(content_type, body))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status().as_u16();
+    let response_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(response_type.starts_with(content_type), "Expected {} response, got {}", content_type, response_type);
+    (status, decode(content_type, &response.bytes().await.unwrap()))
+}
+
+async fn get(test_app: &TestApp, token: &str, path: &str, accept: &str) -> (String, Vec<u8>) {
+    let response = Client::new()
+        .get(format!("{}{}", &test_app.address, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Accept", accept)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    (content_type, response.bytes().await.unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn uploads_in_all_formats_store_the_same_data() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    for content_type in ["application/json", CBOR, MSGPACK] {
+        let (status, body) = post(&test_app, &token, "/health/upload_heart_rate", content_type, &detailed_heart_rate_upload()).await;
+        assert_eq!(200, status, "{} upload should succeed", content_type);
+        assert_eq!(body["status"], "success");
+    }
+
+    let stored = sqlx::query_as::<_, (Value, Value, i32)>(
+        "SELECT device_info, data, sampling_rate_hz FROM health_data ORDER BY created_at"
+    )
+    .fetch_all(&test_app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(3, stored.len());
+    assert_eq!(stored[0], stored[1]);
+    assert_eq!(stored[0], stored[2]);
+}
+
+#[tokio::test]
+async fn read_endpoints_answer_in_the_accepted_format() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    post(&test_app, &token, "/health/upload_heart_rate", "application/json", &detailed_heart_rate_upload()).await;
+
+    let (_, json_body) = get(&test_app, &token, "/health/heart_rate_data", "application/json").await;
+    let expected: Value = serde_json::from_slice(&json_body).unwrap();
+    assert_eq!(expected["count"], 1);
+
+    for accept in [CBOR, MSGPACK] {
+        let (content_type, body) = get(&test_app, &token, "/health/heart_rate_data", accept).await;
+        assert_eq!(accept, content_type);
+        assert_eq!(expected, decode(accept, &body));
+        assert!(body.len() < json_body.len(), "{} should be smaller than JSON", accept);
+    }
+
+    // Quality values decide, unsupported types are ignored
+    let (content_type, _) = get(&test_app, &token, "/health/heart_rate_data", "text/html, application/cbor;q=0.5, application/msgpack;q=0.8").await;
+    assert_eq!(MSGPACK, content_type);
+    let (content_type, _) = get(&test_app, &token, "/health/heart_rate_data", "text/html").await;
+    assert!(content_type.starts_with("application/json"));
+}
+
+#[tokio::test]
+async fn batch_upload_accepts_message_pack() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let gps_upload = sensor_upload("gps_location", json!([
+        {"timestamp": "2025-03-10T12:00:00Z", "latitude": 47.37, "longitude": 8.54}
+    ]));
+    let (status, body) = post(&test_app, &token, "/health/batch", MSGPACK, &json!({
+        "uploads": [detailed_heart_rate_upload(), gps_upload]
+    })).await;
+
+    assert_eq!(200, status);
+    assert_eq!(body["stored"], 2);
+    assert_eq!(body["results"][1]["data_type"], "gps_location");
+}
+
+#[tokio::test]
+async fn invalid_binary_uploads_are_rejected() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Validation errors come back in the request's format
+    let mut upload = detailed_heart_rate_upload();
+    upload["samples"][0]["heart_rate"] = json!(500);
+    let (status, body) = post(&test_app, &token, "/health/upload_heart_rate", CBOR, &upload).await;
+    assert_eq!(400, status);
+    assert_eq!(body["status"], "error");
+
+    let response = Client::new()
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", MSGPACK)
+        .body(serde_json::to_vec(&detailed_heart_rate_upload()).unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    let response = Client::new()
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/xml")
+        .body("<upload/>")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(415, response.status().as_u16());
+}
@@ -1 +1,4 @@
+// Not every test binary uses every helper
+#![allow(dead_code)]
+
 pub mod utils;
\ No newline at end of file
@@ -1,11 +1,12 @@
 use secrecy::ExposeSecret;
 use sqlx::{PgPool, PgConnection, Connection, Executor};
 use std::net::TcpListener;
+use std::path::PathBuf;
 use uuid::Uuid;
 use once_cell::sync::Lazy;
 
 use areum_backend::run;
-use areum_backend::config::settings::{get_config, DatabaseSettings, get_jwt_settings};
+use areum_backend::config::settings::{get_config, DatabaseSettings, Settings};
 use areum_backend::telemetry::{get_subscriber, init_subscriber};
 
 // Ensure that the `tracing` stack is only initialised once using `once_cell`
@@ -32,10 +33,196 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 
 pub struct TestApp{
     pub address: String,
-    pub db_pool: PgPool
+    pub db_pool: PgPool,
+    pub outbox_dir: PathBuf
+}
+
+impl TestApp {
+    /// Returns all emails written to this app's outbox, oldest first.
+    pub fn sent_emails(&self) -> Vec<serde_json::Value> {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(&self.outbox_dir) {
+            Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+            Err(_) => return Vec::new(),
+        };
+        paths.sort();
+        paths.iter()
+            .map(|path| {
+                let content = std::fs::read(path).expect("Failed to read email.");
+                serde_json::from_slice(&content).expect("Failed to parse email.")
+            })
+            .collect()
+    }
+
+    /// Returns the most recent email sent to the given address.
+    pub fn last_email_to(&self, recipient: &str) -> serde_json::Value {
+        self.sent_emails()
+            .into_iter()
+            .rev()
+            .find(|email| email["to"] == recipient)
+            .unwrap_or_else(|| panic!("No email sent to {}", recipient))
+    }
+
+    /// Registers a new user with the password `password123`. Returns the
+    /// username and the email address.
+    pub async fn register_user(&self) -> (String, String) {
+        self.register_user_named(&format!("user{}", Uuid::new_v4())).await
+    }
+
+    async fn register_user_named(&self, username: &str) -> (String, String) {
+        let email = format!("{}@example.com", username);
+        let response = reqwest::Client::new()
+            .post(format!("{}/register_user", &self.address))
+            .json(&serde_json::json!({
+                "username": username,
+                "password": "password123",
+                "email": email
+            }))
+            .send()
+            .await
+            .expect("Failed to execute registration request.");
+        assert_eq!(200, response.status().as_u16(), "Registration should succeed");
+
+        (username.to_string(), email)
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/login", &self.address))
+            .json(&serde_json::json!({
+                "username": username,
+                "password": password
+            }))
+            .send()
+            .await
+            .expect("Failed to execute login request.")
+    }
+
+    /// Logs in a user registered by `register_user` and returns the login response.
+    pub async fn login_as(&self, username: &str) -> serde_json::Value {
+        let response = self.login(username, "password123").await;
+        assert_eq!(200, response.status().as_u16(), "Login should succeed");
+
+        response.json::<serde_json::Value>().await
+            .expect("Failed to parse login response as JSON")
+    }
+
+    /// Registers a new user and logs in. Returns the username and the login response.
+    pub async fn register_and_login(&self) -> (String, serde_json::Value) {
+        let (username, _) = self.register_user().await;
+        let login_json = self.login_as(&username).await;
+        (username, login_json)
+    }
+
+    /// The status of a health data request with the token, to check whether it is accepted.
+    pub async fn get_heart_rate_status(&self, token: &str) -> u16 {
+        reqwest::Client::new()
+            .get(format!("{}/health/heart_rate_data", &self.address))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .status()
+            .as_u16()
+    }
+
+    pub async fn username_of(&self, user_id: Uuid) -> String {
+        sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.db_pool)
+            .await
+            .expect("Failed to fetch username.")
+    }
+
+    /// Registers a new user, grants the roles directly in the database and logs in.
+    /// Returns the user id and an access token carrying the roles.
+    pub async fn create_user_with_roles(&self, roles: &[&str]) -> (Uuid, String) {
+        let (username, _) = self.register_user_named(&format!("roleuser{}", Uuid::new_v4())).await;
+
+        let user_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+            .bind(&username)
+            .fetch_one(&self.db_pool)
+            .await
+            .expect("Failed to fetch user id.");
+        for role in roles {
+            sqlx::query("INSERT INTO user_roles (user_id, role, granted_at) VALUES ($1, $2, now())")
+                .bind(user_id)
+                .bind(role)
+                .execute(&self.db_pool)
+                .await
+                .expect("Failed to grant role.");
+        }
+
+        let login_json = self.login(&username, "password123").await
+            .json::<serde_json::Value>()
+            .await
+            .expect("Failed to parse login response as JSON");
+
+        (user_id, login_json["token"].as_str().unwrap().to_string())
+    }
+}
+
+/// Extracts the value of a `<name>=<value>` query parameter from an email body.
+pub fn extract_link_param(body: &str, name: &str) -> String {
+    let marker = format!("{}=", name);
+    let start = body.find(&marker).expect("Link parameter not found in email") + marker.len();
+    body[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+/// Computes the TOTP code an authenticator app would show at the given unix time.
+pub fn totp_code(secret: &str, unix_time: i64) -> String {
+    use hmac::{Hmac, Mac};
+
+    let key = base32::decode(base32::Alphabet::Rfc4648 { padding: false }, secret)
+        .expect("Invalid TOTP secret");
+    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&(unix_time / 30).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!("{:06}", binary % 1_000_000)
+}
+
+/// A sensor data upload from a smartwatch, for `POST /health/upload_<data_type>`
+/// or as part of a batch.
+pub fn sensor_upload(data_type: &str, samples: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "data_type": data_type,
+        "device_info": {
+            "device_type": "smartwatch",
+            "model": "AppleWatch Series 8",
+            "os_version": "watchOS 10.1"
+        },
+        "sampling_rate_hz": 1,
+        "start_time": "2025-03-10T12:00:00Z",
+        "end_time": "2025-03-10T12:00:10Z",
+        "samples": samples
+    })
+}
+
+/// A heart rate upload with a single sample.
+pub fn heart_rate_upload(heart_rate: i32) -> serde_json::Value {
+    sensor_upload("heart_rate", serde_json::json!([
+        {"timestamp": "2025-03-10T12:00:00Z", "heart_rate": heart_rate, "confidence": 0.95}
+    ]))
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Spawns the app after letting the caller adjust the configuration.
+pub async fn spawn_app_with<F>(customize: F) -> TestApp
+where
+    F: FnOnce(&mut Settings),
+{
     // The first time `initialize` is invoked the code in `TRACING` is executed.
     // All other invocations will instead skip execution.
     Lazy::force(&TRACING);
@@ -47,10 +234,13 @@ pub async fn spawn_app() -> TestApp {
     let address = format!("http://127.0.0.1:{}", port);
     let mut configuration = get_config().expect("Failed to read configuration.");
     configuration.database.db_name = Uuid::new_v4().to_string();
+    // Every test app gets its own outbox so tests can read the mail they triggered
+    let outbox_dir = std::env::temp_dir().join(format!("areum-outbox-{}", Uuid::new_v4()));
+    configuration.email.outbox_dir = outbox_dir.to_string_lossy().to_string();
+    customize(&mut configuration);
     let connection_pool = configure_db(&configuration.database)
         .await;
-    let jwt_settings = get_jwt_settings(&configuration);
-    let server = run(listener, connection_pool.clone(), jwt_settings)
+    let server = run(listener, connection_pool.clone(), configuration)
         .expect("Failed to bind address");
     // Launch the server as a background task
     // tokio::spawn returns a handle to the spawned future,
@@ -58,7 +248,8 @@ pub async fn spawn_app() -> TestApp {
     let _ = tokio::spawn(server);
     TestApp {
         address,
-        db_pool: connection_pool
+        db_pool: connection_pool,
+        outbox_dir
     }
 }
 
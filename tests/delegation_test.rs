@@ -0,0 +1,236 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{heart_rate_upload, spawn_app, TestApp};
+
+async fn upload_heart_rate(client: &Client, test_app: &TestApp, token: &str) {
+    let response = client
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&heart_rate_upload(72))
+        .send()
+        .await
+        .expect("Failed to execute upload request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+async fn invite(
+    client: &Client,
+    test_app: &TestApp,
+    token: &str,
+    username: &str,
+    data_types: &[&str]
+) -> reqwest::Response {
+    client
+        .post(format!("{}/delegations", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "username": username,
+            "data_types": data_types,
+            "expires_at": Utc::now() + Duration::days(7)
+        }))
+        .send()
+        .await
+        .expect("Failed to execute invitation request.")
+}
+
+/// Invites the delegate and accepts the invitation. Returns the delegation id.
+async fn delegate_access(
+    client: &Client,
+    test_app: &TestApp,
+    owner_token: &str,
+    delegate_token: &str,
+    delegate_username: &str,
+    data_types: &[&str]
+) -> String {
+    let response = invite(client, test_app, owner_token, delegate_username, data_types).await;
+    assert_eq!(201, response.status().as_u16());
+    let delegation_id = response.json::<serde_json::Value>().await.unwrap()["id"].as_str().unwrap().to_string();
+
+    let accept_response = client
+        .post(format!("{}/delegations/{}/accept", &test_app.address, delegation_id))
+        .header("Authorization", format!("Bearer {}", delegate_token))
+        .send()
+        .await
+        .expect("Failed to execute accept request.");
+    assert_eq!(200, accept_response.status().as_u16());
+
+    delegation_id
+}
+
+async fn read_as(client: &Client, test_app: &TestApp, token: &str, endpoint: &str, owner_id: Uuid) -> reqwest::Response {
+    client
+        .get(format!("{}/health/{}?user_id={}", &test_app.address, endpoint, owner_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn accepted_delegation_grants_logged_read_access() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (owner_id, owner_token) = test_app.create_user_with_roles(&[]).await;
+    let (delegate_id, delegate_token) = test_app.create_user_with_roles(&[]).await;
+    let delegate_username = test_app.username_of(delegate_id).await;
+    upload_heart_rate(&client, &test_app, &owner_token).await;
+    let invitation = invite(&client, &test_app, &owner_token, &delegate_username, &["heart_rate"]).await;
+    assert_eq!(201, invitation.status().as_u16());
+    let delegation_id = invitation.json::<serde_json::Value>().await.unwrap()["id"].as_str().unwrap().to_string();
+    let before_accepting = read_as(&client, &test_app, &delegate_token, "heart_rate_data", owner_id).await;
+
+    // Act
+    let accept_response = client
+        .post(format!("{}/delegations/{}/accept", &test_app.address, delegation_id))
+        .header("Authorization", format!("Bearer {}", delegate_token))
+        .send()
+        .await
+        .expect("Failed to execute accept request.");
+    let response = read_as(&client, &test_app, &delegate_token, "heart_rate_data", owner_id).await;
+
+    // Assert
+    assert_eq!(403, before_accepting.status().as_u16(), "Invitations grant nothing until accepted");
+    assert_eq!(200, accept_response.status().as_u16());
+    assert_eq!(200, response.status().as_u16());
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(1, data["count"]);
+    assert_eq!(owner_id.to_string(), data["data"][0]["user_id"]);
+    let events = client
+        .get(format!("{}/account/audit?event_type=health_data_accessed", &test_app.address))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    assert_eq!(1, events.len(), "Only the allowed read is recorded");
+    assert_eq!(delegate_id.to_string(), events[0]["actor_id"]);
+    assert_eq!(json!(["heart_rate"]), events[0]["details"]["data_types"]);
+}
+
+#[tokio::test]
+async fn delegation_is_limited_to_its_data_types_and_delegate() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (owner_id, owner_token) = test_app.create_user_with_roles(&[]).await;
+    let (delegate_id, delegate_token) = test_app.create_user_with_roles(&[]).await;
+    let (_, stranger_token) = test_app.create_user_with_roles(&[]).await;
+    let delegate_username = test_app.username_of(delegate_id).await;
+    delegate_access(&client, &test_app, &owner_token, &delegate_token, &delegate_username, &["heart_rate", "sleep"]).await;
+
+    // Act
+    let heart_rate = read_as(&client, &test_app, &delegate_token, "heart_rate_data", owner_id).await;
+    let sleep_trends = read_as(&client, &test_app, &delegate_token, "sleep_trends", owner_id).await;
+    let blood_oxygen = read_as(&client, &test_app, &delegate_token, "blood_oxygen_data", owner_id).await;
+    let stranger = read_as(&client, &test_app, &stranger_token, "heart_rate_data", owner_id).await;
+    let own_data = read_as(&client, &test_app, &delegate_token, "heart_rate_data", delegate_id).await;
+
+    // Assert
+    assert_eq!(200, heart_rate.status().as_u16());
+    assert_ne!(403, sleep_trends.status().as_u16(), "Sleep covers all sleep endpoints");
+    assert_eq!(403, blood_oxygen.status().as_u16());
+    assert_eq!(403, stranger.status().as_u16());
+    assert_eq!(200, own_data.status().as_u16(), "Users can name themselves");
+}
+
+#[tokio::test]
+async fn revoked_and_expired_delegations_stop_access() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (owner_id, owner_token) = test_app.create_user_with_roles(&[]).await;
+    let (revoked_id, revoked_token) = test_app.create_user_with_roles(&[]).await;
+    let (expired_id, expired_token) = test_app.create_user_with_roles(&[]).await;
+    let revoked_username = test_app.username_of(revoked_id).await;
+    let expired_username = test_app.username_of(expired_id).await;
+    let revoked_delegation = delegate_access(&client, &test_app, &owner_token, &revoked_token, &revoked_username, &["heart_rate"]).await;
+    delegate_access(&client, &test_app, &owner_token, &expired_token, &expired_username, &["heart_rate"]).await;
+    sqlx::query("UPDATE delegations SET expires_at = $1 WHERE delegate_id = $2")
+        .bind(Utc::now())
+        .bind(expired_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let revoke_response = client
+        .delete(format!("{}/delegations/{}", &test_app.address, revoked_delegation))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .expect("Failed to execute revoke request.");
+    let after_revoking = read_as(&client, &test_app, &revoked_token, "heart_rate_data", owner_id).await;
+    let after_expiry = read_as(&client, &test_app, &expired_token, "heart_rate_data", owner_id).await;
+    let granted = client
+        .get(format!("{}/delegations/granted", &test_app.address))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, revoke_response.status().as_u16());
+    assert_eq!(403, after_revoking.status().as_u16());
+    assert_eq!(403, after_expiry.status().as_u16());
+    assert!(granted.is_empty(), "Ended delegations aren't listed");
+}
+
+#[tokio::test]
+async fn invitations_are_validated_and_listed_for_both_sides() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (owner_id, owner_token) = test_app.create_user_with_roles(&[]).await;
+    let (delegate_id, delegate_token) = test_app.create_user_with_roles(&[]).await;
+    let owner_username = test_app.username_of(owner_id).await;
+    let delegate_username = test_app.username_of(delegate_id).await;
+
+    // Act
+    let unknown_type = invite(&client, &test_app, &owner_token, &delegate_username, &["diary"]).await;
+    let no_types = invite(&client, &test_app, &owner_token, &delegate_username, &[]).await;
+    let to_self = invite(&client, &test_app, &owner_token, &owner_username, &["heart_rate"]).await;
+    let unknown_user = invite(&client, &test_app, &owner_token, "nobody-at-all", &["heart_rate"]).await;
+    let past_expiry = client
+        .post(format!("{}/delegations", &test_app.address))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({
+            "username": delegate_username,
+            "data_types": ["heart_rate"],
+            "expires_at": Utc::now() - Duration::days(1)
+        }))
+        .send()
+        .await
+        .expect("Failed to execute invitation request.");
+    let created = invite(&client, &test_app, &owner_token, &delegate_username, &["sleep", "heart_rate", "sleep"]).await;
+    let received = client
+        .get(format!("{}/delegations/received", &test_app.address))
+        .header("Authorization", format!("Bearer {}", delegate_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(400, unknown_type.status().as_u16());
+    assert_eq!(400, no_types.status().as_u16());
+    assert_eq!(400, to_self.status().as_u16());
+    assert_eq!(404, unknown_user.status().as_u16());
+    assert_eq!(400, past_expiry.status().as_u16());
+    assert_eq!(201, created.status().as_u16());
+    assert_eq!(1, received.len());
+    assert_eq!(owner_username, received[0]["owner_username"]);
+    assert_eq!(json!(["heart_rate", "sleep"]), received[0]["data_types"]);
+    assert!(received[0]["accepted_at"].is_null(), "Still pending");
+}
@@ -0,0 +1,111 @@
+use reqwest::Client;
+
+mod common;
+use common::utils::{extract_link_param, spawn_app, spawn_app_with, TestApp};
+
+async fn verify_email(client: &Client, test_app: &TestApp, token: &str) -> u16 {
+    client
+        .get(format!("{}/verify_email?token={}", &test_app.address, token))
+        .send()
+        .await
+        .expect("Failed to execute verification request.")
+        .status()
+        .as_u16()
+}
+
+fn verification_token(test_app: &TestApp, email: &str) -> String {
+    let verification_email = test_app.last_email_to(email);
+    extract_link_param(verification_email["body"].as_str().unwrap(), "token")
+}
+
+#[tokio::test]
+async fn registration_sends_a_single_use_verification_link() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, email) = test_app.register_user().await;
+    let token = verification_token(&test_app, &email);
+
+    // Act
+    let status = verify_email(&client, &test_app, &token).await;
+
+    // Assert
+    assert_eq!(200, status, "Verification should succeed");
+    let saved = sqlx::query!("SELECT email_verified_at FROM users WHERE username = $1", username)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert!(saved.email_verified_at.is_some(), "Email should be marked as verified");
+    assert_eq!(400, verify_email(&client, &test_app, &token).await, "Token should only work once");
+}
+
+#[tokio::test]
+async fn unverified_users_are_blocked_from_health_data_when_required() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.application.require_email_verification = true;
+    }).await;
+    let client = Client::new();
+    let (username, email) = test_app.register_user().await;
+    let login_json = test_app.login_as(&username).await;
+    let access_token = login_json["token"].as_str().unwrap();
+
+    // Act & Assert
+    assert_eq!(403, test_app.get_heart_rate_status(access_token).await);
+
+    let token = verification_token(&test_app, &email);
+    assert_eq!(200, verify_email(&client, &test_app, &token).await);
+    assert_eq!(200, test_app.get_heart_rate_status(access_token).await);
+}
+
+#[tokio::test]
+async fn unverified_users_can_access_health_data_when_not_required() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.application.require_email_verification = false;
+    }).await;
+    let (username, _) = test_app.register_user().await;
+    let login_json = test_app.login_as(&username).await;
+    let access_token = login_json["token"].as_str().unwrap();
+
+    // Act
+    let status = test_app.get_heart_rate_status(access_token).await;
+
+    // Assert
+    assert_eq!(200, status);
+}
+
+#[tokio::test]
+async fn resending_the_verification_email_invalidates_the_previous_link() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, email) = test_app.register_user().await;
+    let first_token = verification_token(&test_app, &email);
+    let login_json = test_app.login_as(&username).await;
+    let access_token = login_json["token"].as_str().unwrap();
+
+    // Act
+    let response = client
+        .post(format!("{}/verify_email/resend", &test_app.address))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .expect("Failed to execute resend request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let second_token = verification_token(&test_app, &email);
+    assert_ne!(first_token, second_token);
+    assert_eq!(400, verify_email(&client, &test_app, &first_token).await);
+    assert_eq!(200, verify_email(&client, &test_app, &second_token).await);
+
+    // Nothing left to verify
+    let response = client
+        .post(format!("{}/verify_email/resend", &test_app.address))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .expect("Failed to execute resend request.");
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,152 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+
+mod common;
+use common::utils::{heart_rate_upload, spawn_app, spawn_app_with, TestApp};
+
+async fn upload(test_app: &TestApp, token: &str, idempotency_key: Option<&str>, body: &Value) -> (u16, Value) {
+    let mut request = Client::new()
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body);
+    if let Some(key) = idempotency_key {
+        request = request.header("Idempotency-Key", key);
+    }
+    let response = request.send().await.expect("Failed to execute upload request.");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("Failed to parse upload response as JSON"))
+}
+
+async fn stored_uploads(test_app: &TestApp) -> i64 {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM health_data")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn retry_with_idempotency_key_returns_the_original_response() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (status, first) = upload(&test_app, &token, Some("upload-1"), &heart_rate_upload(72)).await;
+    assert_eq!(200, status);
+    let (status, retry) = upload(&test_app, &token, Some("upload-1"), &heart_rate_upload(72)).await;
+    assert_eq!(200, status);
+
+    assert_eq!(first, retry);
+    assert_eq!(1, stored_uploads(&test_app).await);
+
+    // Keys are unique per user, not globally
+    let (_, other_token) = test_app.create_user_with_roles(&[]).await;
+    let (status, other) = upload(&test_app, &other_token, Some("upload-1"), &heart_rate_upload(72)).await;
+    assert_eq!(200, status);
+    assert_ne!(first["id"], other["id"]);
+}
+
+#[tokio::test]
+async fn client_upload_id_reused_for_different_content_returns_422() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let mut body = heart_rate_upload(72);
+    body["client_upload_id"] = json!("watch-42");
+    let (status, first) = upload(&test_app, &token, None, &body).await;
+    assert_eq!(200, status);
+
+    // The header is an alternative to the field
+    let (status, retry) = upload(&test_app, &token, Some("watch-42"), &heart_rate_upload(72)).await;
+    assert_eq!(200, status);
+    assert_eq!(first["id"], retry["id"]);
+
+    let mut changed = heart_rate_upload(90);
+    changed["client_upload_id"] = json!("watch-42");
+    let (status, body) = upload(&test_app, &token, None, &changed).await;
+    assert_eq!(422, status);
+    assert_eq!(body["status"], "error");
+    assert_eq!(1, stored_uploads(&test_app).await);
+}
+
+#[tokio::test]
+async fn identical_uploads_are_flagged_when_deduplication_is_enabled() {
+    let test_app = spawn_app_with(|config| {
+        config.application.deduplicate_uploads = true;
+    }).await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (_, first) = upload(&test_app, &token, Some("first"), &heart_rate_upload(72)).await;
+    let (status, duplicate) = upload(&test_app, &token, Some("second"), &heart_rate_upload(72)).await;
+
+    assert_eq!(200, status);
+    assert_eq!(duplicate["status"], "duplicate");
+    assert_eq!(first["id"], duplicate["id"]);
+    assert_eq!(1, stored_uploads(&test_app).await);
+
+    let (_, different) = upload(&test_app, &token, None, &heart_rate_upload(73)).await;
+    assert_eq!(different["status"], "success");
+    assert_eq!(2, stored_uploads(&test_app).await);
+}
+
+#[tokio::test]
+async fn identical_uploads_are_stored_twice_without_deduplication() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let (_, first) = upload(&test_app, &token, None, &heart_rate_upload(72)).await;
+    let (_, second) = upload(&test_app, &token, None, &heart_rate_upload(72)).await;
+
+    assert_eq!(second["status"], "success");
+    assert_ne!(first["id"], second["id"]);
+    assert_eq!(2, stored_uploads(&test_app).await);
+}
+
+#[tokio::test]
+async fn batch_retry_does_not_store_uploads_twice() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let mut item = heart_rate_upload(72);
+    item["client_upload_id"] = json!("batch-item-1");
+    let batch = json!({ "uploads": [item] });
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = Client::new()
+            .post(format!("{}/health/batch", &test_app.address))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&batch)
+            .send()
+            .await
+            .expect("Failed to execute batch request.");
+        assert_eq!(200, response.status().as_u16());
+        let body: Value = response.json().await.unwrap();
+        ids.push(body["results"][0]["id"].clone());
+    }
+
+    assert_eq!(ids[0], ids[1]);
+    assert_eq!(1, stored_uploads(&test_app).await);
+}
+
+#[tokio::test]
+async fn batch_of_duplicates_is_accepted() {
+    let test_app = spawn_app_with(|config| {
+        config.application.deduplicate_uploads = true;
+    }).await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    upload(&test_app, &token, None, &heart_rate_upload(72)).await;
+
+    let response = Client::new()
+        .post(format!("{}/health/batch", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "uploads": [heart_rate_upload(72)] }))
+        .send()
+        .await
+        .expect("Failed to execute batch request.");
+
+    assert_eq!(200, response.status().as_u16());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "success");
+    assert_eq!(body["stored"], 1);
+    assert_eq!(body["results"][0]["status"], "duplicate");
+    assert_eq!(1, stored_uploads(&test_app).await);
+}
@@ -0,0 +1,221 @@
+use areum_backend::config::settings::{JwtAlgorithm, JwtKeyConfig};
+use chrono::{Duration, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use reqwest::Client;
+use secrecy::SecretString;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, spawn_app_with, TestApp};
+
+fn key_config(kid: &str, algorithm: JwtAlgorithm, file: &str, active_from_hours: i64) -> JwtKeyConfig {
+    JwtKeyConfig {
+        kid: kid.to_string(),
+        algorithm,
+        private_key_path: format!("{}/tests/fixtures/jwt/{}", env!("CARGO_MANIFEST_DIR"), file),
+        active_from: Utc::now() + Duration::hours(active_from_hours),
+        expires_at: None,
+    }
+}
+
+async fn fetch_jwks(client: &Client, test_app: &TestApp) -> JwkSet {
+    let response = client
+        .get(format!("{}/.well-known/jwks.json", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    response.json::<JwkSet>().await
+        .expect("Failed to parse JWKS")
+}
+
+/// Signs a token for the user with a key from the fixtures, as an earlier key would have.
+fn sign_with_fixture(user_id: Uuid, kid: &str, file: &str) -> String {
+    let pem = std::fs::read(format!("{}/tests/fixtures/jwt/{}", env!("CARGO_MANIFEST_DIR"), file)).unwrap();
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(kid.to_string());
+    let now = Utc::now().timestamp();
+    encode(
+        &header,
+        &json!({
+            "sub": user_id.to_string(),
+            "username": "someone",
+            "exp": now + 600,
+            "iat": now - 5,
+            "jti": Uuid::new_v4().to_string()
+        }),
+        &EncodingKey::from_rsa_pem(&pem).unwrap(),
+    )
+    .unwrap()
+}
+
+/// Signs a token for the user with the shared secret and without a `kid`, as
+/// tokens were before asymmetric keys were configured.
+fn sign_with_secret(user_id: Uuid, secret: &str) -> String {
+    let now = Utc::now().timestamp();
+    encode(
+        &Header::default(),
+        &json!({
+            "sub": user_id.to_string(),
+            "username": "someone",
+            "exp": now + 600,
+            "iat": now - 5,
+            "jti": Uuid::new_v4().to_string()
+        }),
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn jwks_is_empty_without_asymmetric_keys() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let jwks = fetch_jwks(&client, &test_app).await;
+
+    // Assert
+    assert!(jwks.keys.is_empty());
+    let header = decode_header(&token).unwrap();
+    assert_eq!(Algorithm::HS256, header.alg);
+    assert!(header.kid.is_none());
+}
+
+#[tokio::test]
+async fn tokens_can_be_verified_with_the_published_keys() {
+    for (algorithm, file, expected) in [
+        (JwtAlgorithm::RS256, "rsa_current.pem", Algorithm::RS256),
+        (JwtAlgorithm::EdDSA, "ed25519.pem", Algorithm::EdDSA),
+    ] {
+        // Arrange
+        let test_app = spawn_app_with(|settings| {
+            settings.jwt.keys = vec![key_config("key-1", algorithm, file, -1)];
+        }).await;
+        let client = Client::new();
+        let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+        // Act
+        let jwks = fetch_jwks(&client, &test_app).await;
+
+        // Assert
+        let header = decode_header(&token).unwrap();
+        assert_eq!(expected, header.alg);
+        assert_eq!(Some("key-1".to_string()), header.kid);
+        let jwk = jwks.find("key-1").expect("Signing key should be published");
+        let claims = decode::<serde_json::Value>(
+            &token,
+            &DecodingKey::from_jwk(jwk).unwrap(),
+            &Validation::new(expected),
+        )
+        .expect("Token should verify with the published key")
+        .claims;
+        assert_eq!(user_id.to_string(), claims["sub"]);
+        assert_eq!(200, test_app.get_heart_rate_status(&token).await);
+    }
+}
+
+#[tokio::test]
+async fn keys_are_rotated_according_to_the_schedule() {
+    // Arrange
+    let test_app = spawn_app_with(|settings| {
+        let mut retired = key_config("retired", JwtAlgorithm::RS256, "rsa_next.pem", -48);
+        retired.expires_at = Some(Utc::now() - Duration::hours(1));
+        settings.jwt.keys = vec![
+            retired,
+            key_config("previous", JwtAlgorithm::RS256, "rsa_next.pem", -24),
+            key_config("current", JwtAlgorithm::RS256, "rsa_current.pem", -1),
+            key_config("upcoming", JwtAlgorithm::EdDSA, "ed25519.pem", 24),
+        ];
+    }).await;
+    let client = Client::new();
+
+    // Act
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+    let jwks = fetch_jwks(&client, &test_app).await;
+
+    // Assert
+    assert_eq!(Some("current".to_string()), decode_header(&token).unwrap().kid);
+    let published: Vec<_> = jwks.keys.iter().map(|jwk| jwk.common.key_id.clone().unwrap()).collect();
+    assert_eq!(vec!["previous", "current", "upcoming"], published);
+
+    // Tokens signed with a replaced key stay valid until the key expires
+    let previous_token = sign_with_fixture(user_id, "previous", "rsa_next.pem");
+    assert_eq!(200, test_app.get_heart_rate_status(&previous_token).await);
+    let retired_token = sign_with_fixture(user_id, "retired", "rsa_next.pem");
+    assert_eq!(401, test_app.get_heart_rate_status(&retired_token).await);
+}
+
+#[tokio::test]
+async fn tokens_with_an_unknown_key_id_are_rejected() {
+    // Arrange
+    let test_app = spawn_app_with(|settings| {
+        settings.jwt.keys = vec![key_config("current", JwtAlgorithm::RS256, "rsa_current.pem", -1)];
+    }).await;
+    let (user_id, _) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let token = sign_with_fixture(user_id, "unknown", "rsa_next.pem");
+    // Same kid, but signed with a key that isn't ours
+    let forged_token = sign_with_fixture(user_id, "current", "rsa_next.pem");
+
+    // Assert
+    assert_eq!(401, test_app.get_heart_rate_status(&token).await);
+    assert_eq!(401, test_app.get_heart_rate_status(&forged_token).await);
+}
+
+#[tokio::test]
+async fn tokens_without_key_id_are_rejected_once_a_key_is_active() {
+    // Arrange
+    let test_app = spawn_app_with(|settings| {
+        settings.jwt.secret = SecretString::from("legacy-secret");
+        settings.jwt.keys = vec![key_config("current", JwtAlgorithm::RS256, "rsa_current.pem", -1)];
+    }).await;
+    let (user_id, _) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let legacy_token = sign_with_secret(user_id, "legacy-secret");
+
+    // Assert
+    assert_eq!(401, test_app.get_heart_rate_status(&legacy_token).await);
+}
+
+#[tokio::test]
+async fn tokens_without_key_id_are_accepted_when_legacy_hs256_is_enabled() {
+    // Arrange
+    let test_app = spawn_app_with(|settings| {
+        settings.jwt.secret = SecretString::from("legacy-secret");
+        settings.jwt.keys = vec![key_config("current", JwtAlgorithm::RS256, "rsa_current.pem", -1)];
+        settings.jwt.accept_legacy_hs256 = Some(true);
+    }).await;
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let legacy_token = sign_with_secret(user_id, "legacy-secret");
+    let forged_token = sign_with_secret(user_id, "some-other-secret");
+
+    // Assert
+    assert_eq!(Some("current".to_string()), decode_header(&token).unwrap().kid);
+    assert_eq!(200, test_app.get_heart_rate_status(&legacy_token).await);
+    assert_eq!(401, test_app.get_heart_rate_status(&forged_token).await);
+}
+
+#[tokio::test]
+async fn tokens_without_key_id_are_accepted_until_the_first_key_is_active() {
+    // Arrange
+    let test_app = spawn_app_with(|settings| {
+        settings.jwt.keys = vec![key_config("upcoming", JwtAlgorithm::RS256, "rsa_current.pem", 24)];
+    }).await;
+
+    // Act
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Assert
+    assert!(decode_header(&token).unwrap().kid.is_none());
+    assert_eq!(200, test_app.get_heart_rate_status(&token).await);
+}
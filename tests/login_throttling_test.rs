@@ -0,0 +1,163 @@
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app_with, TestApp};
+
+async fn spawn_throttled_app(max_per_account: i32, max_per_ip: i32) -> TestApp {
+    spawn_app_with(|config| {
+        config.login_throttling.max_failed_attempts_per_account = max_per_account;
+        config.login_throttling.max_failed_attempts_per_ip = max_per_ip;
+    }).await
+}
+
+#[tokio::test]
+async fn account_is_locked_after_too_many_failed_logins() {
+    // Arrange
+    let test_app = spawn_throttled_app(3, 100).await;
+    let (username, _) = test_app.register_user().await;
+
+    // Act
+    for _ in 0..3 {
+        let response = test_app.login(&username, "wrongpassword").await;
+        assert_eq!(401, response.status().as_u16());
+    }
+    let response = test_app.login(&username, "password123").await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16(), "Even the correct password should be throttled");
+    let retry_after: i64 = response.headers()["Retry-After"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .expect("Retry-After should be a number of seconds");
+    assert!(retry_after > 0 && retry_after <= 60);
+
+    let event = sqlx::query!(
+        "SELECT scope, user_id FROM lockout_events WHERE key = $1",
+        username
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Lockout should be recorded");
+    assert_eq!("account", event.scope);
+    assert!(event.user_id.is_some());
+}
+
+#[tokio::test]
+async fn client_address_is_locked_after_failures_across_accounts() {
+    // Arrange
+    let test_app = spawn_throttled_app(100, 3).await;
+    let (username, _) = test_app.register_user().await;
+
+    // Act
+    for i in 0..3 {
+        let response = test_app.login(&format!("unknown{}", i), "wrongpassword").await;
+        assert_eq!(401, response.status().as_u16());
+    }
+    let response = test_app.login(&username, "password123").await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+    assert!(response.headers().contains_key("Retry-After"));
+}
+
+#[tokio::test]
+async fn successful_login_resets_the_failure_counter() {
+    // Arrange
+    let test_app = spawn_throttled_app(3, 100).await;
+    let (username, _) = test_app.register_user().await;
+
+    // Act
+    for _ in 0..2 {
+        test_app.login(&username, "wrongpassword").await;
+    }
+    assert_eq!(200, test_app.login(&username, "password123").await.status().as_u16());
+    for _ in 0..2 {
+        test_app.login(&username, "wrongpassword").await;
+    }
+
+    // Assert
+    let response = test_app.login(&username, "password123").await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn admin_can_list_and_lift_account_lockouts() {
+    // Arrange
+    let test_app = spawn_throttled_app(3, 100).await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    for _ in 0..3 {
+        test_app.login(&username, "wrongpassword").await;
+    }
+    assert_eq!(429, test_app.login(&username, "password123").await.status().as_u16());
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+
+    // Act
+    let lockouts = client
+        .get(format!("{}/admin/lockouts?active=true", &test_app.address))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse lockouts as JSON");
+    let user_id = lockouts[0]["user_id"].as_str().unwrap().to_string();
+    let unlock_response = client
+        .post(format!("{}/admin/users/{}/unlock", &test_app.address, user_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(username, lockouts[0]["key"].as_str().unwrap());
+    assert_eq!(200, unlock_response.status().as_u16());
+    assert_eq!(200, test_app.login(&username, "password123").await.status().as_u16());
+}
+
+#[tokio::test]
+async fn lockout_follows_the_account_when_the_username_changes() {
+    // Arrange
+    let test_app = spawn_throttled_app(3, 100).await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let token = test_app.login(&username, "password123").await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for _ in 0..3 {
+        test_app.login(&username, "wrongpassword").await;
+    }
+    let new_username = format!("renamed{}", Uuid::new_v4());
+
+    // Act
+    let response = client
+        .put(format!("{}/account/username", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "username": new_username }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(429, test_app.login(&new_username, "password123").await.status().as_u16());
+    let keys = sqlx::query_scalar::<_, String>("SELECT key FROM lockout_events WHERE scope = 'account'")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(vec![new_username], keys);
+    let old_records = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM login_throttles WHERE key = $1")
+        .bind(&username)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(0, old_records);
+}
@@ -0,0 +1,116 @@
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::spawn_app;
+
+#[tokio::test]
+async fn logout_returns_401_without_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let response = client
+        .post(format!("{}/logout", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn logout_revokes_the_access_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let login_json = test_app.login_as(&username).await;
+    let token = login_json["token"].as_str().unwrap();
+    assert_eq!(200, test_app.get_heart_rate_status(token).await);
+
+    // Act
+    let response = client
+        .post(format!("{}/logout", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Logout should succeed");
+    assert_eq!(401, test_app.get_heart_rate_status(token).await, "Token should be revoked");
+}
+
+#[tokio::test]
+async fn logout_with_refresh_token_revokes_the_refresh_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let login_json = test_app.login_as(&username).await;
+    let token = login_json["token"].as_str().unwrap();
+    let refresh_token = login_json["refresh_token"].as_str().unwrap();
+
+    // Act
+    let response = client
+        .post(format!("{}/logout", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "refresh_token": refresh_token }))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+    assert_eq!(200, response.status().as_u16(), "Logout should succeed");
+
+    // Assert
+    let refresh_response = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": refresh_token }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(401, refresh_response.status().as_u16(), "Refresh token should be revoked");
+}
+
+#[tokio::test]
+async fn logout_all_revokes_every_session() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let phone = test_app.login_as(&username).await;
+    let laptop = test_app.login_as(&username).await;
+    let phone_token = phone["token"].as_str().unwrap();
+    let laptop_token = laptop["token"].as_str().unwrap();
+
+    // Make sure both tokens were issued strictly before the cutoff second
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // Act
+    let response = client
+        .post(format!("{}/logout_all", &test_app.address))
+        .header("Authorization", format!("Bearer {}", phone_token))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Logout should succeed");
+    assert_eq!(401, test_app.get_heart_rate_status(phone_token).await);
+    assert_eq!(401, test_app.get_heart_rate_status(laptop_token).await);
+
+    let refresh_response = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": laptop["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(401, refresh_response.status().as_u16(), "Refresh tokens should be revoked");
+
+    // Logging in again issues a working token
+    let new_login = test_app.login_as(&username).await;
+    let new_token = new_login["token"].as_str().unwrap();
+    assert_eq!(200, test_app.get_heart_rate_status(new_token).await);
+}
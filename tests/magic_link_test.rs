@@ -0,0 +1,228 @@
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{extract_link_param, spawn_app, spawn_app_with, totp_code, TestApp};
+
+async fn request_link(client: &Client, test_app: &TestApp, email: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/login/magic_link/request", &test_app.address))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .expect("Failed to execute magic link request.")
+}
+
+async fn login_with_link(client: &Client, test_app: &TestApp, token: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/login/magic_link", &test_app.address))
+        .json(&json!({ "token": token }))
+        .send()
+        .await
+        .expect("Failed to execute magic link login.")
+}
+
+/// Requests a link for the address and reads its token from the outbox.
+async fn link_token(client: &Client, test_app: &TestApp, email: &str) -> String {
+    let response = request_link(client, test_app, email).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let message = test_app.last_email_to(email);
+    extract_link_param(message["body"].as_str().unwrap(), "token")
+}
+
+#[tokio::test]
+async fn magic_link_logs_the_user_in_once() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    let token = link_token(&client, &test_app, &email).await;
+
+    // Act
+    let response = login_with_link(&client, &test_app, &token).await;
+    let second_use = login_with_link(&client, &test_app, &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let login_json = response.json::<serde_json::Value>().await.unwrap();
+    let heart_rate_response = client
+        .get(format!("{}/health/heart_rate_data", &test_app.address))
+        .header("Authorization", format!("Bearer {}", login_json["token"].as_str().unwrap()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, heart_rate_response.status().as_u16(), "The issued token should work");
+    assert!(login_json["refresh_token"].is_string());
+    assert_eq!(401, second_use.status().as_u16(), "Links are single-use");
+}
+
+#[tokio::test]
+async fn expired_and_replaced_links_are_rejected() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    let replaced_token = link_token(&client, &test_app, &email).await;
+    let expired_token = link_token(&client, &test_app, &email).await;
+    sqlx::query("UPDATE magic_link_tokens SET expires_at = $1 WHERE used_at IS NULL")
+        .bind(Utc::now())
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let replaced = login_with_link(&client, &test_app, &replaced_token).await;
+    let expired = login_with_link(&client, &test_app, &expired_token).await;
+    let unknown = login_with_link(&client, &test_app, "not-a-real-token").await;
+
+    // Assert
+    assert_eq!(401, replaced.status().as_u16(), "Requesting a new link invalidates earlier ones");
+    assert_eq!(401, expired.status().as_u16());
+    assert_eq!(401, unknown.status().as_u16());
+}
+
+#[tokio::test]
+async fn unknown_addresses_get_the_same_answer_without_an_email() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let email = format!("nobody{}@example.com", Uuid::new_v4());
+
+    // Act
+    let response = request_link(&client, &test_app, &email).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(test_app.sent_emails().iter().all(|message| message["to"] != email.as_str()));
+}
+
+#[tokio::test]
+async fn magic_link_still_requires_the_second_factor() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    let username = email.trim_end_matches("@example.com");
+    let login_json = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({ "username": username, "password": "password123" }))
+        .send()
+        .await
+        .expect("Failed to execute login request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let token = login_json["token"].as_str().unwrap();
+    let secret = client
+        .post(format!("{}/mfa/totp/enroll", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute enrollment request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["secret"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let confirm_response = client
+        .post(format!("{}/mfa/totp/confirm", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "code": totp_code(&secret, Utc::now().timestamp()) }))
+        .send()
+        .await
+        .expect("Failed to execute confirmation request.");
+    assert_eq!(200, confirm_response.status().as_u16());
+    let link = link_token(&client, &test_app, &email).await;
+
+    // Act
+    let response = login_with_link(&client, &test_app, &link).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let challenge = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(true, challenge["mfa_required"]);
+    assert!(challenge["mfa_token"].is_string());
+    assert!(challenge.get("token").is_none(), "No session before the second step");
+}
+
+#[tokio::test]
+async fn magic_link_login_can_be_disabled() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.application.enable_magic_link_login = false;
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    let response = request_link(&client, &test_app, &email).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let sign_in_emails = test_app.sent_emails()
+        .into_iter()
+        .filter(|message| message["to"] == email.as_str() && message["subject"] == "Your Areum sign-in link")
+        .count();
+    assert_eq!(0, sign_in_emails);
+}
+
+#[tokio::test]
+async fn magic_link_points_to_the_configured_page() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.application.magic_link_url = "https://app.example.com/sign_in".to_string();
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    let token = link_token(&client, &test_app, &email).await;
+
+    // Assert
+    let body = test_app.last_email_to(&email)["body"].as_str().unwrap().to_string();
+    assert!(body.contains(&format!("https://app.example.com/sign_in?token={}", token)));
+}
+
+#[tokio::test]
+async fn magic_link_request_returns_200_when_the_email_cannot_be_sent() {
+    // Arrange: an outbox below a file can't be created
+    let test_app = spawn_app_with(|config| {
+        config.email.outbox_dir = "/dev/null/outbox".to_string();
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    let response = request_link(&client, &test_app, &email).await;
+
+    // Assert: the same answer as for unknown addresses
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn magic_link_requests_are_rate_limited() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    for _ in 0..5 {
+        assert_eq!(200, request_link(&client, &test_app, &email).await.status().as_u16());
+    }
+    let throttled = request_link(&client, &test_app, &email).await;
+    let unknown = request_link(&client, &test_app, "nobody@example.com").await;
+
+    // Assert
+    assert_eq!(429, throttled.status().as_u16());
+    assert!(throttled.headers().contains_key("Retry-After"));
+    let links = test_app.sent_emails().into_iter()
+        .filter(|message| message["subject"] == "Your Areum sign-in link")
+        .count();
+    assert_eq!(5, links);
+    assert_eq!(200, unknown.status().as_u16(), "Other addresses have their own limit");
+}
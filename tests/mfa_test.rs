@@ -0,0 +1,225 @@
+use chrono::Utc;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, spawn_app_with, totp_code, TestApp};
+
+/// Enrolls the user in TOTP and returns the secret and the recovery codes.
+async fn enable_mfa(client: &Client, test_app: &TestApp, token: &str) -> (String, Vec<String>) {
+    let enroll_json = client
+        .post(format!("{}/mfa/totp/enroll", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute enrollment request.")
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse enrollment response as JSON");
+    let secret = enroll_json["secret"].as_str().unwrap().to_string();
+
+    let confirm_response = client
+        .post(format!("{}/mfa/totp/confirm", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "code": totp_code(&secret, Utc::now().timestamp()) }))
+        .send()
+        .await
+        .expect("Failed to execute confirmation request.");
+    assert_eq!(200, confirm_response.status().as_u16(), "Enrollment confirmation should succeed");
+
+    let confirm_json = confirm_response.json::<serde_json::Value>().await.unwrap();
+    let recovery_codes = confirm_json["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    (secret, recovery_codes)
+}
+
+async fn complete_login(client: &Client, test_app: &TestApp, mfa_token: &str, code: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/login/mfa", &test_app.address))
+        .json(&json!({
+            "mfa_token": mfa_token,
+            "code": code
+        }))
+        .send()
+        .await
+        .expect("Failed to execute MFA login request.")
+}
+
+fn mfa_claim(token: &str) -> bool {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    let claims = decode::<serde_json::Value>(token, &DecodingKey::from_secret(&[]), &validation)
+        .expect("Failed to decode token");
+    claims.claims["mfa"].as_bool().unwrap()
+}
+
+#[tokio::test]
+async fn login_with_totp_requires_a_second_step() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    assert!(!mfa_claim(token));
+    let (secret, recovery_codes) = enable_mfa(&client, &test_app, token).await;
+    assert_eq!(10, recovery_codes.len());
+
+    // Act
+    let challenge = test_app.login_as(&username).await;
+
+    // Assert
+    assert_eq!(true, challenge["mfa_required"]);
+    assert!(challenge.get("token").is_none(), "No access token before the second step");
+    let mfa_token = challenge["mfa_token"].as_str().unwrap();
+    assert_eq!(401, test_app.get_heart_rate_status(mfa_token).await);
+
+    // The enrollment used the current code, so use the next one
+    let code = totp_code(&secret, Utc::now().timestamp() + 30);
+    let response = complete_login(&client, &test_app, mfa_token, &code).await;
+    assert_eq!(200, response.status().as_u16(), "A valid code should complete the login");
+    let login_json = response.json::<serde_json::Value>().await.unwrap();
+    let access_token = login_json["token"].as_str().unwrap();
+    assert!(mfa_claim(access_token), "Access token should record that MFA was satisfied");
+    assert_eq!(200, test_app.get_heart_rate_status(access_token).await);
+
+    // Refreshed tokens keep the MFA status
+    let refresh_json = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": login_json["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert!(mfa_claim(refresh_json["token"].as_str().unwrap()));
+}
+
+#[tokio::test]
+async fn mfa_login_rejects_invalid_and_replayed_codes() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let (secret, _) = enable_mfa(&client, &test_app, token).await;
+    let challenge = test_app.login_as(&username).await;
+    let mfa_token = challenge["mfa_token"].as_str().unwrap();
+
+    // Act & Assert
+    assert_eq!(401, complete_login(&client, &test_app, mfa_token, "abcdef").await.status().as_u16());
+
+    // The code used to confirm the enrollment can't be replayed
+    let used_code = totp_code(&secret, Utc::now().timestamp());
+    let next_code = totp_code(&secret, Utc::now().timestamp() + 30);
+    if used_code != next_code {
+        assert_eq!(401, complete_login(&client, &test_app, mfa_token, &used_code).await.status().as_u16());
+    }
+
+    assert_eq!(200, complete_login(&client, &test_app, mfa_token, &next_code).await.status().as_u16());
+    // An MFA token can only be exchanged once
+    assert_eq!(401, complete_login(&client, &test_app, mfa_token, &next_code).await.status().as_u16());
+}
+
+#[tokio::test]
+async fn recovery_codes_can_only_be_used_once() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let (_, recovery_codes) = enable_mfa(&client, &test_app, token).await;
+
+    // Act
+    let challenge = test_app.login_as(&username).await;
+    let first = complete_login(
+        &client, &test_app, challenge["mfa_token"].as_str().unwrap(), &recovery_codes[0].to_uppercase()
+    ).await;
+    let challenge = test_app.login_as(&username).await;
+    let second = complete_login(
+        &client, &test_app, challenge["mfa_token"].as_str().unwrap(), &recovery_codes[0]
+    ).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16(), "Recovery code should be accepted");
+    assert_eq!(401, second.status().as_u16(), "Recovery code should only work once");
+}
+
+#[tokio::test]
+async fn enrollment_requires_a_valid_code_and_can_only_be_done_once() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let enroll_json = client
+        .post(format!("{}/mfa/totp/enroll", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute enrollment request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let uri = enroll_json["otpauth_uri"].as_str().unwrap();
+    assert!(uri.starts_with(&format!("otpauth://totp/Areum:{}?secret=", username)));
+
+    // Act
+    let invalid_confirm = client
+        .post(format!("{}/mfa/totp/confirm", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "code": "abcdef" }))
+        .send()
+        .await
+        .expect("Failed to execute confirmation request.");
+    enable_mfa(&client, &test_app, token).await;
+    let second_enrollment = client
+        .post(format!("{}/mfa/totp/enroll", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute enrollment request.");
+
+    // Assert
+    assert_eq!(400, invalid_confirm.status().as_u16());
+    assert_eq!(409, second_enrollment.status().as_u16());
+}
+
+#[tokio::test]
+async fn wrong_codes_for_disabling_mfa_are_throttled() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.login_throttling.max_failed_attempts_per_account = 3;
+    }).await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let (secret, _) = enable_mfa(&client, &test_app, token).await;
+    let post_code = |path: &'static str, code: String| {
+        client
+            .post(format!("{}{}", &test_app.address, path))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "code": code }))
+            .send()
+    };
+
+    // Act & Assert
+    for _ in 0..2 {
+        let response = post_code("/mfa/totp/disable", "abcdef".to_string()).await.unwrap();
+        assert_eq!(400, response.status().as_u16());
+    }
+    let response = post_code("/mfa/recovery_codes", "abcdef".to_string()).await.unwrap();
+    assert_eq!(400, response.status().as_u16());
+
+    // Even a valid code is refused while locked out
+    let code = totp_code(&secret, Utc::now().timestamp() + 30);
+    let response = post_code("/mfa/totp/disable", code).await.unwrap();
+    assert_eq!(429, response.status().as_u16());
+    assert!(response.headers().contains_key("Retry-After"));
+}
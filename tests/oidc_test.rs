@@ -0,0 +1,287 @@
+use std::net::TcpListener;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use areum_backend::config::jwt::JwtSettings;
+use areum_backend::config::settings::{JwtAlgorithm, JwtKeyConfig, OidcProviderConfig};
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app_with, TestApp};
+
+const ISSUER: &str = "https://accounts.example.com";
+const CLIENT_ID: &str = "areum-test-client";
+
+/// A stand-in for an identity provider: signs ID tokens and publishes its key.
+struct MockIssuer {
+    keys: JwtSettings,
+    jwks_uri: String,
+}
+
+fn signing_keys(file: &str) -> JwtSettings {
+    JwtSettings::new("unused".to_string(), 15, 1)
+        .with_keys(&[JwtKeyConfig {
+            kid: "mock-key".to_string(),
+            algorithm: JwtAlgorithm::RS256,
+            private_key_path: format!("{}/tests/fixtures/jwt/{}", env!("CARGO_MANIFEST_DIR"), file),
+            active_from: Utc::now() - Duration::hours(1),
+            expires_at: None,
+        }])
+        .expect("Failed to load the mock issuer's key")
+}
+
+async fn spawn_issuer() -> MockIssuer {
+    let jwks = signing_keys("rsa_next.pem").jwks();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let jwks_uri = format!("http://127.0.0.1:{}/keys", listener.local_addr().unwrap().port());
+    let server = HttpServer::new(move || {
+        let jwks = jwks.clone();
+        App::new().route("/keys", web::get().to(move || {
+            let jwks = jwks.clone();
+            async move { HttpResponse::Ok().json(jwks) }
+        }))
+    })
+    .listen(listener)
+    .expect("Failed to bind the mock issuer")
+    .run();
+    tokio::spawn(server);
+
+    MockIssuer { keys: signing_keys("rsa_next.pem"), jwks_uri }
+}
+
+impl MockIssuer {
+    /// A token for a new sign-in, with a nonce the app handed out for it.
+    async fn id_token(&self, test_app: &TestApp, subject: &str, email: &str) -> String {
+        let nonce = request_nonce(test_app).await;
+        self.keys.sign(&self.claims(subject, email, &nonce)).unwrap()
+    }
+
+    fn claims(&self, subject: &str, email: &str, nonce: &str) -> serde_json::Value {
+        json!({
+            "iss": ISSUER,
+            "sub": subject,
+            "aud": CLIENT_ID,
+            "exp": (Utc::now() + Duration::minutes(5)).timestamp(),
+            "email": email,
+            "email_verified": true,
+            "nonce": nonce
+        })
+    }
+}
+
+async fn spawn_app_with_issuer(issuer: &MockIssuer) -> TestApp {
+    let jwks_uri = issuer.jwks_uri.clone();
+    spawn_app_with(|config| {
+        config.oidc.providers = vec![OidcProviderConfig {
+            name: "example".to_string(),
+            issuer: ISSUER.to_string(),
+            jwks_uri,
+            client_id: CLIENT_ID.to_string(),
+        }];
+    }).await
+}
+
+/// Gets a nonce the way the app does before signing in with the provider.
+async fn request_nonce(test_app: &TestApp) -> String {
+    Client::new()
+        .post(format!("{}/login/oidc/nonce", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute nonce request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["nonce"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn login_with_oidc(client: &Client, test_app: &TestApp, provider: &str, id_token: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/login/oidc", &test_app.address))
+        .json(&json!({ "provider": provider, "id_token": id_token }))
+        .send()
+        .await
+        .expect("Failed to execute OIDC login.")
+}
+
+/// The account a token belongs to, found through its session.
+async fn user_id_of(client: &Client, test_app: &TestApp, token: &str) -> Uuid {
+    let sessions = client
+        .get(format!("{}/sessions", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let session = sessions.iter().find(|session| session["current"] == true).unwrap();
+
+    sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM sessions WHERE id = $1")
+        .bind(Uuid::parse_str(session["id"].as_str().unwrap()).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn new_identity_creates_an_account_and_logs_into_it_again() {
+    // Arrange
+    let issuer = spawn_issuer().await;
+    let test_app = spawn_app_with_issuer(&issuer).await;
+    let client = Client::new();
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("social{}@example.com", Uuid::new_v4());
+
+    // Act
+    let first = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &email).await).await;
+    let second = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &email).await).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(200, second.status().as_u16());
+    let first_token = first.json::<serde_json::Value>().await.unwrap()["token"].as_str().unwrap().to_string();
+    let second_token = second.json::<serde_json::Value>().await.unwrap()["token"].as_str().unwrap().to_string();
+    let accounts = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE email = $1 AND email_verified_at IS NOT NULL")
+        .bind(&email)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, accounts, "One account with a verified address");
+    let first_user = user_id_of(&client, &test_app, &first_token).await;
+    assert_eq!(first_user, user_id_of(&client, &test_app, &second_token).await);
+}
+
+#[tokio::test]
+async fn invalid_id_tokens_are_rejected() {
+    // Arrange
+    let issuer = spawn_issuer().await;
+    let test_app = spawn_app_with_issuer(&issuer).await;
+    let client = Client::new();
+    let email = format!("social{}@example.com", Uuid::new_v4());
+    let nonce = request_nonce(&test_app).await;
+    let mut wrong_audience = issuer.claims("subject", &email, &nonce);
+    wrong_audience["aud"] = json!("another-client");
+    let mut expired = issuer.claims("subject", &email, &nonce);
+    expired["exp"] = json!((Utc::now() - Duration::hours(1)).timestamp());
+    // Same key id, but not the key the issuer publishes
+    let forged = signing_keys("rsa_current.pem").sign(&issuer.claims("subject", &email, &nonce)).unwrap();
+
+    // Act
+    let wrong_audience = login_with_oidc(&client, &test_app, "example", &issuer.keys.sign(&wrong_audience).unwrap()).await;
+    let expired = login_with_oidc(&client, &test_app, "example", &issuer.keys.sign(&expired).unwrap()).await;
+    let forged = login_with_oidc(&client, &test_app, "example", &forged).await;
+    let unknown_provider = login_with_oidc(&client, &test_app, "elsewhere", &issuer.id_token(&test_app, "subject", &email).await).await;
+
+    // Assert
+    assert_eq!(401, wrong_audience.status().as_u16());
+    assert_eq!(401, expired.status().as_u16());
+    assert_eq!(401, forged.status().as_u16());
+    assert_eq!(400, unknown_provider.status().as_u16());
+}
+
+#[tokio::test]
+async fn id_tokens_are_bound_to_a_single_use_nonce() {
+    // Arrange
+    let issuer = spawn_issuer().await;
+    let test_app = spawn_app_with_issuer(&issuer).await;
+    let client = Client::new();
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("social{}@example.com", Uuid::new_v4());
+    let id_token = issuer.id_token(&test_app, &subject, &email).await;
+    let mut without_nonce = issuer.claims(&subject, &email, "");
+    without_nonce.as_object_mut().unwrap().remove("nonce");
+    let made_up_nonce = issuer.keys.sign(&issuer.claims(&subject, &email, "made-up")).unwrap();
+
+    // Act
+    let first = login_with_oidc(&client, &test_app, "example", &id_token).await;
+    let replayed = login_with_oidc(&client, &test_app, "example", &id_token).await;
+    let without_nonce = login_with_oidc(&client, &test_app, "example", &issuer.keys.sign(&without_nonce).unwrap()).await;
+    let made_up_nonce = login_with_oidc(&client, &test_app, "example", &made_up_nonce).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(401, replayed.status().as_u16());
+    assert_eq!(401, without_nonce.status().as_u16());
+    assert_eq!(401, made_up_nonce.status().as_u16());
+}
+
+#[tokio::test]
+async fn linked_identity_logs_into_the_account_until_unlinked() {
+    // Arrange
+    let issuer = spawn_issuer().await;
+    let test_app = spawn_app_with_issuer(&issuer).await;
+    let client = Client::new();
+    let (username, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+    let email = format!("{}@example.com", username);
+    let subject = Uuid::new_v4().to_string();
+    let unlinked_login = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &email).await).await;
+    assert_eq!(409, unlinked_login.status().as_u16(), "Existing addresses aren't linked automatically");
+
+    // Act
+    let link_response = client
+        .post(format!("{}/account/identities", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "provider": "example", "id_token": issuer.id_token(&test_app, &subject, &email).await }))
+        .send()
+        .await
+        .expect("Failed to execute link request.");
+    let linked_login = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &email).await).await;
+    let identities = client
+        .get(format!("{}/account/identities", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let unlink_response = client
+        .delete(format!("{}/account/identities/{}", &test_app.address, identities[0]["id"].as_str().unwrap()))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute unlink request.");
+    let login_after_unlink = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &email).await).await;
+
+    // Assert
+    assert_eq!(201, link_response.status().as_u16());
+    assert_eq!(200, linked_login.status().as_u16());
+    let linked_token = linked_login.json::<serde_json::Value>().await.unwrap()["token"].as_str().unwrap().to_string();
+    assert_eq!(user_id_of(&client, &test_app, token).await, user_id_of(&client, &test_app, &linked_token).await);
+    assert_eq!(1, identities.len());
+    assert_eq!("example", identities[0]["provider"]);
+    assert!(identities[0]["last_login_at"].is_string());
+    assert_eq!(200, unlink_response.status().as_u16());
+    assert_eq!(409, login_after_unlink.status().as_u16());
+}
+
+#[tokio::test]
+async fn identity_can_only_be_linked_to_one_account() {
+    // Arrange
+    let issuer = spawn_issuer().await;
+    let test_app = spawn_app_with_issuer(&issuer).await;
+    let client = Client::new();
+    let subject = Uuid::new_v4().to_string();
+    let social_email = format!("social{}@example.com", Uuid::new_v4());
+    let social_login = login_with_oidc(&client, &test_app, "example", &issuer.id_token(&test_app, &subject, &social_email).await).await;
+    assert_eq!(200, social_login.status().as_u16());
+    let (_, login_json) = test_app.register_and_login().await;
+    let token = login_json["token"].as_str().unwrap();
+
+    // Act
+    let link_response = client
+        .post(format!("{}/account/identities", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "provider": "example", "id_token": issuer.id_token(&test_app, &subject, &social_email).await }))
+        .send()
+        .await
+        .expect("Failed to execute link request.");
+
+    // Assert
+    assert_eq!(409, link_response.status().as_u16());
+}
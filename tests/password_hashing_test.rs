@@ -0,0 +1,133 @@
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::spawn_app;
+
+#[tokio::test]
+async fn register_stores_argon2id_hash() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let username = format!("argonuser{}", Uuid::new_v4());
+
+    // Act
+    let response = client
+        .post(format!("{}/register_user", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": "password123",
+            "email": format!("{}@example.com", username)
+        }))
+        .send()
+        .await
+        .expect("Failed to execute registration request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Registration should succeed");
+    let saved = sqlx::query!("SELECT password_hash FROM users WHERE username = $1", username)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert!(saved.password_hash.starts_with("$argon2id$"), "Password should be hashed with Argon2id");
+}
+
+#[tokio::test]
+async fn login_with_bcrypt_hash_upgrades_to_argon2id() {
+    // Arrange - A user that registered before the switch to Argon2id
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let username = format!("bcryptuser{}", Uuid::new_v4());
+    let password = "password123";
+
+    sqlx::query!(
+        r#"
+        INSERT INTO users (id, username, password_hash, email, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        "#,
+        Uuid::new_v4(),
+        username,
+        bcrypt::hash(password, 4).unwrap(),
+        format!("{}@example.com", username),
+        Utc::now()
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert legacy user.");
+
+    // Act
+    let response = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Login with bcrypt hash should succeed");
+    let saved = sqlx::query!("SELECT password_hash FROM users WHERE username = $1", username)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert!(saved.password_hash.starts_with("$argon2id$"), "Hash should be upgraded to Argon2id");
+
+    // The upgraded hash keeps working
+    let response = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    assert_eq!(200, response.status().as_u16(), "Login with upgraded hash should succeed");
+}
+
+#[tokio::test]
+async fn login_with_wrong_password_keeps_bcrypt_hash() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let username = format!("bcryptuser{}", Uuid::new_v4());
+    let legacy_hash = bcrypt::hash("password123", 4).unwrap();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO users (id, username, password_hash, email, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        "#,
+        Uuid::new_v4(),
+        username,
+        legacy_hash,
+        format!("{}@example.com", username),
+        Utc::now()
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert legacy user.");
+
+    // Act
+    let response = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": "wrongpassword"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    let saved = sqlx::query!("SELECT password_hash FROM users WHERE username = $1", username)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert_eq!(saved.password_hash, legacy_hash, "Hash should only change after a successful login");
+}
@@ -0,0 +1,284 @@
+use reqwest::Client;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+mod common;
+use common::utils::{extract_link_param, spawn_app, spawn_app_with, TestApp};
+use areum_backend::config::settings::EmailTransport;
+
+async fn login_status(client: &Client, test_app: &TestApp, username: &str, password: &str) -> u16 {
+    client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.")
+        .status()
+        .as_u16()
+}
+
+async fn request_reset(client: &Client, test_app: &TestApp, email: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/password_reset/request", &test_app.address))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .expect("Failed to execute password reset request.")
+}
+
+async fn confirm_reset(client: &Client, test_app: &TestApp, token: &str, new_password: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/password_reset/confirm", &test_app.address))
+        .json(&json!({
+            "token": token,
+            "new_password": new_password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute password reset confirmation.")
+}
+
+/// Minimal SMTP server standing in for a real mail server.
+/// Every received message (DATA section) is forwarded through the channel.
+async fn spawn_smtp_stub() -> (u16, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind SMTP stub");
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.into_split();
+                let mut lines = BufReader::new(reader).lines();
+                writer.write_all(b"220 localhost ESMTP stub\r\n").await.unwrap();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let command = line.to_uppercase();
+                    if command.starts_with("DATA") {
+                        writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                        let mut data = String::new();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            if line == "." {
+                                break;
+                            }
+                            data.push_str(&line);
+                            data.push('\n');
+                        }
+                        sender.send(data).unwrap();
+                        writer.write_all(b"250 OK\r\n").await.unwrap();
+                    } else if command.starts_with("QUIT") {
+                        writer.write_all(b"221 Bye\r\n").await.unwrap();
+                        break;
+                    } else {
+                        writer.write_all(b"250 OK\r\n").await.unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    (port, receiver)
+}
+
+#[tokio::test]
+async fn password_reset_sets_new_password_and_revokes_sessions() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, email) = test_app.register_user().await;
+
+    let login_response = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": "password123"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    let login_json = login_response.json::<serde_json::Value>().await.unwrap();
+    let old_token = login_json["token"].as_str().unwrap();
+
+    // Make sure the old token was issued strictly before the revocation cutoff
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // Act
+    let response = request_reset(&client, &test_app, &email).await;
+    assert_eq!(200, response.status().as_u16());
+    let reset_email = test_app.last_email_to(&email);
+    let token = extract_link_param(reset_email["body"].as_str().unwrap(), "token");
+    let response = confirm_reset(&client, &test_app, &token, "newpassword456").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Password reset should succeed");
+    assert_eq!(401, login_status(&client, &test_app, &username, "password123").await);
+    assert_eq!(200, login_status(&client, &test_app, &username, "newpassword456").await);
+
+    let protected_response = client
+        .get(format!("{}/health/heart_rate_data", &test_app.address))
+        .header("Authorization", format!("Bearer {}", old_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, protected_response.status().as_u16(), "Old sessions should be revoked");
+}
+
+#[tokio::test]
+async fn password_reset_token_is_single_use() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    request_reset(&client, &test_app, &email).await;
+    let reset_email = test_app.last_email_to(&email);
+    let token = extract_link_param(reset_email["body"].as_str().unwrap(), "token");
+    let response = confirm_reset(&client, &test_app, &token, "newpassword456").await;
+    assert_eq!(200, response.status().as_u16());
+
+    // Act
+    let response = confirm_reset(&client, &test_app, &token, "anotherpassword789").await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16(), "Reset token should only work once");
+}
+
+#[tokio::test]
+async fn requesting_a_new_reset_link_invalidates_the_previous_one() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    request_reset(&client, &test_app, &email).await;
+    let first_token = extract_link_param(
+        test_app.last_email_to(&email)["body"].as_str().unwrap(),
+        "token"
+    );
+
+    // Act
+    request_reset(&client, &test_app, &email).await;
+
+    // Assert
+    let response = confirm_reset(&client, &test_app, &first_token, "newpassword456").await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn password_reset_requests_are_rate_limited() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    for _ in 0..5 {
+        request_reset(&client, &test_app, "nobody@example.com").await;
+    }
+    let response = request_reset(&client, &test_app, "nobody@example.com").await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16(), "Unregistered addresses are limited alike");
+    assert!(response.headers().contains_key("Retry-After"));
+}
+
+#[tokio::test]
+async fn password_reset_rejects_short_passwords() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    request_reset(&client, &test_app, &email).await;
+    let token = extract_link_param(
+        test_app.last_email_to(&email)["body"].as_str().unwrap(),
+        "token"
+    );
+
+    // Act
+    let response = confirm_reset(&client, &test_app, &token, "short").await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn password_reset_for_unknown_email_returns_200_without_sending_mail() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let response = request_reset(&client, &test_app, "nobody@example.com").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(test_app.sent_emails().is_empty(), "No email should be sent");
+}
+
+#[tokio::test]
+async fn password_reset_link_points_to_the_configured_page() {
+    // Arrange
+    let test_app = spawn_app_with(|config| {
+        config.application.password_reset_url = "https://app.example.com/reset".to_string();
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    request_reset(&client, &test_app, &email).await;
+
+    // Assert
+    let body = test_app.last_email_to(&email)["body"].as_str().unwrap().to_string();
+    let token = extract_link_param(&body, "token");
+    assert!(body.contains(&format!("https://app.example.com/reset?token={}", token)));
+}
+
+#[tokio::test]
+async fn password_reset_returns_200_when_the_email_cannot_be_sent() {
+    // Arrange: an outbox below a file can't be created
+    let test_app = spawn_app_with(|config| {
+        config.email.outbox_dir = "/dev/null/outbox".to_string();
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+
+    // Act
+    let response = request_reset(&client, &test_app, &email).await;
+
+    // Assert: the same answer as for unknown addresses
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn password_reset_email_is_delivered_over_smtp() {
+    // Arrange
+    let (smtp_port, mut received) = spawn_smtp_stub().await;
+    let test_app = spawn_app_with(|config| {
+        config.email.transport = EmailTransport::Smtp;
+        config.email.smtp_host = "127.0.0.1".to_string();
+        config.email.smtp_port = smtp_port;
+        config.email.smtp_tls = false;
+    }).await;
+    let client = Client::new();
+    let (_, email) = test_app.register_user().await;
+    // Skip the verification email sent on registration
+    tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+        .await
+        .expect("Timed out waiting for SMTP delivery")
+        .expect("SMTP stub stopped");
+
+    // Act
+    let response = request_reset(&client, &test_app, &email).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let message = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+        .await
+        .expect("Timed out waiting for SMTP delivery")
+        .expect("SMTP stub stopped");
+    assert!(message.contains(&format!("To: {}", email)), "Message should be addressed to the user");
+    assert!(message.contains("Subject: Reset your Areum password"));
+}
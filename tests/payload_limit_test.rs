@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use reqwest::Client;
+use serde_json::{json, Value};
+
+mod common;
+use common::utils::{spawn_app, spawn_app_with, TestApp};
+
+fn acceleration_upload(samples: usize) -> Value {
+    let samples: Vec<Value> = (0..samples)
+        .map(|i| json!({"timestamp": "2025-03-10T14:27:31.850Z", "x": 0.012, "y": -0.043, "z": 0.971 + i as f64 * 0.001}))
+        .collect();
+    json!({
+        "data_type": "acceleration",
+        "device_info": {
+            "device_type": "smartphone",
+            "model": "iPhone 14",
+            "os_version": "iOS 16.5"
+        },
+        "sampling_rate_hz": 50,
+        "start_time": "2025-03-10T14:27:31.850Z",
+        "end_time": "2025-03-10T14:32:31.850Z",
+        "samples": samples
+    })
+}
+
+fn compress(encoding: &str, body: &[u8]) -> Vec<u8> {
+    match encoding {
+        "gzip" => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body).unwrap();
+            encoder.finish().unwrap()
+        }
+        "br" => {
+            let mut compressed = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+                encoder.write_all(body).unwrap();
+            }
+            compressed
+        }
+        _ => zstd::encode_all(body, 3).unwrap(),
+    }
+}
+
+async fn post_body(test_app: &TestApp, token: &str, encoding: Option<&str>, body: Vec<u8>) -> reqwest::Response {
+    let mut request = Client::new()
+        .post(format!("{}/health/upload_acceleration", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json");
+    if let Some(encoding) = encoding {
+        request = request.header("Content-Encoding", encoding);
+    }
+    request.body(body).send().await.expect("Failed to execute upload request.")
+}
+
+#[tokio::test]
+async fn minutes_of_50_hz_acceleration_data_can_be_uploaded() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Five minutes at 50 Hz
+    let body = serde_json::to_vec(&acceleration_upload(15_000)).unwrap();
+    assert!(body.len() > 1024 * 1024);
+    let response = post_body(&test_app, &token, None, body).await;
+
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oversized_upload_returns_structured_413() {
+    let test_app = spawn_app_with(|config| {
+        config.application.payload_limits.sensor_upload_bytes = 10_000;
+    }).await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let body = serde_json::to_vec(&acceleration_upload(500)).unwrap();
+    let response = post_body(&test_app, &token, None, body).await;
+
+    assert_eq!(413, response.status().as_u16());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["max_size_bytes"], 10_000);
+
+    // The other endpoints have their own limit
+    let response = Client::new()
+        .post(format!("{}/login", &test_app.address))
+        .header("Content-Type", "application/json")
+        .body(vec![b' '; 100 * 1024])
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    assert_eq!(413, response.status().as_u16());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["max_size_bytes"], 64 * 1024);
+}
+
+#[tokio::test]
+async fn compressed_uploads_are_accepted() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    let body = serde_json::to_vec(&acceleration_upload(100)).unwrap();
+
+    for encoding in ["gzip", "br", "zstd"] {
+        let response = post_body(&test_app, &token, Some(encoding), compress(encoding, &body)).await;
+        assert_eq!(200, response.status().as_u16(), "{} upload should succeed", encoding);
+    }
+
+    let stored = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM health_data")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(3, stored);
+
+    let response = post_body(&test_app, &token, Some("compress"), body).await;
+    assert_eq!(415, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn decompression_bomb_is_rejected() {
+    let test_app = spawn_app_with(|config| {
+        config.application.payload_limits.sensor_upload_bytes = 1024 * 1024;
+    }).await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // 100 MB of spaces compress to about 100 KB
+    let bomb = compress("gzip", &vec![b' '; 100 * 1024 * 1024]);
+    assert!(bomb.len() < 1024 * 1024);
+    let response = post_body(&test_app, &token, Some("gzip"), bomb).await;
+
+    assert_eq!(413, response.status().as_u16());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["max_size_bytes"], 1024 * 1024);
+}
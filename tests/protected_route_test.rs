@@ -77,4 +77,52 @@ async fn protected_route_returns_200_with_valid_token() {
 
     // Assert
     assert_eq!(200, protected_response.status().as_u16());
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn protected_route_returns_the_authenticated_user() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let response = client
+        .get(format!("{}/protected/resource", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(user_id.to_string(), body["user_id"]);
+    assert!(body["username"].as_str().unwrap().starts_with("roleuser"));
+}
+
+#[tokio::test]
+async fn protected_route_rejects_revoked_tokens() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    let logout_response = client
+        .post(format!("{}/logout", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+    assert_eq!(200, logout_response.status().as_u16());
+
+    // Act
+    let response = client
+        .get(format!("{}/protected/resource", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,178 @@
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::spawn_app;
+
+fn roles_claim(token: &str) -> Vec<String> {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    let claims = decode::<serde_json::Value>(token, &DecodingKey::from_secret(&[]), &validation)
+        .expect("Failed to decode token");
+    serde_json::from_value(claims.claims["roles"].clone()).unwrap()
+}
+
+#[tokio::test]
+async fn admin_endpoints_require_the_admin_role() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, user_token) = test_app.create_user_with_roles(&[]).await;
+    let (_, clinician_token) = test_app.create_user_with_roles(&["clinician"]).await;
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let url = format!("{}/admin/lockouts", &test_app.address);
+
+    // Act
+    let anonymous = client.get(&url).send().await.expect("Failed to execute request.");
+    let user = client.get(&url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send().await.expect("Failed to execute request.");
+    let clinician = client.get(&url)
+        .header("Authorization", format!("Bearer {}", clinician_token))
+        .send().await.expect("Failed to execute request.");
+    let admin = client.get(&url)
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send().await.expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, anonymous.status().as_u16());
+    assert_eq!(403, user.status().as_u16());
+    assert_eq!(403, clinician.status().as_u16());
+    assert_eq!(200, admin.status().as_u16());
+}
+
+#[tokio::test]
+async fn granted_roles_are_carried_in_new_tokens() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let (user_id, user_token) = test_app.create_user_with_roles(&[]).await;
+    assert!(roles_claim(&user_token).is_empty());
+    assert_eq!(vec!["admin".to_string()], roles_claim(&admin_token));
+
+    // Act
+    let response = client
+        .put(format!("{}/admin/users/{}/roles/clinician", &test_app.address, user_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let roles_json = client
+        .get(format!("{}/admin/users/{}/roles", &test_app.address, user_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(json!(["clinician"]), roles_json["roles"]);
+
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    let login_json = client
+        .post(format!("{}/login", &test_app.address))
+        .json(&json!({
+            "username": username,
+            "password": "password123"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(vec!["clinician".to_string()], roles_claim(login_json["token"].as_str().unwrap()));
+}
+
+#[tokio::test]
+async fn revoking_a_role_revokes_tokens_carrying_it() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let (other_admin_id, other_admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+
+    // Make sure the token was issued strictly before the revocation cutoff
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // Act
+    let response = client
+        .delete(format!("{}/admin/users/{}/roles/admin", &test_app.address, other_admin_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let response = client
+        .get(format!("{}/admin/lockouts", &test_app.address))
+        .header("Authorization", format!("Bearer {}", other_admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn role_changes_are_audited() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (admin_id, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let (user_id, _) = test_app.create_user_with_roles(&[]).await;
+    let url = format!("{}/admin/users/{}/roles/clinician", &test_app.address, user_id);
+
+    // Act
+    for _ in 0..2 {
+        client.put(&url)
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .send().await.expect("Failed to execute request.");
+    }
+    client.delete(&url)
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send().await.expect("Failed to execute request.");
+
+    // Assert
+    let events = sqlx::query_as::<_, (String, Option<Uuid>, serde_json::Value)>(
+        "SELECT event_type, actor_id, details FROM audit_events WHERE user_id = $1 AND event_type LIKE 'role_%' ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(&test_app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(vec![
+        ("role_granted".to_string(), Some(admin_id), json!({ "role": "clinician" })),
+        ("role_revoked".to_string(), Some(admin_id), json!({ "role": "clinician" })),
+    ], events);
+}
+
+#[tokio::test]
+async fn granting_an_unknown_role_returns_400() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let (user_id, _) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let response = client
+        .put(format!("{}/admin/users/{}/roles/superuser", &test_app.address, user_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,107 @@
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, TestApp};
+
+async fn refresh(client: &Client, test_app: &TestApp, refresh_token: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": refresh_token }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.")
+}
+
+#[tokio::test]
+async fn login_returns_access_and_refresh_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+
+    // Act
+    let (_, login_json) = test_app.register_and_login().await;
+
+    // Assert
+    assert!(login_json["token"].is_string(), "Response should contain an access token");
+    assert!(login_json["refresh_token"].is_string(), "Response should contain a refresh token");
+    assert!(login_json["expires_in"].as_i64().unwrap() > 0, "Response should contain the token lifetime");
+
+    // The refresh token must only be stored hashed
+    let refresh_token = login_json["refresh_token"].as_str().unwrap();
+    let stored = sqlx::query!(
+        "SELECT COUNT(*) as count FROM refresh_tokens WHERE token_hash = $1",
+        refresh_token
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to query refresh tokens.");
+    assert_eq!(stored.count, Some(0), "Plain refresh token should not be stored");
+}
+
+#[tokio::test]
+async fn refresh_rotates_the_refresh_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let first_refresh_token = login_json["refresh_token"].as_str().unwrap();
+
+    // Act
+    let response = refresh(&client, &test_app, first_refresh_token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16(), "Refresh should succeed");
+    let refresh_json = response.json::<serde_json::Value>().await
+        .expect("Failed to parse refresh response as JSON");
+    let second_refresh_token = refresh_json["refresh_token"].as_str().unwrap();
+    assert_ne!(first_refresh_token, second_refresh_token, "Refresh token should be rotated");
+
+    // The new access token is accepted by protected endpoints
+    let protected_response = client
+        .get(format!("{}/health/heart_rate_data", &test_app.address))
+        .header("Authorization", format!("Bearer {}", refresh_json["token"].as_str().unwrap()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, protected_response.status().as_u16());
+
+    // The new refresh token can be used in turn
+    let response = refresh(&client, &test_app, second_refresh_token).await;
+    assert_eq!(200, response.status().as_u16(), "Rotated token should be usable");
+}
+
+#[tokio::test]
+async fn reusing_a_rotated_refresh_token_revokes_the_family() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, login_json) = test_app.register_and_login().await;
+    let first_refresh_token = login_json["refresh_token"].as_str().unwrap();
+
+    let response = refresh(&client, &test_app, first_refresh_token).await;
+    assert_eq!(200, response.status().as_u16(), "Refresh should succeed");
+    let refresh_json = response.json::<serde_json::Value>().await
+        .expect("Failed to parse refresh response as JSON");
+    let second_refresh_token = refresh_json["refresh_token"].as_str().unwrap();
+
+    // Act - Replay the already used token
+    let replay_response = refresh(&client, &test_app, first_refresh_token).await;
+
+    // Assert
+    assert_eq!(401, replay_response.status().as_u16(), "Replayed token should be rejected");
+    let response = refresh(&client, &test_app, second_refresh_token).await;
+    assert_eq!(401, response.status().as_u16(), "Whole family should be revoked");
+}
+
+#[tokio::test]
+async fn refresh_returns_401_for_unknown_token() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let response = refresh(&client, &test_app, "not-a-real-token").await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
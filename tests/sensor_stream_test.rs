@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+mod common;
+use common::utils::{spawn_app, TestApp};
+
+fn upload(data_type: &str, samples: Value) -> Value {
+    json!({
+        "data_type": data_type,
+        "device_info": {
+            "device_type": "smartwatch",
+            "model": "AppleWatch Series 8",
+            "os_version": "watchOS 10.1"
+        },
+        "sampling_rate_hz": 1,
+        "start_time": "2025-03-10T12:00:00Z",
+        "end_time": "2025-03-10T12:00:10Z",
+        "samples": samples
+    })
+}
+
+async fn post_upload(test_app: &TestApp, token: &str, endpoint: &str, body: &Value) -> reqwest::Response {
+    Client::new()
+        .post(format!("{}/health/upload_{}", &test_app.address, endpoint))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute upload request.")
+}
+
+#[tokio::test]
+async fn upload_with_data_type_of_another_stream_is_rejected() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let body = upload("heart_rate", json!([
+        {"timestamp": "2025-03-10T12:00:00Z", "spo2": 97.5, "confidence": 0.9}
+    ]));
+    let response = post_upload(&test_app, &token, "blood_oxygen", &body).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["message"], "Invalid data type. Expected 'blood_oxygen'.");
+}
+
+#[tokio::test]
+async fn upload_with_out_of_range_samples_is_rejected() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let cases = [
+        ("heart_rate", json!([{"timestamp": "2025-03-10T12:00:00Z", "heart_rate": 0}])),
+        ("heart_rate", json!([{"timestamp": "2025-03-10T12:00:00Z", "heart_rate": 72, "confidence": 1.5}])),
+        ("blood_oxygen", json!([{"timestamp": "2025-03-10T12:00:00Z", "spo2": 120.0}])),
+        ("skin_temperature", json!([{"timestamp": "2025-03-10T12:00:00Z", "temperature": 80.0}])),
+        ("gps_location", json!([{"timestamp": "2025-03-10T12:00:00Z", "latitude": 95.0, "longitude": 8.5}])),
+    ];
+    for (data_type, samples) in cases {
+        let response = post_upload(&test_app, &token, data_type, &upload(data_type, samples)).await;
+        assert_eq!(400, response.status().as_u16(), "{} upload should be rejected", data_type);
+    }
+
+    let stored = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM health_data")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(0, stored);
+}
+
+#[tokio::test]
+async fn upload_with_end_time_before_start_time_is_rejected() {
+    let test_app = spawn_app().await;
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    let mut body = upload("acceleration", json!([
+        {"timestamp": "2025-03-10T12:00:00Z", "x": 0.1, "y": 0.2, "z": 9.8}
+    ]));
+    body["end_time"] = json!("2025-03-10T11:00:00Z");
+    let response = post_upload(&test_app, &token, "acceleration", &body).await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn end_time_is_taken_from_last_sample_when_equal_to_start_time() {
+    let test_app = spawn_app().await;
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+
+    let mut body = upload("skin_temperature", json!([
+        {"timestamp": "2025-03-10T12:00:00Z", "temperature": 33.1},
+        {"timestamp": "2025-03-10T12:00:30Z", "temperature": 33.4}
+    ]));
+    body["end_time"] = body["start_time"].clone();
+    let response = post_upload(&test_app, &token, "skin_temperature", &body).await;
+    assert_eq!(200, response.status().as_u16());
+    let response_body: Value = response.json().await.unwrap();
+    assert_eq!(response_body["message"], "Skin temperature data uploaded successfully");
+
+    let end_time = sqlx::query_scalar::<_, DateTime<Utc>>("SELECT end_time FROM health_data WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!("2025-03-10T12:00:30Z".parse::<DateTime<Utc>>().unwrap(), end_time);
+}
@@ -0,0 +1,174 @@
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, TestApp};
+
+async fn login(client: &Client, test_app: &TestApp, username: &str, device_name: &str) -> serde_json::Value {
+    let response = client
+        .post(format!("{}/login", &test_app.address))
+        .header("User-Agent", "AreumApp/2.1 (iPhone; iOS 17.4)")
+        .json(&json!({
+            "username": username,
+            "password": "password123",
+            "device_name": device_name
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request.");
+    assert_eq!(200, response.status().as_u16(), "Login should succeed");
+
+    response.json::<serde_json::Value>().await
+        .expect("Failed to parse login response as JSON")
+}
+
+async fn list_sessions(client: &Client, test_app: &TestApp, token: &str) -> reqwest::Response {
+    client
+        .get(format!("{}/sessions", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn terminate_session(client: &Client, test_app: &TestApp, token: &str, session_id: &str) -> reqwest::Response {
+    client
+        .delete(format!("{}/sessions/{}", &test_app.address, session_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn sessions_are_listed_with_their_device_details() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let phone = login(&client, &test_app, &username, "My phone").await;
+    login(&client, &test_app, &username, "My tablet").await;
+
+    // Act
+    let response = list_sessions(&client, &test_app, phone["token"].as_str().unwrap()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let sessions = response.json::<Vec<serde_json::Value>>().await.unwrap();
+    assert_eq!(2, sessions.len());
+    let current: Vec<_> = sessions.iter().filter(|session| session["current"] == true).collect();
+    assert_eq!(1, current.len(), "Exactly one session should be the current one");
+    assert_eq!("My phone", current[0]["device_name"]);
+    assert_eq!("AreumApp/2.1 (iPhone; iOS 17.4)", current[0]["user_agent"]);
+    assert!(current[0]["ip_address"].is_string());
+    assert!(current[0]["created_at"].is_string());
+    assert!(current[0]["last_seen_at"].is_string());
+}
+
+#[tokio::test]
+async fn terminated_session_is_signed_out() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let phone = login(&client, &test_app, &username, "My phone").await;
+    let lost_tablet = login(&client, &test_app, &username, "Lost tablet").await;
+    let phone_token = phone["token"].as_str().unwrap();
+    let sessions = list_sessions(&client, &test_app, phone_token).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let tablet_session = sessions.iter().find(|session| session["device_name"] == "Lost tablet").unwrap();
+
+    // Act
+    let response = terminate_session(&client, &test_app, phone_token, tablet_session["id"].as_str().unwrap()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let tablet_response = list_sessions(&client, &test_app, lost_tablet["token"].as_str().unwrap()).await;
+    assert_eq!(401, tablet_response.status().as_u16(), "The session's access token should be rejected");
+    let tablet_refresh = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": lost_tablet["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(401, tablet_refresh.status().as_u16(), "The session's refresh token should be revoked");
+
+    let remaining = list_sessions(&client, &test_app, phone_token).await;
+    assert_eq!(200, remaining.status().as_u16(), "Other sessions should keep working");
+    let remaining = remaining.json::<Vec<serde_json::Value>>().await.unwrap();
+    assert_eq!(1, remaining.len());
+    assert_eq!("My phone", remaining[0]["device_name"]);
+}
+
+#[tokio::test]
+async fn sessions_of_other_users_cannot_be_terminated() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let (other_username, _) = test_app.register_user().await;
+    let token = login(&client, &test_app, &username, "My phone").await["token"].as_str().unwrap().to_string();
+    let other = login(&client, &test_app, &other_username, "Their phone").await;
+    let other_token = other["token"].as_str().unwrap();
+    let other_sessions = list_sessions(&client, &test_app, other_token).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Act
+    let other_user_session = terminate_session(&client, &test_app, &token, other_sessions[0]["id"].as_str().unwrap()).await;
+    let unknown_session = terminate_session(&client, &test_app, &token, &Uuid::new_v4().to_string()).await;
+
+    // Assert
+    assert_eq!(404, other_user_session.status().as_u16());
+    assert_eq!(404, unknown_session.status().as_u16());
+    assert_eq!(200, list_sessions(&client, &test_app, other_token).await.status().as_u16());
+}
+
+#[tokio::test]
+async fn refreshing_keeps_the_session_and_logout_ends_it() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (username, _) = test_app.register_user().await;
+    let phone = login(&client, &test_app, &username, "My phone").await;
+    let tablet = login(&client, &test_app, &username, "My tablet").await;
+
+    // Act
+    let refreshed = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": phone["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let refreshed_token = refreshed["token"].as_str().unwrap();
+    let logout = client
+        .post(format!("{}/logout", &test_app.address))
+        .header("Authorization", format!("Bearer {}", tablet["token"].as_str().unwrap()))
+        .send()
+        .await
+        .expect("Failed to execute logout request.");
+
+    // Assert
+    assert_eq!(200, logout.status().as_u16());
+    let sessions = list_sessions(&client, &test_app, refreshed_token).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    assert_eq!(1, sessions.len(), "The logged out session should be gone");
+    assert_eq!("My phone", sessions[0]["device_name"]);
+    assert_eq!(true, sessions[0]["current"], "A refreshed token belongs to the same session");
+    let tablet_refresh = client
+        .post(format!("{}/refresh", &test_app.address))
+        .json(&json!({ "refresh_token": tablet["refresh_token"] }))
+        .send()
+        .await
+        .expect("Failed to execute refresh request.");
+    assert_eq!(401, tablet_refresh.status().as_u16());
+}
@@ -0,0 +1,262 @@
+use chrono::{Duration, NaiveDate, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, TestApp};
+
+async fn upload_blood_oxygen(client: &Client, test_app: &TestApp, token: &str, start_time: &str, model: &str) {
+    let response = client
+        .post(format!("{}/health/upload_blood_oxygen", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "data_type": "blood_oxygen",
+            "device_info": {
+                "device_type": "smartwatch",
+                "model": model,
+                "os_version": "watchOS 10.1"
+            },
+            "sampling_rate_hz": 1,
+            "start_time": start_time,
+            "end_time": start_time,
+            "samples": [
+                {"timestamp": start_time, "spo2": 97.5, "confidence": 0.95}
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute upload request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+async fn insert_sleep_summary(test_app: &TestApp, user_id: Uuid, night_date: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO processed_sleep_data (id, user_id, data_type, night_date, data, created_at)
+        VALUES ($1, $2, 'sleep_summary', $3, $4, $5)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(NaiveDate::parse_from_str(night_date, "%Y-%m-%d").unwrap())
+    .bind(json!({ "sleep_score": 82, "overall_quality": "good" }))
+    .bind(Utc::now())
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert sleep summary");
+}
+
+async fn create_share_link(client: &Client, test_app: &TestApp, token: &str, body: serde_json::Value) -> reqwest::Response {
+    client
+        .post(format!("{}/share_links", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn march_link(data_types: &[&str]) -> serde_json::Value {
+    json!({
+        "data_types": data_types,
+        "start_date": "2025-03-01",
+        "end_date": "2025-03-30",
+        "expires_at": Utc::now() + Duration::days(7)
+    })
+}
+
+/// The URL of a created link on the test server, `url` uses the configured base URL.
+fn shared_url(test_app: &TestApp, created: &serde_json::Value) -> String {
+    format!("{}/shared/{}", &test_app.address, created["token"].as_str().unwrap())
+}
+
+async fn view(client: &Client, url: &str) -> reqwest::Response {
+    client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn share_link_shows_the_shared_period_without_an_account() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (user_id, token) = test_app.create_user_with_roles(&[]).await;
+    upload_blood_oxygen(&client, &test_app, &token, "2025-03-10T23:00:00Z", "AppleWatch Series 8").await;
+    upload_blood_oxygen(&client, &test_app, &token, "2025-04-02T23:00:00Z", "AppleWatch Series 8").await;
+    insert_sleep_summary(&test_app, user_id, "2025-03-10").await;
+    insert_sleep_summary(&test_app, user_id, "2025-02-10").await;
+    let created = create_share_link(&client, &test_app, &token, march_link(&["sleep", "blood_oxygen"])).await;
+    assert_eq!(201, created.status().as_u16());
+    let created = created.json::<serde_json::Value>().await.unwrap();
+
+    // Act
+    let response = view(&client, &shared_url(&test_app, &created)).await;
+
+    // Assert
+    assert!(created["url"].as_str().unwrap().ends_with(created["token"].as_str().unwrap()));
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("no-store", response.headers()["cache-control"]);
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json!(["blood_oxygen", "sleep"]), data["data_types"]);
+    assert_eq!(1, data["health_data"].as_array().unwrap().len(), "Only records in the period");
+    assert_eq!("2025-03-10T23:00:00Z", data["health_data"][0]["start_time"]);
+    assert_eq!(1, data["sleep_summaries"].as_array().unwrap().len());
+    assert_eq!(82, data["sleep_summaries"][0]["data"]["sleep_score"]);
+    let links = client
+        .get(format!("{}/share_links", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    assert_eq!(1, links[0]["access_count"]);
+    assert!(links[0].get("token").is_none(), "Tokens are only shown once");
+    let events = client
+        .get(format!("{}/account/audit?event_type=share_link_accessed", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    assert_eq!(1, events.len());
+    assert!(events[0]["actor_id"].is_null());
+    assert!(events[0]["ip_address"].is_string());
+    assert_eq!(created["id"], events[0]["details"]["share_link_id"]);
+}
+
+#[tokio::test]
+async fn share_link_renders_escaped_html_for_browsers() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    upload_blood_oxygen(&client, &test_app, &token, "2025-03-10T23:00:00Z", "<script>alert(1)</script>").await;
+    let created = create_share_link(&client, &test_app, &token, march_link(&["blood_oxygen", "heart_rate"])).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let url = shared_url(&test_app, &created);
+
+    // Act
+    let browser = client
+        .get(&url)
+        .header("Accept", "text/html,application/xhtml+xml")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let explicit = view(&client, &format!("{}?format=html", url)).await;
+    let unknown_format = view(&client, &format!("{}?format=pdf", url)).await;
+
+    // Assert
+    assert_eq!(200, browser.status().as_u16());
+    assert!(browser.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    assert!(browser.headers().contains_key("content-security-policy"));
+    let page = browser.text().await.unwrap();
+    assert!(page.contains("blood oxygen"));
+    assert!(!page.contains("<script>"), "Stored values are escaped");
+    assert!(page.contains("&lt;script&gt;"));
+    assert_eq!(200, explicit.status().as_u16());
+    assert_eq!(400, unknown_format.status().as_u16());
+}
+
+#[tokio::test]
+async fn revoked_and_expired_share_links_stop_working() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+    let (_, other_token) = test_app.create_user_with_roles(&[]).await;
+    let revoked = create_share_link(&client, &test_app, &token, march_link(&["sleep"])).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let expired = create_share_link(&client, &test_app, &token, march_link(&["sleep"])).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    sqlx::query("UPDATE share_links SET expires_at = $1 WHERE id = $2")
+        .bind(Utc::now())
+        .bind(Uuid::parse_str(expired["id"].as_str().unwrap()).unwrap())
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    let revoke_url = format!("{}/share_links/{}", &test_app.address, revoked["id"].as_str().unwrap());
+
+    // Act
+    let by_other_user = client
+        .delete(&revoke_url)
+        .header("Authorization", format!("Bearer {}", other_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let before_revoking = view(&client, &shared_url(&test_app, &revoked)).await;
+    let revoke_response = client
+        .delete(&revoke_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let after_revoking = view(&client, &shared_url(&test_app, &revoked)).await;
+    let after_expiry = view(&client, &shared_url(&test_app, &expired)).await;
+    let unknown = view(&client, &format!("{}/shared/not-a-real-token", &test_app.address)).await;
+
+    // Assert
+    assert_eq!(404, by_other_user.status().as_u16());
+    assert_eq!(200, before_revoking.status().as_u16());
+    assert_eq!(200, revoke_response.status().as_u16());
+    assert_eq!(404, after_revoking.status().as_u16());
+    assert_eq!(404, after_expiry.status().as_u16());
+    assert_eq!(404, unknown.status().as_u16());
+}
+
+#[tokio::test]
+async fn share_link_requests_are_validated() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let unknown_type = create_share_link(&client, &test_app, &token, march_link(&["diary"])).await;
+    let no_types = create_share_link(&client, &test_app, &token, march_link(&[])).await;
+    let reversed_dates = create_share_link(&client, &test_app, &token, json!({
+        "data_types": ["sleep"],
+        "start_date": "2025-03-30",
+        "end_date": "2025-03-01",
+        "expires_at": Utc::now() + Duration::days(7)
+    })).await;
+    let past_expiry = create_share_link(&client, &test_app, &token, json!({
+        "data_types": ["sleep"],
+        "start_date": "2025-03-01",
+        "end_date": "2025-03-30",
+        "expires_at": Utc::now() - Duration::days(1)
+    })).await;
+    let too_long = create_share_link(&client, &test_app, &token, json!({
+        "data_types": ["sleep"],
+        "start_date": "2025-03-01",
+        "end_date": "2025-03-30",
+        "expires_at": Utc::now() + Duration::days(365)
+    })).await;
+    let unauthenticated = client
+        .post(format!("{}/share_links", &test_app.address))
+        .json(&march_link(&["sleep"]))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(400, unknown_type.status().as_u16());
+    assert_eq!(400, no_types.status().as_u16());
+    assert_eq!(400, reversed_dates.status().as_u16());
+    assert_eq!(400, past_expiry.status().as_u16());
+    assert_eq!(400, too_long.status().as_u16());
+    assert_eq!(401, unauthenticated.status().as_u16());
+}
@@ -0,0 +1,293 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{sensor_upload, spawn_app, TestApp};
+
+async fn set_permissions(client: &Client, test_app: &TestApp, token: &str, heart_rate_enabled: bool) {
+    let response = client
+        .post(format!("{}/onboarding/permissions_setup", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "heart_rate_enabled": heart_rate_enabled,
+            "temperature_enabled": false,
+            "spo2_enabled": true,
+            "accelerometer_enabled": false,
+            "notifications_enabled": false,
+            "background_usage_enabled": false,
+            "third_party_connections": []
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+async fn upload_heart_rate(client: &Client, test_app: &TestApp, token: &str, start_time: DateTime<Utc>) {
+    let mut upload = sensor_upload("heart_rate", json!([
+        {"timestamp": start_time, "heart_rate": 72, "confidence": 0.95},
+        {"timestamp": start_time, "heart_rate": 74, "confidence": 0.95}
+    ]));
+    upload["start_time"] = json!(start_time);
+    upload["end_time"] = json!(start_time);
+
+    let response = client
+        .post(format!("{}/health/upload_heart_rate", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&upload)
+        .send()
+        .await
+        .expect("Failed to execute upload request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+/// Creates an organization through a platform admin and returns its id and
+/// a token of its admin.
+async fn create_organization(client: &Client, test_app: &TestApp) -> (String, String) {
+    let (_, platform_admin_token) = test_app.create_user_with_roles(&["admin"]).await;
+    let (organization_admin_id, organization_admin_token) = test_app.create_user_with_roles(&[]).await;
+    let response = client
+        .post(format!("{}/admin/organizations", &test_app.address))
+        .header("Authorization", format!("Bearer {}", platform_admin_token))
+        .json(&json!({
+            "name": format!("Clinic {}", Uuid::new_v4()),
+            "admin_username": test_app.username_of(organization_admin_id).await
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(201, response.status().as_u16());
+    let organization = response.json::<serde_json::Value>().await.unwrap();
+    (organization["id"].as_str().unwrap().to_string(), organization_admin_token)
+}
+
+async fn create_study(client: &Client, test_app: &TestApp, organization_id: &str, token: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/organizations/{}/studies", &test_app.address, organization_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Sleep apnea pilot",
+            "data_types": ["heart_rate", "blood_oxygen"],
+            "start_date": (Utc::now() - Duration::days(30)).date_naive(),
+            "end_date": (Utc::now() + Duration::days(30)).date_naive()
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn enroll(client: &Client, test_app: &TestApp, token: &str, invite_code: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/studies/enroll", &test_app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "invite_code": invite_code }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get(client: &Client, url: String, token: &str) -> reqwest::Response {
+    client
+        .get(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn study_admins_read_participant_data_within_the_study_window() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (organization_id, admin_token) = create_organization(&client, &test_app).await;
+    let study = create_study(&client, &test_app, &organization_id, &admin_token).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let study_id = study["id"].as_str().unwrap();
+    let (participant_id, participant_token) = test_app.create_user_with_roles(&[]).await;
+    set_permissions(&client, &test_app, &participant_token, true).await;
+    upload_heart_rate(&client, &test_app, &participant_token, Utc::now() - Duration::days(10)).await;
+    upload_heart_rate(&client, &test_app, &participant_token, Utc::now() - Duration::days(60)).await;
+    // Codes are accepted in lower case too
+    let invite_code = study["invite_code"].as_str().unwrap().to_lowercase();
+    let enroll_response = enroll(&client, &test_app, &participant_token, &invite_code).await;
+
+    // Act
+    let participants = get(&client, format!("{}/studies/{}/participants", &test_app.address, study_id), &admin_token).await;
+    let aggregate = get(&client, format!("{}/studies/{}/aggregate", &test_app.address, study_id), &admin_token).await;
+    let participant_data = get(
+        &client,
+        format!("{}/studies/{}/participants/{}/data", &test_app.address, study_id, participant_id),
+        &admin_token
+    ).await;
+
+    // Assert
+    assert_eq!(201, enroll_response.status().as_u16());
+    let participants = participants.json::<Vec<serde_json::Value>>().await.unwrap();
+    assert_eq!(1, participants.len());
+    assert_eq!(json!(["blood_oxygen", "heart_rate"]), participants[0]["consented_data_types"]);
+    let aggregate = aggregate.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(1, aggregate["participant_count"]);
+    let heart_rate = aggregate["data_types"].as_array().unwrap().iter()
+        .find(|entry| entry["data_type"] == "heart_rate")
+        .unwrap();
+    assert_eq!(1, heart_rate["record_count"], "Only uploads in the study window");
+    assert_eq!(2, heart_rate["sample_count"]);
+    assert_eq!(200, participant_data.status().as_u16());
+    let participant_data = participant_data.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(1, participant_data["health_data"].as_array().unwrap().len());
+    assert_eq!(json!(["blood_oxygen", "heart_rate"]), participant_data["data_types"]);
+    let events = get(
+        &client,
+        format!("{}/account/audit?event_type=health_data_accessed", &test_app.address),
+        &participant_token
+    ).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(study_id, events[0]["details"]["study_id"]);
+}
+
+#[tokio::test]
+async fn enrollment_requires_a_valid_code_and_matching_permissions() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (organization_id, admin_token) = create_organization(&client, &test_app).await;
+    let study = create_study(&client, &test_app, &organization_id, &admin_token).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let invite_code = study["invite_code"].as_str().unwrap();
+    let (_, token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let without_permissions = enroll(&client, &test_app, &token, invite_code).await;
+    set_permissions(&client, &test_app, &token, false).await;
+    let missing_heart_rate = enroll(&client, &test_app, &token, invite_code).await;
+    set_permissions(&client, &test_app, &token, true).await;
+    let unknown_code = enroll(&client, &test_app, &token, "NOTACODE").await;
+    let enrolled = enroll(&client, &test_app, &token, invite_code).await;
+    let again = enroll(&client, &test_app, &token, invite_code).await;
+    let enrollments = get(&client, format!("{}/studies/enrollments", &test_app.address), &token).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(400, without_permissions.status().as_u16());
+    assert_eq!(400, missing_heart_rate.status().as_u16());
+    let missing_heart_rate = missing_heart_rate.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json!(["heart_rate"]), missing_heart_rate["missing_permissions"]);
+    assert_eq!(404, unknown_code.status().as_u16());
+    assert_eq!(201, enrolled.status().as_u16());
+    assert_eq!(409, again.status().as_u16());
+    assert_eq!(1, enrollments.len());
+    assert_eq!("Sleep apnea pilot", enrollments[0]["study_name"]);
+}
+
+#[tokio::test]
+async fn withdrawing_or_disabling_permissions_ends_access() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (organization_id, admin_token) = create_organization(&client, &test_app).await;
+    let study = create_study(&client, &test_app, &organization_id, &admin_token).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let study_id = study["id"].as_str().unwrap();
+    let invite_code = study["invite_code"].as_str().unwrap();
+    let (withdrawing_id, withdrawing_token) = test_app.create_user_with_roles(&[]).await;
+    let (restricting_id, restricting_token) = test_app.create_user_with_roles(&[]).await;
+    for token in [&withdrawing_token, &restricting_token] {
+        set_permissions(&client, &test_app, token, true).await;
+        assert_eq!(201, enroll(&client, &test_app, token, invite_code).await.status().as_u16());
+    }
+    let data_url = |user_id: Uuid| format!("{}/studies/{}/participants/{}/data", &test_app.address, study_id, user_id);
+
+    // Act
+    let withdraw_response = client
+        .delete(format!("{}/studies/{}/enrollment", &test_app.address, study_id))
+        .header("Authorization", format!("Bearer {}", withdrawing_token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    set_permissions(&client, &test_app, &restricting_token, false).await;
+    let withdrawn_data = get(&client, data_url(withdrawing_id), &admin_token).await;
+    let restricted_data = get(&client, data_url(restricting_id), &admin_token).await;
+    let rejoined = enroll(&client, &test_app, &withdrawing_token, invite_code).await;
+
+    // Assert
+    assert_eq!(200, withdraw_response.status().as_u16());
+    assert_eq!(404, withdrawn_data.status().as_u16());
+    assert_eq!(200, restricted_data.status().as_u16());
+    let restricted_data = restricted_data.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json!(["blood_oxygen"]), restricted_data["data_types"], "Heart rate consent was taken back");
+    assert_eq!(201, rejoined.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_organization_admins_manage_and_read_studies() {
+    // Arrange
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (organization_id, admin_token) = create_organization(&client, &test_app).await;
+    let study = create_study(&client, &test_app, &organization_id, &admin_token).await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let study_id = study["id"].as_str().unwrap();
+    let (outsider_id, outsider_token) = test_app.create_user_with_roles(&[]).await;
+
+    // Act
+    let organization_by_non_admin = client
+        .post(format!("{}/admin/organizations", &test_app.address))
+        .header("Authorization", format!("Bearer {}", outsider_token))
+        .json(&json!({ "name": "Rogue clinic", "admin_username": test_app.username_of(outsider_id).await }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let study_by_outsider = create_study(&client, &test_app, &organization_id, &outsider_token).await;
+    let aggregate_by_outsider = get(&client, format!("{}/studies/{}/aggregate", &test_app.address, study_id), &outsider_token).await;
+    let unknown_study = get(&client, format!("{}/studies/{}/aggregate", &test_app.address, Uuid::new_v4()), &admin_token).await;
+    let add_admin = client
+        .post(format!("{}/organizations/{}/admins", &test_app.address, organization_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .json(&json!({ "username": test_app.username_of(outsider_id).await }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let aggregate_as_new_admin = get(&client, format!("{}/studies/{}/aggregate", &test_app.address, study_id), &outsider_token).await;
+    let studies = get(&client, format!("{}/organizations/{}/studies", &test_app.address, organization_id), &outsider_token).await
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let invalid_study = client
+        .post(format!("{}/organizations/{}/studies", &test_app.address, organization_id))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .json(&json!({
+            "name": "Backwards",
+            "data_types": ["heart_rate"],
+            "start_date": "2025-03-30",
+            "end_date": "2025-03-01"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, organization_by_non_admin.status().as_u16());
+    assert_eq!(403, study_by_outsider.status().as_u16());
+    assert_eq!(403, aggregate_by_outsider.status().as_u16());
+    assert_eq!(404, unknown_study.status().as_u16());
+    assert_eq!(200, add_admin.status().as_u16());
+    assert_eq!(200, aggregate_as_new_admin.status().as_u16());
+    assert_eq!(1, studies.len());
+    assert_eq!(400, invalid_study.status().as_u16());
+}